@@ -0,0 +1,60 @@
+// benches/backend_compare.rs
+// Same writes on SQLite and redb files, one commit per call.
+// redb syncs every commit, `SqliteStore` runs WAL with synchronous=NORMAL and only syncs on checkpoints.
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::backend::Backend;
+
+const BATCH: usize = 100;
+
+fn random_tx() -> Transaction {
+    Transaction {
+        id: format!("tx-{}", rand::random::<u64>()),
+        amount: 42.0,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+fn random_score() -> Score {
+    Score {
+        id: format!("tx-{}", rand::random::<u64>()),
+        score: rand::random::<f64>(),
+        is_fraud: false,
+    }
+}
+
+fn bench_backends(c: &mut Criterion) {
+    let dir = std::env::temp_dir();
+    let sqlite_path = dir.join("bench_backend.db");
+    let redb_path = dir.join("bench_backend.redb");
+    for path in [&sqlite_path, &redb_path] {
+        let _ = std::fs::remove_file(path);
+    }
+    let backends = [
+        Backend::Sqlite(sqlite_path.to_str().unwrap().to_string()),
+        Backend::Redb(redb_path.to_str().unwrap().to_string()),
+    ];
+
+    let mut group = c.benchmark_group("backend");
+    for backend in &backends {
+        let name = backend.name();
+        let repos = backend.open();
+        let (transactions, scores) = (&repos.transactions, &repos.scores);
+        group.bench_function(format!("{name}_transaction_save_idempotent"), |b| {
+            b.iter(|| transactions.save_idempotent(random_tx()));
+        });
+        group.bench_function(format!("{name}_score_save"), |b| {
+            b.iter(|| scores.save(random_score()));
+        });
+        group.bench_function(format!("{name}_score_save_batch_{BATCH}"), |b| {
+            b.iter_batched(|| (0..BATCH).map(|_| random_score()).collect(), |batch| scores.save_batch(batch), BatchSize::SmallInput);
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_backends);
+criterion_main!(benches);
@@ -16,6 +16,7 @@ fn bench_end_to_end(c: &mut Criterion) {
                 id: format!("tx-{}", rand::random::<u64>()),
                 amount: 100.0,
                 currency: "USD".to_string(),
+                ..Default::default()
             };
             process_transaction_bench(tx);
         });
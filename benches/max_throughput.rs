@@ -2,26 +2,12 @@ use criterion::{Criterion, criterion_group, criterion_main};
 use fraud_detection_3::domain::repository::{ScoreRepository, TransRepository};
 use fraud_detection_3::domain::scoring::Score;
 use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::in_memory::{InMemoryScoreRepo, InMemoryTransactionRepo};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::runtime::Runtime;
 use tokio::sync::{Mutex, mpsc}; // tokio Mutex is Send
 
-#[derive(Clone)]
-struct InMemRepo;
-impl TransRepository for InMemRepo {
-    fn save(&self, _tx: Transaction) {}
-    fn get(&self, _id: &str) -> Option<Transaction> {
-        None
-    }
-}
-impl ScoreRepository for InMemRepo {
-    fn save(&self, _result: Score) {}
-    fn get(&self, _id: &str) -> Option<Score> {
-        None
-    }
-}
-
 async fn start_worker(rx: Arc<Mutex<mpsc::Receiver<Transaction>>>, tx_repo: Arc<dyn TransRepository + Send + Sync>, score_repo: Arc<dyn ScoreRepository + Send + Sync>) {
     loop {
         let maybe_tx = {
@@ -53,12 +39,13 @@ fn bench_max_throughput(c: &mut Criterion) {
         b.to_async(&rt).iter(|| async {
             let (tx, rx) = mpsc::channel::<Transaction>(NUM_TX);
             let rx = Arc::new(Mutex::new(rx));
-            let repo = Arc::new(InMemRepo);
+            let tx_repo = Arc::new(InMemoryTransactionRepo::new());
+            let score_repo = Arc::new(InMemoryScoreRepo::new());
 
             for _ in 0..NUM_WORKERS {
                 let rx_clone = rx.clone();
-                let tx_repo = repo.clone();
-                let score_repo = repo.clone();
+                let tx_repo = tx_repo.clone();
+                let score_repo = score_repo.clone();
                 tokio::spawn(start_worker(rx_clone, tx_repo, score_repo));
             }
 
@@ -69,6 +56,7 @@ fn bench_max_throughput(c: &mut Criterion) {
                     id: format!("tx-{}", i),
                     amount: 100.0,
                     currency: "USD".to_string(),
+                    ..Default::default()
                 };
                 tx.send(tx_data).await.unwrap();
             }
@@ -3,7 +3,7 @@
 use criterion::{Criterion, criterion_group, criterion_main};
 use fraud_detection_3::domain::repository::TransRepository;
 use fraud_detection_3::domain::transaction::Transaction;
-use fraud_detection_3::persistence::sqlite::SQLiteTransRepo;
+use fraud_detection_3::persistence::sqlite::{SQLiteTransRepo, SqlitePool, SqliteViews};
 use std::sync::Arc;
 
 fn bench_sqlite_transaction_save(c: &mut Criterion) {
@@ -20,6 +20,7 @@ fn bench_sqlite_transaction_save(c: &mut Criterion) {
                 id: format!("tx-{}", rand::random::<u64>()),
                 amount: 42.0,
                 currency: "USD".to_string(),
+                ..Default::default()
             };
             repo.save(tx);
         });
@@ -29,5 +30,65 @@ fn bench_sqlite_transaction_save(c: &mut Criterion) {
     // let _ = fs::remove_file("bench_trans_save.db");
 }
 
-criterion_group!(benches, bench_sqlite_transaction_save);
+const READERS: usize = 4;
+const READS_PER_READER: usize = 200;
+const WRITES: usize = 200;
+
+// One writer thread saving while READERS threads read back known ids, the API-under-load case
+fn mixed_load(repo: &dyn TransRepository, known: &[String]) {
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            for _ in 0..WRITES {
+                repo.save(Transaction {
+                    id: format!("tx-{}", rand::random::<u64>()),
+                    amount: 42.0,
+                    currency: "USD".to_string(),
+                    ..Default::default()
+                });
+            }
+        });
+        for r in 0..READERS {
+            s.spawn(move || {
+                for i in 0..READS_PER_READER {
+                    let _ = repo.get(&known[(r + i) % known.len()]);
+                }
+            });
+        }
+    });
+}
+
+fn seed(repo: &dyn TransRepository) -> Vec<String> {
+    let ids: Vec<String> = (0..100).map(|i| format!("known-{i}")).collect();
+    for id in &ids {
+        repo.save(Transaction {
+            id: id.clone(),
+            amount: 1.0,
+            currency: "USD".to_string(),
+            ..Default::default()
+        });
+    }
+    ids
+}
+
+fn bench_sqlite_read_write(c: &mut Criterion) {
+    let dir = std::env::temp_dir();
+    let single_path = dir.join("bench_rw_single.db");
+    let pool_path = dir.join("bench_rw_pool.db");
+    for path in [&single_path, &pool_path] {
+        let _ = std::fs::remove_file(path);
+    }
+
+    // Every read waits for the single connection, including behind the writer
+    let single = SQLiteTransRepo::new(single_path.to_str().unwrap());
+    let known = seed(&single);
+    c.bench_function("sqlite_read_write_single_connection", |b| b.iter(|| mixed_load(&single, &known)));
+
+    // Readers use their own WAL connections and don't wait for the writer
+    let pool = SqlitePool::open(pool_path.to_str().unwrap());
+    let pooled = pool.transactions();
+    let known = seed(&pooled);
+    c.bench_function("sqlite_read_write_pool", |b| b.iter(|| mixed_load(&pooled, &known)));
+}
+
+criterion_group!(benches, bench_sqlite_transaction_save, bench_sqlite_read_write);
 criterion_main!(benches);
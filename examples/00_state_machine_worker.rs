@@ -10,6 +10,8 @@
 //      println!(“[SM] Transaction {}: Waiting for score... (but I'm continuing to process other transactions!)”, tx_id);
 //      This line is displayed before the blocking call (score_rx.await).
 //      In reality, the SM does not process anything while waiting, but other SMs (on other transactions) continue to progress.
+//
+// The library version (timeouts, fallback, cancellation) lives in src/state_machine/async_fsm.rs and src/workers/scoring_pool.rs
 
 use std::time::Duration;
 use tokio::sync::{mpsc, oneshot};
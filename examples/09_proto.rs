@@ -2,6 +2,7 @@
 // - Command Bus pattern
 // - Channel-driven Actor Model (Tokio + async fn)
 // - Mocked logic using println! for didactic purposes
+// The reusable library version of this pipeline lives in src/pipeline (used by src/main.rs)
 
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -13,6 +14,7 @@ struct Transaction {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)] // mocked results are never read back
 struct ScoringResult {
     id: usize,
     score: f64,
@@ -17,9 +17,11 @@
 use fraud_detection_3::command_bus::dispatch;
 use fraud_detection_3::commands::process_transaction::{ProcessTransaction, ProcessTransactionHandler};
 use fraud_detection_3::domain::fraud_scorer::{FraudScorer, RandomScorer};
+use fraud_detection_3::domain::scoring::Score;
 use fraud_detection_3::domain::transaction::Transaction;
 use fraud_detection_3::state_machine::event::Event;
-use fraud_detection_3::state_machine::state::{Enriched, /*Persisted,*/ State, Validated};
+use fraud_detection_3::state_machine::transitions::{StateMachine, TransitionContext, standard_table};
+use std::sync::Arc;
 
 // fn run_state_machine() {
 //     let mut state: Box<dyn State> = Box::new(Validated);
@@ -37,28 +39,22 @@ use fraud_detection_3::state_machine::state::{Enriched, /*Persisted,*/ State, Va
 //     }
 // }
 
+// The transitions now live in the declarative table of `state_machine::transitions`
 fn run_state_machine(tx: &Transaction, scorer: &dyn FraudScorer) {
-    let mut state: Box<dyn State> = Box::new(Validated);
-
-    loop {
-        // Store current state's name before moving it
-        let current_name = state.name();
-
-        state = match state.name() {
-            "Validated" => state.handle(Event::Process),
-            "Enriched" => {
-                // let enriched = state.downcast::<Enriched>().expect("Expected Enriched state");
-                let enriched = state.as_any().downcast::<Enriched>().expect("Expected Enriched state");
-                enriched.handle_with_scorer(tx, scorer)
-            }
-            _ => state.handle(Event::Process),
-        };
-
-        if current_name == state.name() {
-            println!("Final state: {}", state.name());
-            break;
-        }
-    }
+    let mut fsm = StateMachine::new(Arc::new(standard_table()));
+    let ctx = TransitionContext::new(tx.id.clone());
+
+    fsm.fire(Event::Process, &ctx).expect("Validated -> Enriched");
+    let is_fraud = scorer.is_fraud(tx);
+    let score = Score {
+        id: tx.id.clone(),
+        score: if is_fraud { 1.0 } else { 0.0 },
+        is_fraud,
+    };
+    let ctx = ctx.with_score(score);
+    fsm.fire(Event::Scored, &ctx).expect("Enriched -> Scored");
+    fsm.fire(Event::Persist, &ctx).expect("Scored -> Persisted | FlaggedAsFraud");
+    println!("Final state: {}", fsm.current());
 }
 
 // fn main() {
@@ -85,6 +81,7 @@ fn main() {
         id: "tx-001".into(),
         amount: 500.0,
         currency: "USD".into(),
+        ..Default::default()
     };
 
     let cmd = ProcessTransaction { transaction: tx.clone() };
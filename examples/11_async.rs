@@ -5,14 +5,20 @@ use fraud_detection_3::{
 };
 use tokio::sync::mpsc;
 
+use fraud_detection_3::persistence::in_memory::{InMemoryScoreRepo, InMemoryTransactionRepo};
+use std::sync::Arc;
+
 #[tokio::main]
 async fn main() {
     println!("Launching async worker demo...");
 
+    let tx_repo = Arc::new(InMemoryTransactionRepo::new());
+    let score_repo = Arc::new(InMemoryScoreRepo::new());
+
     let (tx, rx) = mpsc::channel(10);
 
     // Launch worker
-    tokio::spawn(dispatcher::start_worker(rx));
+    tokio::spawn(dispatcher::start_worker(rx, tx_repo, score_repo));
 
     // Simulate sending transactions
     for i in 1..=5 {
@@ -20,6 +26,7 @@ async fn main() {
             id: format!("tx-{i:03}"),
             amount: 100.0 * i as f64,
             currency: "USD".to_string(),
+            ..Default::default()
         };
         tx.send(WorkerMessage::Transaction(tx_data)).await.unwrap();
     }
@@ -6,9 +6,13 @@ use fraud_detection_3::{
 
 use tokio::sync::mpsc;
 
+use fraud_detection_3::persistence::in_memory::{InMemoryScoreRepo, InMemoryTransactionRepo};
+use std::sync::Arc;
+
 use tracing::{info, warn};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 // use tracing_subscriber::fmt::format::FmtSpan;
+use fraud_detection_3::security::RedactingFields;
 use tracing_subscriber::{Registry, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
 fn init_logging() -> tracing_appender::non_blocking::WorkerGuard {
@@ -18,11 +22,11 @@ fn init_logging() -> tracing_appender::non_blocking::WorkerGuard {
     // Optional: make it non-blocking
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
 
-    let file_layer = fmt::layer()
+    let file_layer = fmt::layer().fmt_fields(RedactingFields::default())
         .with_ansi(false) // no ANSI color codes in log file
         .with_writer(non_blocking);
 
-    let stdout_layer = fmt::layer().with_writer(std::io::stdout).with_ansi(true); // keep ANSI for console
+    let stdout_layer = fmt::layer().fmt_fields(RedactingFields::default()).with_writer(std::io::stdout).with_ansi(true); // keep ANSI for console
 
     // Combine layers with explicit subscriber registry
     Registry::default().with(stdout_layer).with(file_layer).init();
@@ -45,10 +49,13 @@ async fn main() {
     info!("Launching async worker demo...");
     warn!("This is a warning");
 
+    let tx_repo = Arc::new(InMemoryTransactionRepo::new());
+    let score_repo = Arc::new(InMemoryScoreRepo::new());
+
     let (tx, rx) = mpsc::channel(10);
 
     // Launch worker
-    tokio::spawn(dispatcher::start_worker(rx));
+    tokio::spawn(dispatcher::start_worker(rx, tx_repo, score_repo));
 
     // Simulate sending transactions
     for i in 1..=5 {
@@ -56,6 +63,7 @@ async fn main() {
             id: format!("tx-{i:03}"),
             amount: 100.0 * i as f64,
             currency: "USD".to_string(),
+            ..Default::default()
         };
         info!(tx_id = %tx_data.id, amount = tx_data.amount, "Processing transaction");
         tx.send(WorkerMessage::Transaction(tx_data)).await.unwrap();
@@ -12,6 +12,7 @@ fn main() {
         id: "tx-001".to_string(),
         amount: 123.45,
         currency: "EUR".to_string(),
+        ..Default::default()
     };
 
     repo.save(tx.clone());
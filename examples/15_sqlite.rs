@@ -9,11 +9,12 @@ use tokio::sync::mpsc;
 // For tracing
 use tracing::{info, warn};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use fraud_detection_3::security::RedactingFields;
 use tracing_subscriber::{Layer, Registry, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
 // For persistence
 // use fraud_detection_3::persistence::in_memory::InMemoryTransactionRepo;
-use fraud_detection_3::persistence::sqlite::SQLiteTransRepo;
+use fraud_detection_3::persistence::sqlite::{SqliteStore, SqliteViews};
 
 fn init_logging() -> tracing_appender::non_blocking::WorkerGuard {
     // Create a daily rotating file appender in ./logs/
@@ -22,12 +23,12 @@ fn init_logging() -> tracing_appender::non_blocking::WorkerGuard {
     // Optional: make it non-blocking
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
 
-    let file_layer = fmt::layer()
+    let file_layer = fmt::layer().fmt_fields(RedactingFields::default())
         .with_ansi(false) // no ANSI color codes in log file
         .with_writer(non_blocking)
         .with_filter(tracing::level_filters::LevelFilter::DEBUG); // File: keep all logs (incl. debug)
 
-    let stdout_layer = fmt::layer()
+    let stdout_layer = fmt::layer().fmt_fields(RedactingFields::default())
         .with_writer(std::io::stdout)
         .with_ansi(true) // keep ANSI for console
         .with_filter(tracing::level_filters::LevelFilter::INFO); // Console: info and above
@@ -86,20 +87,24 @@ async fn main() {
     warn!("This is a warning");
 
     // let repo = Arc::new(InMemoryTransactionRepo::new());
-    let repo = Arc::new(SQLiteTransRepo::new("data.db"));
+    // One connection for both repositories
+    let store = SqliteStore::open("data.db");
+    let repo = Arc::new(store.transactions());
+    let score_repo = Arc::new(store.scores());
 
     let (tx, rx) = mpsc::channel(10);
 
     // In order to make sure logs are also written to file
     // Keeps logs alive as long as the worker is running.
     // Ensures that all logs are flushed properly to the file.
-    let worker_handle = tokio::spawn(dispatcher::start_worker(rx, repo.clone()));
+    let worker_handle = tokio::spawn(dispatcher::start_worker(rx, repo.clone(), score_repo.clone()));
 
     for i in 1..=5 {
         let tx_data = Transaction {
             id: format!("tx-{i:03}"),
             amount: 100.0 * i as f64,
             currency: "USD".to_string(),
+            ..Default::default()
         };
         tx.send(WorkerMessage::Transaction(tx_data)).await.unwrap();
     }
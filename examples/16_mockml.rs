@@ -10,6 +10,7 @@ use tokio::sync::mpsc;
 // For tracing
 use tracing::{info, warn};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use fraud_detection_3::security::RedactingFields;
 use tracing_subscriber::{Layer, Registry, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
 // For persistence
@@ -23,12 +24,12 @@ fn init_logging() -> tracing_appender::non_blocking::WorkerGuard {
     // Optional: make it non-blocking
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
 
-    let file_layer = fmt::layer()
+    let file_layer = fmt::layer().fmt_fields(RedactingFields::default())
         .with_ansi(false) // no ANSI color codes in log file
         .with_writer(non_blocking)
         .with_filter(tracing::level_filters::LevelFilter::DEBUG); // File: keep all logs (incl. debug)
 
-    let stdout_layer = fmt::layer()
+    let stdout_layer = fmt::layer().fmt_fields(RedactingFields::default())
         .with_writer(std::io::stdout)
         .with_ansi(true) // keep ANSI for console
         .with_filter(tracing::level_filters::LevelFilter::INFO); // Console: info and above
@@ -101,6 +102,7 @@ async fn main() {
             id: format!("tx-{i:03}"),
             amount: 100.0 * i as f64,
             currency: "USD".to_string(),
+            ..Default::default()
         };
         tx.send(WorkerMessage::Transaction(tx_data)).await.unwrap();
     }
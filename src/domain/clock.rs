@@ -0,0 +1,8 @@
+// src/domain/clock.rs
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current wall-clock time as milliseconds since the Unix epoch
+pub fn now_millis() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
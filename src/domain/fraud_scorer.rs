@@ -1,11 +1,28 @@
 // src/domain/fraud_scorer.rs
 
+use crate::domain::scoring::Score;
 use crate::domain::transaction::Transaction;
 use rand::Rng;
+use tracing::debug;
 
 // Trait that defines fraud detection behavior
 pub trait FraudScorer {
     fn is_fraud(&self, tx: &Transaction) -> bool;
+
+    /// Scores a whole batch in one call, results are in the same order as `txs`.
+    /// Override it when the model is vectorized; the default calls `is_fraud` per transaction.
+    fn score_batch(&self, txs: &[Transaction]) -> Vec<Score> {
+        txs.iter()
+            .map(|tx| {
+                let is_fraud = self.is_fraud(tx);
+                Score {
+                    id: tx.id.clone(),
+                    score: if is_fraud { 1.0 } else { 0.0 },
+                    is_fraud,
+                }
+            })
+            .collect()
+    }
 }
 
 // Random scoring implementation
@@ -36,7 +53,7 @@ pub struct MlModelScorer;
 
 impl FraudScorer for MlModelScorer {
     fn is_fraud(&self, tx: &Transaction) -> bool {
-        println!("Calling ML model for transaction {}", tx.id);
+        debug!(tx_id = %tx.id, "Calling ML model");
         false // Stubbed for now
     }
 }
@@ -0,0 +1,69 @@
+// src/domain/label.rs
+
+use crate::domain::scoring::Score;
+use crate::state_machine::event::Event;
+use serde::{Deserialize, Serialize};
+
+/// Ground truth learned after settlement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LabelOutcome {
+    /// A chargeback: the transaction was fraudulent
+    Fraud,
+    /// A refund: the transaction was legitimate
+    Legit,
+    /// A customer dispute: not settled yet
+    Disputed,
+}
+
+impl LabelOutcome {
+    /// The outcome a post-settlement event stands for, `None` for pipeline events
+    pub fn from_event(event: Event) -> Option<Self> {
+        match event {
+            Event::Chargeback => Some(LabelOutcome::Fraud),
+            Event::Refund => Some(LabelOutcome::Legit),
+            Event::CustomerDisputed => Some(LabelOutcome::Disputed),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LabelOutcome::Fraud => "Fraud",
+            LabelOutcome::Legit => "Legit",
+            LabelOutcome::Disputed => "Disputed",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [LabelOutcome::Fraud, LabelOutcome::Legit, LabelOutcome::Disputed]
+            .into_iter()
+            .find(|o| o.name() == name)
+    }
+}
+
+/// A labeled outcome linked to the score the pipeline gave at the time,
+/// for model training and rule evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub tx_id: String,
+    pub outcome: LabelOutcome,
+    /// The post-settlement event that produced the label
+    pub source: Event,
+    /// Original score, `None` if it was never persisted
+    pub score: Option<Score>,
+    /// ms since epoch
+    pub labeled_at: i64,
+    pub notes: Option<String>,
+}
+
+impl Label {
+    /// Whether the original decision agreed with the outcome, `None` while disputed or unscored
+    pub fn score_was_right(&self) -> Option<bool> {
+        let score = self.score.as_ref()?;
+        match self.outcome {
+            LabelOutcome::Fraud => Some(score.is_fraud),
+            LabelOutcome::Legit => Some(!score.is_fraud),
+            LabelOutcome::Disputed => None,
+        }
+    }
+}
@@ -1,4 +1,9 @@
+pub mod clock;
 pub mod fraud_scorer;
+pub mod label;
+pub mod query;
 pub mod repository;
+pub mod retention;
 pub mod scoring;
 pub mod transaction;
+pub mod tx_event;
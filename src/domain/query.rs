@@ -0,0 +1,192 @@
+// src/domain/query.rs
+
+// Filters and keyset pagination for the repositories' `query`/`count` methods.
+//
+// Pages are ordered on a unique key (sort value, id), and a `Cursor` remembers the last key returned:
+// rows inserted while a client is paging never shift or duplicate the following pages.
+
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use std::fmt;
+use std::str::FromStr;
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Opaque position in a result set, returned as `Page::next` and passed back with `after`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor(String);
+
+impl Cursor {
+    pub(crate) fn new(key: impl fmt::Display, id: &str) -> Self {
+        Cursor(format!("{key}|{id}"))
+    }
+
+    /// Parses a token previously obtained with `as_str`, e.g. from a dashboard URL
+    pub fn from_token(token: &str) -> Option<Self> {
+        token.split_once('|').filter(|(key, _)| !key.is_empty()).map(|_| Cursor(token.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// `None` when the cursor came from a query with another sort key
+    pub(crate) fn decode<K: FromStr>(&self) -> Option<(K, &str)> {
+        let (key, id) = self.0.split_once('|')?;
+        Some((key.parse().ok()?, id))
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Set when more items may follow
+    pub next: Option<Cursor>,
+}
+
+impl<T> Default for Page<T> {
+    fn default() -> Self {
+        Self { items: Vec::new(), next: None }
+    }
+}
+
+impl<T> Page<T> {
+    /// Builds a page from up to `limit + 1` sorted items, the extra one only tells that more follow
+    pub(crate) fn from_overfetch(mut items: Vec<T>, limit: usize, cursor_of: impl Fn(&T) -> Cursor) -> Self {
+        let next = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(cursor_of)
+        } else {
+            None
+        };
+        Self { items, next }
+    }
+}
+
+fn page_size(limit: usize) -> usize {
+    if limit == 0 { DEFAULT_PAGE_SIZE } else { limit }
+}
+
+/// Transactions ordered by reception time, oldest first.
+/// A cursor from another kind of query yields an empty page.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionQuery {
+    /// Inclusive, ms since epoch
+    pub received_from: Option<i64>,
+    /// Exclusive, ms since epoch
+    pub received_to: Option<i64>,
+    pub currency: Option<String>,
+    pub merchant_id: Option<String>,
+    pub account_id: Option<String>,
+    pub after: Option<Cursor>,
+    /// 0 means `DEFAULT_PAGE_SIZE`
+    pub limit: usize,
+}
+
+impl TransactionQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn received_between(mut self, from: i64, to: i64) -> Self {
+        self.received_from = Some(from);
+        self.received_to = Some(to);
+        self
+    }
+
+    pub fn currency(mut self, currency: impl Into<String>) -> Self {
+        self.currency = Some(currency.into());
+        self
+    }
+
+    pub fn merchant(mut self, merchant_id: impl Into<String>) -> Self {
+        self.merchant_id = Some(merchant_id.into());
+        self
+    }
+
+    pub fn account(mut self, account_id: impl Into<String>) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn page_size(&self) -> usize {
+        page_size(self.limit)
+    }
+
+    /// Whether a transaction received at `received_at` passes the filters (the cursor is not checked)
+    pub fn matches(&self, tx: &Transaction, received_at: i64) -> bool {
+        self.received_from.is_none_or(|from| received_at >= from)
+            && self.received_to.is_none_or(|to| received_at < to)
+            && self.currency.as_ref().is_none_or(|c| *c == tx.currency)
+            && self.merchant_id.as_ref().is_none_or(|m| *m == tx.merchant_id)
+            && self.account_id.as_ref().is_none_or(|a| *a == tx.account_id)
+    }
+}
+
+/// Scores ordered from the most to the least suspicious, the review queue order.
+/// A cursor from another kind of query yields an empty page.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoreQuery {
+    pub is_fraud: Option<bool>,
+    /// Inclusive
+    pub min_score: Option<f64>,
+    /// Inclusive
+    pub max_score: Option<f64>,
+    pub after: Option<Cursor>,
+    /// 0 means `DEFAULT_PAGE_SIZE`
+    pub limit: usize,
+}
+
+impl ScoreQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fraud(mut self, is_fraud: bool) -> Self {
+        self.is_fraud = Some(is_fraud);
+        self
+    }
+
+    pub fn score_between(mut self, min: f64, max: f64) -> Self {
+        self.min_score = Some(min);
+        self.max_score = Some(max);
+        self
+    }
+
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn page_size(&self) -> usize {
+        page_size(self.limit)
+    }
+
+    /// Whether `score` passes the filters (the cursor is not checked)
+    pub fn matches(&self, score: &Score) -> bool {
+        self.is_fraud.is_none_or(|f| f == score.is_fraud)
+            && self.min_score.is_none_or(|min| score.score >= min)
+            && self.max_score.is_none_or(|max| score.score <= max)
+    }
+}
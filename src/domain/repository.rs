@@ -1,10 +1,54 @@
 // src/domain/repository.rs
 
+use std::fmt;
+use std::time::Duration;
+
+use crate::domain::query::{Page, ScoreQuery, TransactionQuery};
 use crate::domain::transaction::Transaction;
 
+/// How long a transaction id is remembered for deduplication by default
+pub const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Outcome of `TransRepository::save_idempotent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveOutcome {
+    /// The id was unknown (or last seen outside the dedup window) and the transaction was stored
+    Inserted,
+    /// The same id with the same payload was already stored within the dedup window
+    Duplicate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepoError {
+    /// The id was already stored within the dedup window with a different payload
+    Conflict { id: String },
+}
+
+impl fmt::Display for RepoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepoError::Conflict { id } => write!(f, "transaction {id} already exists with a different payload"),
+        }
+    }
+}
+
+impl std::error::Error for RepoError {}
+
 pub trait TransRepository: Send + Sync {
     fn save(&self, tx: Transaction);
     fn get(&self, id: &str) -> Option<Transaction>;
+    /// Stores `tx` unless its id was already seen within the repository's dedup window.
+    /// Never overwrites the original: a retry is reported as `Duplicate`, a different payload as `Conflict`.
+    fn save_idempotent(&self, tx: Transaction) -> Result<SaveOutcome, RepoError>;
+    /// One page of the transactions matching `query`, see `TransactionQuery` for the order
+    fn query(&self, query: &TransactionQuery) -> Page<Transaction>;
+    /// Number of transactions matching the filters of `query`, ignoring its cursor and limit
+    fn count(&self, query: &TransactionQuery) -> usize;
+
+    /// Batch form of `save_idempotent`, one result per transaction in input order
+    fn save_idempotent_batch(&self, txs: Vec<Transaction>) -> Vec<Result<SaveOutcome, RepoError>> {
+        txs.into_iter().map(|tx| self.save_idempotent(tx)).collect()
+    }
 }
 
 use crate::domain::scoring::Score;
@@ -12,4 +56,134 @@ use crate::domain::scoring::Score;
 pub trait ScoreRepository: Send + Sync {
     fn save(&self, result: Score);
     fn get(&self, tx_id: &str) -> Option<Score>;
+    /// One page of the scores matching `query`, see `ScoreQuery` for the order
+    fn query(&self, query: &ScoreQuery) -> Page<Score>;
+    /// Number of scores matching the filters of `query`, ignoring its cursor and limit
+    fn count(&self, query: &ScoreQuery) -> usize;
+
+    fn save_batch(&self, results: Vec<Score>) {
+        for result in results {
+            self.save(result);
+        }
+    }
+}
+
+use crate::state_machine::event::Event;
+use crate::state_machine::timers::Timer;
+use crate::state_machine::transitions::{StateId, TransitionRecord};
+
+/// Current state of each transaction and the transitions that led there
+pub trait StateRepository: Send + Sync {
+    /// Sets the current state without recording a transition (initial state, imports)
+    fn save_state(&self, tx_id: &str, state: StateId);
+    fn get_state(&self, tx_id: &str) -> Option<StateId>;
+    /// Appends `record` to the history and moves the current state to `record.to`, atomically
+    fn record_transition(&self, record: TransitionRecord);
+    /// Compare-and-set form of `record_transition`: writes only if the current state is still `expected`
+    /// (`None`: nothing stored yet). Returns false, writing nothing, when another writer moved it first.
+    fn record_transition_if(&self, expected: Option<StateId>, record: TransitionRecord) -> bool {
+        self.record_transitions_if(expected, std::slice::from_ref(&record))
+    }
+    /// Appends `records` in order and moves the current state to the last `to`, all or nothing, under the
+    /// same check as `record_transition_if`
+    fn record_transitions_if(&self, expected: Option<StateId>, records: &[TransitionRecord]) -> bool;
+    /// Oldest first
+    fn history(&self, tx_id: &str) -> Vec<TransitionRecord>;
+    /// Ids of the transactions currently in `state`, to resume pending work after a restart
+    fn ids_in_state(&self, state: StateId) -> Vec<String>;
+}
+
+/// Durable scheduled events
+pub trait TimerStore: Send + Sync {
+    /// Returns the id of the new timer
+    fn schedule(&self, tx_id: &str, event: Event, due_at: i64) -> i64;
+    /// Removes every pending timer of `tx_id`
+    fn cancel_all(&self, tx_id: &str);
+    fn remove(&self, timer_id: i64);
+    /// Timers due at `now` (ms since epoch), earliest first, at most `limit`
+    fn due(&self, now: i64, limit: usize) -> Vec<Timer>;
+    fn pending(&self, tx_id: &str) -> Vec<Timer>;
+}
+
+use crate::domain::label::Label;
+
+/// Post-settlement labels, append only
+pub trait LabelRepository: Send + Sync {
+    fn save(&self, label: Label);
+    /// Oldest first
+    fn for_tx(&self, tx_id: &str) -> Vec<Label>;
+    /// Labels recorded at or after `since` (ms since epoch), oldest first, at most `limit`
+    fn since(&self, since: i64, limit: usize) -> Vec<Label>;
+}
+
+/// Writes a post-settlement transition and its label together: both are stored or neither is
+pub trait FeedbackStore: Send + Sync {
+    /// Writes only if the stored state is still `record.from`, returns false otherwise
+    fn record_feedback(&self, record: TransitionRecord, label: Label) -> bool;
+}
+
+use crate::domain::tx_event::{RecordedEvent, Snapshot, TxEvent};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppendError {
+    /// Another writer appended to the stream since it was read
+    VersionConflict { stream_id: String, expected: u64, actual: u64 },
+}
+
+impl fmt::Display for AppendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppendError::VersionConflict { stream_id, expected, actual } => {
+                write!(f, "stream {stream_id} is at version {actual}, expected {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppendError {}
+
+/// Append-only event streams, one per transaction id
+pub trait EventStore: Send + Sync {
+    /// Appends `events` if the stream is still at `expected_version` (0 for a new stream),
+    /// returns the new version. All or nothing.
+    fn append(&self, stream_id: &str, expected_version: u64, events: Vec<TxEvent>) -> Result<u64, AppendError>;
+    /// Events of `stream_id` with a version greater than `after_version`, oldest first
+    fn read_stream(&self, stream_id: &str, after_version: u64) -> Vec<RecordedEvent>;
+    /// Events of every stream with a `seq` greater than `after_seq`, oldest first, at most `limit`
+    fn read_all(&self, after_seq: i64, limit: usize) -> Vec<RecordedEvent>;
+    /// Keeps only the snapshot with the highest version
+    fn save_snapshot(&self, snapshot: Snapshot);
+    fn load_snapshot(&self, stream_id: &str) -> Option<Snapshot>;
+}
+
+use crate::outbox::OutboxMessage;
+
+/// Messages written together with the data they describe, published later by `outbox::OutboxRelay`
+pub trait Outbox: Send + Sync {
+    /// Undelivered messages, oldest first, at most `limit`
+    fn pending(&self, limit: usize) -> Vec<OutboxMessage>;
+    fn mark_delivered(&self, id: i64);
+    /// Keeps the message pending and remembers why the attempt failed
+    fn record_failure(&self, id: i64, error: &str);
+}
+
+use crate::domain::retention::{ExpiredRow, RetentionTable};
+
+/// Read and delete expired rows, see `workers::purge::PurgeJob`
+pub trait RetentionStore: Send + Sync {
+    /// Rows of `table` stored before `before` (ms since epoch), oldest first, at most `limit`, as stored
+    /// (encrypted columns stay encrypted). Rows of transactions whose lifecycle is not over are never
+    /// returned, nor with `keep_fraud` those of transactions scored as fraud.
+    fn expired(&self, table: RetentionTable, before: i64, keep_fraud: bool, limit: usize) -> Vec<ExpiredRow>;
+    /// Deletes the rows of `table` with these ids, and what only served a deleted transaction (timers,
+    /// delivered alerts), in one transaction. Returns how many rows of `table` were deleted.
+    fn delete(&self, table: RetentionTable, ids: &[String]) -> usize;
+}
+
+use crate::export::ExportRow;
+
+/// Transactions joined with their score, see `export::export`
+pub trait ExportSource: Send + Sync {
+    /// Same filters, order and cursors as `TransRepository::query`
+    fn export_page(&self, query: &TransactionQuery) -> Page<ExportRow>;
 }
@@ -0,0 +1,79 @@
+// src/domain/retention.rs
+
+// How long stored rows are kept. Purging is done by `workers::purge::PurgeJob` on a `RetentionStore`.
+
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Tables a retention policy applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetentionTable {
+    Transactions,
+    Scores,
+}
+
+impl RetentionTable {
+    pub const ALL: [RetentionTable; 2] = [RetentionTable::Transactions, RetentionTable::Scores];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RetentionTable::Transactions => "transactions",
+            RetentionTable::Scores => "scores",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionPolicy {
+    /// Age after which a raw transaction is purged, `None` keeps them forever
+    pub transactions: Option<Duration>,
+    pub scores: Option<Duration>,
+    /// Transactions scored as fraud and their scores are never purged
+    pub keep_fraud: bool,
+}
+
+impl Default for RetentionPolicy {
+    /// Raw transactions 90 days, scores 2 years, fraud forever
+    fn default() -> Self {
+        Self {
+            transactions: Some(DAY * 90),
+            scores: Some(DAY * 730),
+            keep_fraud: true,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn max_age(&self, table: RetentionTable) -> Option<Duration> {
+        match table {
+            RetentionTable::Transactions => self.transactions,
+            RetentionTable::Scores => self.scores,
+        }
+    }
+
+    /// Rows of `table` stored before this time (ms since epoch) are expired at `now`
+    pub fn cutoff(&self, table: RetentionTable, now: i64) -> Option<i64> {
+        self.max_age(table).map(|age| now - age.as_millis() as i64)
+    }
+}
+
+/// A row about to be purged, as written to the archive (one JSON object per line)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "table", rename_all = "snake_case")]
+pub enum ExpiredRow {
+    Transaction { transaction: Transaction, received_at: i64 },
+    Score { score: Score, scored_at: i64 },
+}
+
+impl ExpiredRow {
+    pub fn id(&self) -> &str {
+        match self {
+            ExpiredRow::Transaction { transaction, .. } => &transaction.id,
+            ExpiredRow::Score { score, .. } => &score.id,
+        }
+    }
+}
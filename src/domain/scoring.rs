@@ -1,6 +1,8 @@
 // src/domain/scoring.rs
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Score {
     pub id: String,
     pub score: f64,
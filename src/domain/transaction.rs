@@ -1,8 +1,63 @@
 // src/domain/transaction.rs
 
-#[derive(Debug, Clone)]
+use crate::security::redact::mask_tail;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct Transaction {
     pub id: String,
     pub amount: f64,
     pub currency: String,
+    pub merchant_id: String,
+    pub account_id: String,
+}
+
+// Account ids can be card numbers or emails: `?tx` in a log line prints their last 4 characters only
+impl fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("id", &self.id)
+            .field("amount", &self.amount)
+            .field("currency", &self.currency)
+            .field("merchant_id", &self.merchant_id)
+            .field("account_id", &mask_tail(&self.account_id))
+            .finish()
+    }
+}
+
+/// `tx-1 (12.50 EUR)`, nothing personal
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.2} {})", self.id, self.amount, self.currency)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTransaction {
+    pub reason: &'static str,
+}
+
+impl fmt::Display for InvalidTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid transaction: {}", self.reason)
+    }
+}
+
+impl std::error::Error for InvalidTransaction {}
+
+impl Transaction {
+    /// Format checks done before anything is written
+    pub fn validate(&self) -> Result<(), InvalidTransaction> {
+        let reason = if self.id.is_empty() {
+            "empty id"
+        } else if !self.amount.is_finite() || self.amount <= 0.0 {
+            "amount must be positive"
+        } else if self.currency.is_empty() {
+            "empty currency"
+        } else {
+            return Ok(());
+        };
+        Err(InvalidTransaction { reason })
+    }
 }
@@ -0,0 +1,176 @@
+// src/domain/tx_event.rs
+
+// Event-sourced view of a transaction. Each transaction id is a stream of `TxEvent`s, appended with an
+// expected version (optimistic concurrency) and never updated. Read models (`TxView`, and the regular
+// `Transaction`/`Score` repositories) are rebuilt by replaying the events, starting from the latest
+// snapshot when there is one.
+
+use crate::domain::label::LabelOutcome;
+use crate::domain::repository::{AppendError, EventStore, ScoreRepository, TransRepository};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+
+/// Replaying more events than this on load takes a new snapshot
+pub const DEFAULT_SNAPSHOT_EVERY: u64 = 100;
+
+/// Attempts of `append_latest` before it gives up on a busy stream
+const APPEND_ATTEMPTS: usize = 5;
+
+/// Facts about one transaction, with the inputs of every decision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TxEvent {
+    TransactionReceived { transaction: Transaction },
+    Validated,
+    Rejected { reason: String },
+    Scored { score: Score, model: String },
+    Flagged { reason: String },
+    Reviewed { analyst_id: String, confirmed_fraud: bool, notes: Option<String> },
+    DisputeOpened { notes: Option<String> },
+    ChargebackReceived { notes: Option<String> },
+    RefundReceived { notes: Option<String> },
+}
+
+impl TxEvent {
+    /// Stable name, stored next to the payload for querying
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TxEvent::TransactionReceived { .. } => "TransactionReceived",
+            TxEvent::Validated => "Validated",
+            TxEvent::Rejected { .. } => "Rejected",
+            TxEvent::Scored { .. } => "Scored",
+            TxEvent::Flagged { .. } => "Flagged",
+            TxEvent::Reviewed { .. } => "Reviewed",
+            TxEvent::DisputeOpened { .. } => "DisputeOpened",
+            TxEvent::ChargebackReceived { .. } => "ChargebackReceived",
+            TxEvent::RefundReceived { .. } => "RefundReceived",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    /// Position in the whole store, for projections that follow every stream
+    pub seq: i64,
+    pub stream_id: String,
+    /// Position in the stream, starting at 1
+    pub version: u64,
+    pub event: TxEvent,
+    /// ms since epoch
+    pub recorded_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Review {
+    pub analyst_id: String,
+    pub confirmed_fraud: bool,
+    pub notes: Option<String>,
+}
+
+/// Read model of one stream
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TxView {
+    /// Version of the last event applied, 0 for an empty stream
+    pub version: u64,
+    pub transaction: Option<Transaction>,
+    pub validated: bool,
+    pub rejected: Option<String>,
+    pub score: Option<Score>,
+    pub flagged: Option<String>,
+    pub review: Option<Review>,
+    /// Latest post-settlement outcome
+    pub outcome: Option<LabelOutcome>,
+}
+
+impl TxView {
+    pub fn apply(&mut self, event: &TxEvent) {
+        self.version += 1;
+        match event {
+            TxEvent::TransactionReceived { transaction } => self.transaction = Some(transaction.clone()),
+            TxEvent::Validated => self.validated = true,
+            TxEvent::Rejected { reason } => self.rejected = Some(reason.clone()),
+            TxEvent::Scored { score, .. } => self.score = Some(score.clone()),
+            TxEvent::Flagged { reason } => self.flagged = Some(reason.clone()),
+            TxEvent::Reviewed { analyst_id, confirmed_fraud, notes } => {
+                self.review = Some(Review {
+                    analyst_id: analyst_id.clone(),
+                    confirmed_fraud: *confirmed_fraud,
+                    notes: notes.clone(),
+                })
+            }
+            TxEvent::DisputeOpened { .. } => self.outcome = Some(LabelOutcome::Disputed),
+            TxEvent::ChargebackReceived { .. } => self.outcome = Some(LabelOutcome::Fraud),
+            TxEvent::RefundReceived { .. } => self.outcome = Some(LabelOutcome::Legit),
+        }
+    }
+
+    pub fn replay<'a>(events: impl IntoIterator<Item = &'a TxEvent>) -> Self {
+        let mut view = Self::default();
+        for event in events {
+            view.apply(event);
+        }
+        view
+    }
+}
+
+/// A `TxView` saved at `view.version`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub stream_id: String,
+    pub view: TxView,
+}
+
+/// Rebuilds the view of `stream_id` from its latest snapshot and the events recorded after it.
+/// Takes a new snapshot when more than `snapshot_every` events had to be replayed.
+/// `None` for a stream without events.
+pub fn load_view(store: &dyn EventStore, stream_id: &str, snapshot_every: u64) -> Option<TxView> {
+    let mut view = store.load_snapshot(stream_id).map(|s| s.view).unwrap_or_default();
+    let tail = store.read_stream(stream_id, view.version);
+    for recorded in &tail {
+        view.apply(&recorded.event);
+    }
+    if tail.len() as u64 > snapshot_every {
+        store.save_snapshot(Snapshot {
+            stream_id: stream_id.to_string(),
+            view: view.clone(),
+        });
+    }
+    (view.version > 0).then_some(view)
+}
+
+/// Replays every stream into the `Transaction` and `Score` repositories, returns the number of events read
+pub fn rebuild_read_models(store: &dyn EventStore, tx_repo: &dyn TransRepository, score_repo: &dyn ScoreRepository) -> usize {
+    let mut after = 0;
+    let mut read = 0;
+    loop {
+        let page = store.read_all(after, 500);
+        let Some(last) = page.last() else {
+            break;
+        };
+        after = last.seq;
+        read += page.len();
+        for recorded in page {
+            match recorded.event {
+                TxEvent::TransactionReceived { transaction } => tx_repo.save(transaction),
+                TxEvent::Scored { score, .. } => score_repo.save(score),
+                _ => {}
+            }
+        }
+    }
+    read
+}
+
+/// Appends `events` at the end of `stream_id` whatever its version, for writers recording a decision
+/// made elsewhere (the pipeline, a stored transition) rather than one taken on a read of the stream.
+/// Concurrent appends are retried at the version they reached.
+pub fn append_latest(store: &dyn EventStore, stream_id: &str, events: Vec<TxEvent>) -> Result<u64, AppendError> {
+    let mut expected = 0;
+    for _ in 1..APPEND_ATTEMPTS {
+        match store.append(stream_id, expected, events.clone()) {
+            Err(AppendError::VersionConflict { actual, .. }) => expected = actual,
+            done => return done,
+        }
+    }
+    store.append(stream_id, expected, events)
+}
@@ -0,0 +1,137 @@
+// src/export/mod.rs
+
+// Export of transactions joined with their score, for model training and audits. Rows are read page by
+// page from an `ExportSource` and streamed to the writer, so an export never holds the whole range in memory.
+//
+// The columns of `COLUMNS` are the contract with the consumers: add new ones at the end, never rename or
+// reorder them.
+
+mod writers;
+
+use crate::domain::query::TransactionQuery;
+use crate::domain::repository::ExportSource;
+use crate::security::redact::mask_tail;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, Write};
+use tracing::debug;
+
+/// Column names, in file order
+pub const COLUMNS: [&str; 9] = [
+    "tx_id",
+    "received_at",
+    "amount",
+    "currency",
+    "merchant_id",
+    "account_id",
+    "score",
+    "is_fraud",
+    "scored_at",
+];
+
+/// Rows read per page when the query has no limit
+pub const EXPORT_PAGE_SIZE: usize = 1000;
+
+/// One transaction and its score, if it was scored. Times are ms since epoch.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportRow {
+    pub tx_id: String,
+    pub received_at: i64,
+    pub amount: f64,
+    pub currency: String,
+    pub merchant_id: String,
+    pub account_id: String,
+    pub score: Option<f64>,
+    pub is_fraud: Option<bool>,
+    pub scored_at: Option<i64>,
+}
+
+// Same masking as `Transaction`'s
+impl fmt::Debug for ExportRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExportRow")
+            .field("tx_id", &self.tx_id)
+            .field("received_at", &self.received_at)
+            .field("amount", &self.amount)
+            .field("currency", &self.currency)
+            .field("merchant_id", &self.merchant_id)
+            .field("account_id", &mask_tail(&self.account_id))
+            .field("score", &self.score)
+            .field("is_fraud", &self.is_fraud)
+            .field("scored_at", &self.scored_at)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    /// Needs the `parquet` feature
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [ExportFormat::Csv, ExportFormat::Jsonl, ExportFormat::Parquet]
+            .into_iter()
+            .find(|f| f.name() == name)
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    /// The format needs a cargo feature this build doesn't have
+    Unsupported(ExportFormat),
+    Encoding(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "export failed: {e}"),
+            ExportError::Unsupported(format) => write!(f, "{} export is not enabled in this build", format.name()),
+            ExportError::Encoding(e) => write!(f, "export encoding failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+/// Writes every row matching `query` (ordered by reception time) to `out`, returns the number of rows.
+/// `query.after` and `query.limit` set where the export starts and the page size.
+pub fn export<W: Write + Send>(source: &dyn ExportSource, query: &TransactionQuery, format: ExportFormat, out: W) -> Result<usize, ExportError> {
+    let mut writer = writers::for_format(format, out)?;
+    let mut query = query.clone();
+    if query.limit == 0 {
+        query.limit = EXPORT_PAGE_SIZE;
+    }
+    let mut exported = 0;
+    loop {
+        let page = source.export_page(&query);
+        writer.write(&page.items)?;
+        exported += page.items.len();
+        match page.next {
+            Some(cursor) => query.after = Some(cursor),
+            None => break,
+        }
+    }
+    writer.finish()?;
+    debug!(rows = exported, format = format.name(), "Export done");
+    Ok(exported)
+}
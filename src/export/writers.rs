@@ -0,0 +1,213 @@
+// src/export/writers.rs
+
+use super::{COLUMNS, ExportError, ExportFormat, ExportRow};
+use std::io::{BufWriter, Write};
+
+pub(super) trait RowWriter {
+    fn write(&mut self, rows: &[ExportRow]) -> Result<(), ExportError>;
+    /// Flushes, and writes the footer for formats that have one
+    fn finish(self: Box<Self>) -> Result<(), ExportError>;
+}
+
+pub(super) fn for_format<'a, W: Write + Send + 'a>(format: ExportFormat, out: W) -> Result<Box<dyn RowWriter + 'a>, ExportError> {
+    match format {
+        ExportFormat::Csv => Ok(Box::new(CsvWriter::new(out)?)),
+        ExportFormat::Jsonl => Ok(Box::new(JsonlWriter(BufWriter::new(out)))),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => Ok(Box::new(parquet_writer::ParquetWriter::new(out)?)),
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => Err(ExportError::Unsupported(format)),
+    }
+}
+
+/// RFC 4180: a header line, fields quoted when they contain a comma, a quote or a line break.
+/// Missing scores are empty fields.
+struct CsvWriter<W: Write>(BufWriter<W>);
+
+impl<W: Write> CsvWriter<W> {
+    fn new(out: W) -> Result<Self, ExportError> {
+        let mut out = BufWriter::new(out);
+        writeln!(out, "{}", COLUMNS.join(","))?;
+        Ok(Self(out))
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+impl<W: Write> RowWriter for CsvWriter<W> {
+    fn write(&mut self, rows: &[ExportRow]) -> Result<(), ExportError> {
+        for row in rows {
+            writeln!(
+                self.0,
+                "{},{},{},{},{},{},{},{},{}",
+                csv_field(&row.tx_id),
+                row.received_at,
+                row.amount,
+                csv_field(&row.currency),
+                csv_field(&row.merchant_id),
+                csv_field(&row.account_id),
+                optional(row.score),
+                optional(row.is_fraud),
+                optional(row.scored_at),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), ExportError> {
+        Ok(self.0.flush()?)
+    }
+}
+
+/// One JSON object per line, missing scores are `null`
+struct JsonlWriter<W: Write>(BufWriter<W>);
+
+impl<W: Write> RowWriter for JsonlWriter<W> {
+    fn write(&mut self, rows: &[ExportRow]) -> Result<(), ExportError> {
+        for row in rows {
+            serde_json::to_writer(&mut self.0, row).map_err(|e| ExportError::Encoding(e.to_string()))?;
+            self.0.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), ExportError> {
+        Ok(self.0.flush()?)
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_writer {
+    use super::{ExportError, ExportRow, RowWriter};
+    use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    // Same columns and order as `COLUMNS`
+    const SCHEMA: &str = "message fraud_export {
+        REQUIRED BYTE_ARRAY tx_id (UTF8);
+        REQUIRED INT64 received_at (TIMESTAMP(MILLIS, true));
+        REQUIRED DOUBLE amount;
+        REQUIRED BYTE_ARRAY currency (UTF8);
+        REQUIRED BYTE_ARRAY merchant_id (UTF8);
+        REQUIRED BYTE_ARRAY account_id (UTF8);
+        OPTIONAL DOUBLE score;
+        OPTIONAL BOOLEAN is_fraud;
+        OPTIONAL INT64 scored_at (TIMESTAMP(MILLIS, true));
+    }";
+
+    /// Rows buffered before a row group is written
+    const ROW_GROUP_SIZE: usize = 64 * 1024;
+
+    fn encoding(e: parquet::errors::ParquetError) -> ExportError {
+        ExportError::Encoding(e.to_string())
+    }
+
+    pub(super) struct ParquetWriter<W: Write + Send> {
+        file: SerializedFileWriter<W>,
+        buffer: Vec<ExportRow>,
+    }
+
+    impl<W: Write + Send> ParquetWriter<W> {
+        pub(super) fn new(out: W) -> Result<Self, ExportError> {
+            let schema = Arc::new(parse_message_type(SCHEMA).map_err(encoding)?);
+            let file = SerializedFileWriter::new(out, schema, Arc::new(WriterProperties::builder().build())).map_err(encoding)?;
+            Ok(Self { file, buffer: Vec::new() })
+        }
+
+        fn flush_row_group(&mut self) -> Result<(), ExportError> {
+            if self.buffer.is_empty() {
+                return Ok(());
+            }
+            let rows = std::mem::take(&mut self.buffer);
+            let mut group = self.file.next_row_group().map_err(encoding)?;
+            let mut index = 0;
+            while let Some(mut column) = group.next_column().map_err(encoding)? {
+                match index {
+                    0 | 3 | 4 | 5 => {
+                        let values: Vec<ByteArray> = rows
+                            .iter()
+                            .map(|r| match index {
+                                0 => r.tx_id.as_str(),
+                                3 => r.currency.as_str(),
+                                4 => r.merchant_id.as_str(),
+                                _ => r.account_id.as_str(),
+                            })
+                            .map(ByteArray::from)
+                            .collect();
+                        column.typed::<ByteArrayType>().write_batch(&values, None, None).map_err(encoding)?;
+                    }
+                    1 => {
+                        let values: Vec<i64> = rows.iter().map(|r| r.received_at).collect();
+                        column.typed::<Int64Type>().write_batch(&values, None, None).map_err(encoding)?;
+                    }
+                    2 => {
+                        let values: Vec<f64> = rows.iter().map(|r| r.amount).collect();
+                        column.typed::<DoubleType>().write_batch(&values, None, None).map_err(encoding)?;
+                    }
+                    // Optional columns: definition level 0 for a missing value
+                    6 => {
+                        let (values, levels) = optional_column(&rows, |r| r.score);
+                        column.typed::<DoubleType>().write_batch(&values, Some(&levels), None).map_err(encoding)?;
+                    }
+                    7 => {
+                        let (values, levels) = optional_column(&rows, |r| r.is_fraud);
+                        column.typed::<BoolType>().write_batch(&values, Some(&levels), None).map_err(encoding)?;
+                    }
+                    _ => {
+                        let (values, levels) = optional_column(&rows, |r| r.scored_at);
+                        column.typed::<Int64Type>().write_batch(&values, Some(&levels), None).map_err(encoding)?;
+                    }
+                }
+                column.close().map_err(encoding)?;
+                index += 1;
+            }
+            group.close().map_err(encoding)?;
+            Ok(())
+        }
+    }
+
+    fn optional_column<T>(rows: &[ExportRow], value: impl Fn(&ExportRow) -> Option<T>) -> (Vec<T>, Vec<i16>) {
+        let mut values = Vec::new();
+        let levels = rows
+            .iter()
+            .map(|r| match value(r) {
+                Some(v) => {
+                    values.push(v);
+                    1
+                }
+                None => 0,
+            })
+            .collect();
+        (values, levels)
+    }
+
+    impl<W: Write + Send> RowWriter for ParquetWriter<W> {
+        fn write(&mut self, rows: &[ExportRow]) -> Result<(), ExportError> {
+            self.buffer.extend_from_slice(rows);
+            if self.buffer.len() >= ROW_GROUP_SIZE {
+                self.flush_row_group()?;
+            }
+            Ok(())
+        }
+
+        fn finish(mut self: Box<Self>) -> Result<(), ExportError> {
+            self.flush_row_group()?;
+            self.file.close().map_err(encoding)?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// src/ingestion/mod.rs
+
+// Entry point for transactions coming from the outside world.
+// Everything submitted to the workers goes through the rate limiter first.
+
+pub mod rate_limit;
+
+use crate::domain::fraud_scorer::FraudScorer;
+use crate::domain::transaction::Transaction;
+use crate::telemetry::transaction_span;
+use crate::workers::dispatcher::WorkerMessage;
+use rate_limit::{Admission, RateLimiter, RiskSignals};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Returned by `Ingress::submit` once the workers are gone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IngressClosed;
+
+impl fmt::Display for IngressClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "worker channel is closed")
+    }
+}
+
+impl std::error::Error for IngressClosed {}
+
+/// Rate-limited front door of the `WorkerMessage` channel
+#[derive(Clone)]
+pub struct Ingress {
+    sender: mpsc::Sender<WorkerMessage>,
+    limiter: Arc<RateLimiter>,
+    signals: Arc<RiskSignals>,
+}
+
+impl Ingress {
+    pub fn new(sender: mpsc::Sender<WorkerMessage>, limiter: Arc<RateLimiter>, signals: Arc<RiskSignals>) -> Self {
+        Self { sender, limiter, signals }
+    }
+
+    /// Forwards `tx` to the workers if its merchant and account are within their limits.
+    /// A throttled transaction is not forwarded, it is recorded as a risk signal instead.
+    /// The transaction span starts here and travels with `tx` to the workers.
+    pub async fn submit(&self, tx: Transaction) -> Result<Admission, IngressClosed> {
+        let span = transaction_span(&tx);
+        let admission = span.in_scope(|| self.limiter.check(&tx));
+        match &admission {
+            Admission::Admitted => self.sender.send(WorkerMessage::Traced { tx, span }).await.map_err(|_| IngressClosed)?,
+            Admission::Throttled { scope, key } => {
+                let _entered = span.enter();
+                warn!(tx_id = %tx.id, %scope, key, "Transaction over rate limit");
+                self.signals.record_throttled(*scope, key);
+            }
+        }
+        Ok(admission)
+    }
+}
+
+/// Flags transactions whose merchant or account recently went over its rate limit,
+/// otherwise defers to the wrapped scorer
+pub struct RateLimitAwareScorer<S> {
+    pub inner: S,
+    pub signals: Arc<RiskSignals>,
+    /// Number of recent over-limit events from which a transaction is flagged
+    pub threshold: usize,
+}
+
+impl<S: FraudScorer> FraudScorer for RateLimitAwareScorer<S> {
+    fn is_fraud(&self, tx: &Transaction) -> bool {
+        self.signals.recent_throttles(tx) >= self.threshold || self.inner.is_fraud(tx)
+    }
+}
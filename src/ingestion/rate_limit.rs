@@ -0,0 +1,250 @@
+// src/ingestion/rate_limit.rs
+
+// Token buckets keyed by merchant id and by account id.
+// A transaction is admitted only if both its merchant and its account buckets have a token left;
+// tokens are taken from both buckets at once, so a rejection never consumes anything.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::domain::transaction::Transaction;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Maximum burst size
+    pub capacity: f64,
+    /// Tokens added back per second
+    pub refill_per_sec: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub per_merchant: RateLimit,
+    pub per_account: RateLimit,
+    /// Buckets untouched for this long are evicted (they would be full again anyway)
+    pub idle_ttl: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_merchant: RateLimit {
+                capacity: 200.0,
+                refill_per_sec: 100.0,
+            },
+            per_account: RateLimit {
+                capacity: 10.0,
+                refill_per_sec: 1.0,
+            },
+            idle_ttl: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitScope {
+    Merchant,
+    Account,
+}
+
+impl fmt::Display for LimitScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitScope::Merchant => write!(f, "merchant"),
+            LimitScope::Account => write!(f, "account"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    Admitted,
+    Throttled { scope: LimitScope, key: String },
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_seen: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_seen).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.refill_per_sec).min(limit.capacity);
+        self.last_seen = now;
+    }
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(LimitScope, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn limit(&self, scope: LimitScope) -> RateLimit {
+        match scope {
+            LimitScope::Merchant => self.config.per_merchant,
+            LimitScope::Account => self.config.per_account,
+        }
+    }
+
+    /// Takes one token from the merchant and the account buckets of `tx`.
+    /// Empty ids are not limited.
+    pub fn check(&self, tx: &Transaction) -> Admission {
+        let now = Instant::now();
+        let keys: Vec<(LimitScope, &str)> = [(LimitScope::Merchant, tx.merchant_id.as_str()), (LimitScope::Account, tx.account_id.as_str())]
+            .into_iter()
+            .filter(|(_, key)| !key.is_empty())
+            .collect();
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        for &(scope, key) in &keys {
+            let limit = self.limit(scope);
+            let bucket = buckets.entry((scope, key.to_string())).or_insert(Bucket {
+                tokens: limit.capacity,
+                last_seen: now,
+            });
+            bucket.refill(limit, now);
+            if bucket.tokens < 1.0 {
+                return Admission::Throttled { scope, key: key.to_string() };
+            }
+        }
+
+        for (scope, key) in keys {
+            if let Some(bucket) = buckets.get_mut(&(scope, key.to_string())) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Admission::Admitted
+    }
+
+    /// Drops buckets idle for longer than `idle_ttl`, returns how many were removed
+    pub fn evict_idle(&self) -> usize {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let before = buckets.len();
+        buckets.retain(|_, bucket| now.saturating_duration_since(bucket.last_seen) < self.config.idle_ttl);
+        before - buckets.len()
+    }
+
+    pub fn tracked_keys(&self) -> usize {
+        self.buckets.lock().unwrap().len()
+    }
+}
+
+/// Slots a `RiskSignals` window is split into
+const SIGNAL_SLOTS: u64 = 10;
+
+/// Over-limit events of one key, counted per slot of a tenth of the window: memory stays the same
+/// however many events a flooding merchant produces
+#[derive(Debug, Default)]
+struct SlotCounts {
+    /// (slot number, events in it), slot `n` is stored at `n % SIGNAL_SLOTS`
+    slots: [(u64, u32); SIGNAL_SLOTS as usize],
+}
+
+impl SlotCounts {
+    fn record(&mut self, slot: u64) {
+        let entry = &mut self.slots[(slot % SIGNAL_SLOTS) as usize];
+        if entry.0 != slot {
+            *entry = (slot, 0);
+        }
+        entry.1 = entry.1.saturating_add(1);
+    }
+
+    /// Events in the `SIGNAL_SLOTS` slots ending at `slot`
+    fn count(&self, slot: u64) -> usize {
+        self.slots
+            .iter()
+            .filter(|(n, _)| *n <= slot && slot - *n < SIGNAL_SLOTS)
+            .map(|(_, events)| *events as usize)
+            .sum()
+    }
+}
+
+/// Over-limit events per merchant / account, consumed by `RateLimitAwareScorer`
+pub struct RiskSignals {
+    origin: Instant,
+    slot_width: Duration,
+    throttled: Mutex<HashMap<(LimitScope, String), SlotCounts>>,
+}
+
+impl RiskSignals {
+    /// Signals older than `window` (give or take a tenth of it) are ignored and evicted
+    pub fn new(window: Duration) -> Self {
+        Self {
+            origin: Instant::now(),
+            slot_width: (window / SIGNAL_SLOTS as u32).max(Duration::from_nanos(1)),
+            throttled: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn current_slot(&self) -> u64 {
+        (self.origin.elapsed().as_nanos() / self.slot_width.as_nanos()) as u64
+    }
+
+    /// Also sweeps the expired keys whenever a new key doubles the map past 1024 keys,
+    /// so a flood of distinct keys doesn't wait for `spawn_eviction`
+    pub fn record_throttled(&self, scope: LimitScope, key: &str) {
+        let slot = self.current_slot();
+        let mut throttled = self.throttled.lock().unwrap();
+        let before = throttled.len();
+        throttled.entry((scope, key.to_string())).or_default().record(slot);
+        let len = throttled.len();
+        if len > before && len >= 1024 && len.is_power_of_two() {
+            throttled.retain(|_, counts| counts.count(slot) > 0);
+        }
+        debug!(%scope, key, "Rate limit risk signal recorded");
+    }
+
+    /// Number of over-limit events within the window for `tx`'s merchant and account
+    pub fn recent_throttles(&self, tx: &Transaction) -> usize {
+        let slot = self.current_slot();
+        let throttled = self.throttled.lock().unwrap();
+        [(LimitScope::Merchant, &tx.merchant_id), (LimitScope::Account, &tx.account_id)]
+            .into_iter()
+            .filter_map(|(scope, key)| throttled.get(&(scope, key.clone())))
+            .map(|counts| counts.count(slot))
+            .sum()
+    }
+
+    /// Drops the keys without signal within the window, returns how many were removed
+    pub fn evict_expired(&self) -> usize {
+        let slot = self.current_slot();
+        let mut throttled = self.throttled.lock().unwrap();
+        let before = throttled.len();
+        throttled.retain(|_, counts| counts.count(slot) > 0);
+        before - throttled.len()
+    }
+
+    pub fn tracked_keys(&self) -> usize {
+        self.throttled.lock().unwrap().len()
+    }
+}
+
+/// Periodically evicts idle buckets and expired signals
+pub fn spawn_eviction(limiter: Arc<RateLimiter>, signals: Arc<RiskSignals>, every: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let buckets = limiter.evict_idle();
+            let keys = signals.evict_expired();
+            if buckets + keys > 0 {
+                debug!(buckets, keys, "Evicted rate limit state");
+            }
+        }
+    })
+}
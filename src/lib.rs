@@ -12,3 +12,24 @@ pub mod workers;
 
 // added for the version with persistence
 pub mod persistence;
+
+// added for the staged pipeline (library version of examples/09_proto.rs)
+pub mod pipeline;
+
+// rate-limited entry point in front of the workers
+pub mod ingestion;
+
+// reliable publishing of fraud alerts to downstream systems
+pub mod outbox;
+
+// data out of the store for model training and audits
+pub mod export;
+
+// tokenization and column encryption of sensitive data
+pub mod security;
+
+// Prometheus metrics and the /metrics endpoint
+pub mod metrics;
+
+// spans following a transaction end to end, optional OTLP export
+pub mod telemetry;
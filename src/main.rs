@@ -0,0 +1,488 @@
+// src/main.rs
+// App entry point: reads transactions from stdin and runs them through the staged pipeline
+//
+// cargo run -- run --db data.db [--archive archive/] < transactions.csv
+// where each line is: id,amount,currency[,merchant_id,account_id]
+// --db takes sqlite:<path> (the default for a bare path), redb:<path> or memory
+// expired SQLite rows are purged hourly, and written to --archive first when given
+// transactions go through per merchant and per account rate limits first, over-limit ones are
+// dropped and make the next transactions of that merchant or account score as fraud
+// --batch <size> scores micro-batches of up to <size> transactions instead of running the staged pipeline
+// --metrics <addr> serves Prometheus metrics on http://<addr>/metrics, e.g. --metrics 127.0.0.1:9184
+// --otlp <endpoint> exports the spans of every transaction to an OpenTelemetry collector, e.g. --otlp http://localhost:4317
+// (built with --features otlp)
+//
+// cargo run -- diagram --format mermaid|dot
+// prints the transaction state machine for the compliance documentation
+//
+// cargo run -- export --db data.db --format csv|jsonl|parquet [--from <ms>] [--to <ms>] [--out <path>]
+// writes transactions received in [from, to) with their score to --out or stdout
+//
+// cargo run -- rotate-keys --keys keys.txt [--db data.db]
+// creates the key file, or adds a column key to it and re-encrypts the SQLite rows with it
+// run and export take --keys too: account ids are tokenized and SQLite identifier columns encrypted
+
+use fraud_detection_3::domain::fraud_scorer::{FraudScorer, RuleBasedScorer};
+use fraud_detection_3::domain::query::TransactionQuery;
+use fraud_detection_3::domain::repository::{EventStore, ScoreRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::export::{self, ExportFormat};
+use fraud_detection_3::ingestion::rate_limit::{self, RateLimitConfig, RateLimiter, RiskSignals};
+use fraud_detection_3::ingestion::{Ingress, RateLimitAwareScorer};
+use fraud_detection_3::metrics::{self, MeteredScoreRepo, MeteredScorer, MeteredTransRepo, Metrics};
+use fraud_detection_3::outbox::sinks::{AlertSink, FileSink, SinkError, WebhookSink};
+use fraud_detection_3::outbox::{OutboxMessage, OutboxRelay, RelayConfig};
+use fraud_detection_3::persistence::backend::{Backend, sqlite_encryption};
+use fraud_detection_3::persistence::sqlite::{SqliteStore, SqliteViews, StoreConfig};
+use fraud_detection_3::pipeline::Pipeline;
+use fraud_detection_3::pipeline::stages::{LifecycleStage, PersistenceStage, ScorePersistenceStage, ScoringStage, ValidationStage};
+use fraud_detection_3::security::{KeyFile, redact};
+use fraud_detection_3::state_machine::observer::LifecycleEvents;
+use fraud_detection_3::state_machine::timers::{TimerWheel, default_timer_rules};
+use fraud_detection_3::state_machine::transitions::standard_table;
+use fraud_detection_3::telemetry::transaction_span;
+use fraud_detection_3::workers::batcher::{self, BatchConfig};
+use fraud_detection_3::workers::dispatcher::WorkerMessage;
+use fraud_detection_3::workers::purge::{PurgeConfig, PurgeJob};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+use tracing::{Instrument, error, info, warn};
+use tracing_subscriber::prelude::*;
+
+const USAGE: &str = "usage: fraud_detection_3 [run] [--db <path>] [--archive <dir>] [--alerts <path|http://url>] [--keys <path>] [--batch <size>] [--metrics <addr>] [--otlp <endpoint>]
+       fraud_detection_3 diagram [--format mermaid|dot]
+       fraud_detection_3 export [--db <path>] [--format csv|jsonl|parquet] [--from <ms>] [--to <ms>] [--out <path>] [--keys <path>]
+       fraud_detection_3 rotate-keys --keys <path> [--db <path>]";
+
+/// How long an over-limit event raises the risk of its merchant and account
+const RISK_SIGNAL_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// Over-limit events within the window from which a merchant's or account's transactions are flagged
+const RISK_SIGNAL_THRESHOLD: usize = 3;
+/// Model name of the scores in the metrics and the event streams
+const SCORER_MODEL: &str = "rule_based";
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut iter = args.iter().map(String::as_str).peekable();
+    let command = match iter.peek() {
+        Some(&"run") | Some(&"diagram") | Some(&"export") | Some(&"rotate-keys") => iter.next().unwrap(),
+        _ => "run",
+    };
+
+    let mut db_path = "data.db".to_string();
+    let mut format = None;
+    let mut archive_dir = None;
+    let mut alerts = None;
+    let mut query = TransactionQuery::new();
+    let mut out = None;
+    let mut keys_path = None;
+    let mut metrics_addr = None;
+    let mut batch_size = None;
+    let mut otlp_endpoint = None;
+    while let Some(arg) = iter.next() {
+        match (arg, iter.next()) {
+            ("--db", Some(path)) => db_path = path.to_string(),
+            ("--archive", Some(dir)) => archive_dir = Some(PathBuf::from(dir)),
+            ("--alerts", Some(target)) => alerts = Some(target.to_string()),
+            ("--format", Some(f)) => format = Some(f),
+            ("--from", Some(ms)) => query.received_from = Some(ms.parse().unwrap_or_else(|_| usage())),
+            ("--to", Some(ms)) => query.received_to = Some(ms.parse().unwrap_or_else(|_| usage())),
+            ("--out", Some(path)) => out = Some(PathBuf::from(path)),
+            ("--keys", Some(path)) => keys_path = Some(PathBuf::from(path)),
+            ("--metrics", Some(addr)) => metrics_addr = Some(addr.to_string()),
+            ("--batch", Some(size)) => batch_size = Some(size.parse().unwrap_or_else(|_| usage())),
+            ("--otlp", Some(endpoint)) => otlp_endpoint = Some(endpoint),
+            _ => usage(),
+        }
+    }
+
+    // Flushes the exported spans when dropped, at the end of main
+    let _telemetry = init_tracing(otlp_endpoint);
+
+    // Refuse to start with a broken lifecycle
+    let table = Arc::new(standard_table());
+    if let Err(issues) = table.validate() {
+        for issue in issues {
+            error!(%issue, "Invalid transition table");
+        }
+        std::process::exit(1);
+    }
+
+    if command == "rotate-keys" {
+        let Some(keys_path) = keys_path else { usage() };
+        return rotate_keys(&keys_path, &db_path);
+    }
+    let keys = match &keys_path {
+        Some(path) => KeyFile::load(path).unwrap_or_else(|e| {
+            error!(error = %e, "Cannot load keys");
+            std::process::exit(1);
+        }),
+        None => KeyFile::default(),
+    };
+
+    match (command, format) {
+        ("diagram", Some("dot")) => print!("{}", table.to_dot()),
+        ("diagram", Some("mermaid") | None) => print!("{}", table.to_mermaid()),
+        ("export", format) => {
+            let format = ExportFormat::from_name(format.unwrap_or("csv")).unwrap_or_else(|| usage());
+            match Backend::parse(&db_path) {
+                Backend::Sqlite(path) => export_rows(&path, &keys, &query, format, out),
+                other => {
+                    error!(backend = other.name(), "Export reads SQLite databases only");
+                    std::process::exit(1);
+                }
+            }
+        }
+        ("run", None) => {
+            let options = RunOptions {
+                archive_dir,
+                alerts,
+                metrics_addr,
+                batch_size,
+            };
+            run(Backend::parse(&db_path), &keys, options).await
+        }
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    std::process::exit(2);
+}
+
+/// Redacted logs on stderr, plus the OTLP export of the spans when an endpoint is given
+#[cfg(feature = "otlp")]
+fn init_tracing(otlp_endpoint: Option<&str>) -> Option<fraud_detection_3::telemetry::otlp::OtlpGuard> {
+    use fraud_detection_3::telemetry::otlp;
+
+    let exporter = otlp_endpoint.map(otlp::layer).transpose().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    let (otlp_layer, guard) = exporter.unzip();
+    tracing_subscriber::registry().with(redact::layer().with_writer(std::io::stderr)).with(otlp_layer).init();
+    guard
+}
+
+#[cfg(not(feature = "otlp"))]
+fn init_tracing(otlp_endpoint: Option<&str>) -> Option<std::convert::Infallible> {
+    if otlp_endpoint.is_some() {
+        eprintln!("--otlp needs a build with the otlp feature: cargo run --features otlp");
+        std::process::exit(2);
+    }
+    tracing_subscriber::registry().with(redact::layer().with_writer(std::io::stderr)).init();
+    None
+}
+
+fn export_rows(db_path: &str, keys: &KeyFile, query: &TransactionQuery, format: ExportFormat, out: Option<PathBuf>) {
+    let config = StoreConfig {
+        encryption: sqlite_encryption(keys),
+        ..StoreConfig::default()
+    };
+    let source = SqliteStore::open_with(db_path, config).export();
+    let exported = match out {
+        Some(path) => match std::fs::File::create(&path) {
+            Ok(file) => export::export(&source, query, format, file),
+            Err(e) => Err(e.into()),
+        },
+        None => export::export(&source, query, format, std::io::stdout()),
+    };
+    match exported {
+        Ok(rows) => info!(rows, format = format.name(), "Export done"),
+        Err(e) => {
+            error!(error = %e, "Export failed");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn rotate_keys(keys_path: &Path, db_path: &str) {
+    let rotated = if keys_path.exists() {
+        KeyFile::rotate(keys_path).map(|id| info!(key = id, "Column key added"))
+    } else {
+        KeyFile::generate(keys_path).map(|_| info!(path = %keys_path.display(), "Key file created"))
+    };
+    let keys = rotated.and_then(|_| KeyFile::load(keys_path)).unwrap_or_else(|e| {
+        error!(error = %e, "Key rotation failed");
+        std::process::exit(1);
+    });
+    match Backend::parse(db_path) {
+        Backend::Sqlite(path) => {
+            let config = StoreConfig {
+                encryption: sqlite_encryption(&keys),
+                ..StoreConfig::default()
+            };
+            let rewritten = SqliteStore::open_with(&path, config).transactions().reencrypt(500);
+            info!(rewritten, "Transactions re-encrypted");
+        }
+        other => warn!(backend = other.name(), "Column encryption is implemented on SQLite only, nothing to re-encrypt"),
+    }
+}
+
+/// `http://` targets get a webhook, anything else is a JSON lines file
+enum AlertTarget {
+    File(FileSink),
+    Webhook(WebhookSink),
+}
+
+impl AlertTarget {
+    fn parse(target: &str) -> Result<Self, SinkError> {
+        if target.starts_with("http://") {
+            WebhookSink::new(target).map(AlertTarget::Webhook)
+        } else {
+            Ok(AlertTarget::File(FileSink::new(target)))
+        }
+    }
+}
+
+impl AlertSink for AlertTarget {
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        match self {
+            AlertTarget::File(sink) => sink.publish(message).await,
+            AlertTarget::Webhook(sink) => sink.publish(message).await,
+        }
+    }
+}
+
+struct RunOptions {
+    archive_dir: Option<PathBuf>,
+    /// Where the fraud alerts of the outbox are published, `<db>.alerts.jsonl` by default
+    alerts: Option<String>,
+    metrics_addr: Option<String>,
+    /// Micro-batching instead of the staged pipeline
+    batch_size: Option<usize>,
+}
+
+async fn run(backend: Backend, keys: &KeyFile, options: RunOptions) {
+    let metrics = Arc::new(Metrics::new());
+    let server = match options.metrics_addr {
+        Some(addr) => match metrics::server::spawn(addr.as_str(), metrics.clone()).await {
+            Ok((_, handle)) => Some(handle),
+            Err(e) => {
+                error!(addr, error = %e, "Cannot serve metrics");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let repos = backend.open_with_keys(keys);
+    let retention = repos.retention;
+    let events = repos.events;
+    // The lifecycle's decisions join the events appended by the pipeline stages
+    let table = match &events {
+        Some(events) => standard_table().observe(Arc::new(LifecycleEvents::new(events.clone()))),
+        None => standard_table(),
+    };
+    let table = Arc::new(table);
+    // Records the decisions and fires the timers they arm
+    let wheel = Arc::new(TimerWheel::new(table, repos.states, repos.timers, default_timer_rules()));
+    let timers = wheel.clone().spawn(Duration::from_secs(60));
+    let transactions = Arc::new(MeteredTransRepo::new(repos.transactions, metrics.clone()));
+    let scores = Arc::new(MeteredScoreRepo::new(repos.scores, metrics.clone()));
+    info!(backend = backend.name(), "Storage opened");
+
+    let limiter = Arc::new(RateLimiter::new(RateLimitConfig::default()));
+    let signals = Arc::new(RiskSignals::new(RISK_SIGNAL_WINDOW));
+    let eviction = rate_limit::spawn_eviction(limiter.clone(), signals.clone(), Duration::from_secs(60));
+    let rate_aware = RateLimitAwareScorer {
+        inner: RuleBasedScorer,
+        signals: signals.clone(),
+        threshold: RISK_SIGNAL_THRESHOLD,
+    };
+    let scorer = Arc::new(MeteredScorer::new(SCORER_MODEL, rate_aware, metrics.clone()));
+
+    // Retention is implemented on SQLite only
+    let purge = retention.map(|store| {
+        let purge_config = PurgeConfig {
+            archive_dir: options.archive_dir,
+            ..PurgeConfig::default()
+        };
+        Arc::new(PurgeJob::new(store, purge_config)).spawn(Duration::from_secs(60 * 60))
+    });
+
+    // Fraud scores enqueue their alert in the same SQL transaction, the relay publishes them
+    let relay = repos.outbox.map(|outbox| {
+        let target = match (options.alerts, &backend) {
+            (Some(target), _) => target,
+            (None, Backend::Sqlite(path)) => format!("{path}.alerts.jsonl"),
+            (None, other) => unreachable!("{} has no outbox", other.name()),
+        };
+        let sink = AlertTarget::parse(&target).unwrap_or_else(|e| {
+            error!(target, error = %e, "Cannot publish alerts");
+            std::process::exit(1);
+        });
+        let relay = Arc::new(OutboxRelay::new(outbox, sink, RelayConfig::default()));
+        (relay.clone(), relay.spawn())
+    });
+
+    // Admitted transactions reach the batch worker or the pipeline through this channel
+    let (sender, rx) = mpsc::channel(1024);
+    let ingress = Ingress::new(sender, limiter, signals);
+    let workers = match options.batch_size {
+        Some(max_size) => tokio::spawn(run_batched(rx, transactions, scores, scorer, wheel, max_size)),
+        None => tokio::spawn(run_pipeline(rx, transactions, scores, scorer, wheel, events, metrics)),
+    };
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let tx = match parse_line(&line) {
+            Some(tx) => tx,
+            None if line.trim().is_empty() => continue,
+            None => {
+                warn!(line, "Skipping malformed line");
+                continue;
+            }
+        };
+        // The pipeline has a validation stage, the batch worker does not
+        if options.batch_size.is_some()
+            && let Err(e) = tx.validate()
+        {
+            warn!(tx_id = %tx.id, reason = e.reason, "Transaction rejected by validation");
+            continue;
+        }
+        if ingress.submit(tx).await.is_err() {
+            break;
+        }
+    }
+
+    drop(ingress);
+    if let Err(e) = workers.await {
+        error!(error = %e, "Workers panicked");
+    }
+    eviction.abort();
+    timers.abort();
+    if let Some((relay, task)) = relay {
+        task.abort();
+        // Alerts of the last transactions, until drained or the sink fails
+        while relay.relay_once().await > 0 {}
+    }
+    if let Some(purge) = purge {
+        purge.abort();
+    }
+    if let Some(server) = server {
+        server.abort();
+    }
+}
+
+async fn run_pipeline<TR, SR, S>(
+    mut rx: mpsc::Receiver<WorkerMessage>,
+    transactions: Arc<TR>,
+    scores: Arc<SR>,
+    scorer: Arc<S>,
+    wheel: Arc<TimerWheel>,
+    events: Option<Arc<dyn EventStore>>,
+    metrics: Arc<Metrics>,
+)
+where
+    TR: TransRepository + ?Sized + 'static,
+    SR: ScoreRepository + ?Sized + 'static,
+    S: FraudScorer + Send + Sync + 'static,
+{
+    let mut persistence = PersistenceStage::new(transactions, scores.clone());
+    let mut score_persistence = ScorePersistenceStage::new(scores);
+    if let Some(events) = events {
+        persistence = persistence.with_events(events.clone());
+        score_persistence = score_persistence.with_events(events, SCORER_MODEL);
+    }
+    let (pipeline, mut scored) = Pipeline::builder(1024)
+        .metrics(metrics)
+        .stage(ValidationStage, 1)
+        .stage(persistence, 1)
+        .stage(ScoringStage::new(scorer), 4)
+        .stage(score_persistence, 1)
+        .stage(LifecycleStage::new(wheel), 1)
+        .build();
+
+    // Last stage output: one line per scored transaction on stdout
+    let printer = tokio::spawn(async move {
+        while let Some(score) = scored.recv().await {
+            print_score(&score);
+        }
+    });
+
+    while let Some(msg) = rx.recv().await {
+        let (tx, span) = match msg {
+            WorkerMessage::Traced { tx, span } => (tx, span),
+            WorkerMessage::Transaction(tx) => {
+                let span = transaction_span(&tx);
+                (tx, span)
+            }
+            WorkerMessage::Shutdown => break,
+        };
+        if pipeline.submit(tx).instrument(span).await.is_err() {
+            break;
+        }
+    }
+
+    for stage in pipeline.shutdown().await {
+        info!(
+            stage = stage.name,
+            workers = stage.workers,
+            processed = stage.processed,
+            dropped = stage.dropped,
+            avg_latency_us = stage.avg_latency.as_micros() as u64,
+            throughput = format!("{:.1}", stage.throughput),
+            "Stage summary"
+        );
+    }
+    let _ = printer.await;
+}
+
+async fn run_batched<TR, SR, S>(
+    rx: mpsc::Receiver<WorkerMessage>,
+    transactions: Arc<TR>,
+    scores: Arc<SR>,
+    scorer: Arc<S>,
+    wheel: Arc<TimerWheel>,
+    max_size: usize,
+)
+where
+    TR: TransRepository + ?Sized + 'static,
+    SR: ScoreRepository + ?Sized + 'static,
+    S: FraudScorer + Send + Sync + 'static,
+{
+    let (scored_tx, mut scored) = mpsc::channel(1024);
+    let config = BatchConfig {
+        max_size,
+        ..BatchConfig::default()
+    };
+    // The batch worker has no lifecycle stage, the printer records the decisions. It appends no
+    // `TransactionReceived` or `Scored` event: only the lifecycle's reach the event streams.
+    let printer = tokio::spawn(async move {
+        while let Some(score) = scored.recv().await {
+            if let Err(e) = wheel.record_decision(&score) {
+                warn!(tx_id = %score.id, error = %e, "Decision not recorded in the state machine");
+            }
+            print_score(&score);
+        }
+    });
+    batcher::start_batch_worker_with_output(rx, transactions, scores, scorer, config, scored_tx).await;
+    let _ = printer.await;
+}
+
+fn print_score(score: &Score) {
+    println!("{},{:.4},{}", score.id, score.score, score.is_fraud);
+}
+
+fn parse_line(line: &str) -> Option<Transaction> {
+    let mut fields = line.trim().split(',');
+    let id = fields.next()?.trim().to_string();
+    let amount = fields.next()?.trim().parse().ok()?;
+    let currency = fields.next()?.trim().to_string();
+    let merchant_id = fields.next().unwrap_or_default().trim().to_string();
+    let account_id = fields.next().unwrap_or_default().trim().to_string();
+    Some(Transaction {
+        id,
+        amount,
+        currency,
+        merchant_id,
+        account_id,
+    })
+}
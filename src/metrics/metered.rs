@@ -0,0 +1,147 @@
+// src/metrics/metered.rs
+
+// Decorators recording calls, latency and failures of any repository or scorer, whatever the backend.
+// Repositories panic on database errors: the panic is counted in `db_errors`, then carries on.
+
+use super::Metrics;
+use crate::domain::fraud_scorer::FraudScorer;
+use crate::domain::query::{Page, ScoreQuery, TransactionQuery};
+use crate::domain::repository::{RepoError, SaveOutcome, ScoreRepository, TransRepository};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Instant;
+
+struct RepoMeter {
+    repo: &'static str,
+    metrics: Arc<Metrics>,
+}
+
+impl RepoMeter {
+    fn call<T>(&self, op: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        self.metrics.repo_operations.with(&[self.repo, op]).inc();
+        self.metrics.repo_latency.with(&[self.repo, op]).observe_duration(start.elapsed());
+        result.unwrap_or_else(|payload| {
+            self.metrics.db_errors.with(&[self.repo, op]).inc();
+            panic::resume_unwind(payload)
+        })
+    }
+}
+
+/// Metrics labelled `repo="transactions"`
+pub struct MeteredTransRepo<R: ?Sized> {
+    inner: Arc<R>,
+    meter: RepoMeter,
+}
+
+impl<R: TransRepository + ?Sized> MeteredTransRepo<R> {
+    pub fn new(inner: Arc<R>, metrics: Arc<Metrics>) -> Self {
+        Self {
+            inner,
+            meter: RepoMeter { repo: "transactions", metrics },
+        }
+    }
+}
+
+impl<R: TransRepository + ?Sized> TransRepository for MeteredTransRepo<R> {
+    fn save(&self, tx: Transaction) {
+        self.meter.call("save", || self.inner.save(tx))
+    }
+
+    fn get(&self, id: &str) -> Option<Transaction> {
+        self.meter.call("get", || self.inner.get(id))
+    }
+
+    fn save_idempotent(&self, tx: Transaction) -> Result<SaveOutcome, RepoError> {
+        self.meter.call("save_idempotent", || self.inner.save_idempotent(tx))
+    }
+
+    fn save_idempotent_batch(&self, txs: Vec<Transaction>) -> Vec<Result<SaveOutcome, RepoError>> {
+        self.meter.call("save_idempotent_batch", || self.inner.save_idempotent_batch(txs))
+    }
+
+    fn query(&self, query: &TransactionQuery) -> Page<Transaction> {
+        self.meter.call("query", || self.inner.query(query))
+    }
+
+    fn count(&self, query: &TransactionQuery) -> usize {
+        self.meter.call("count", || self.inner.count(query))
+    }
+}
+
+/// Metrics labelled `repo="scores"`
+pub struct MeteredScoreRepo<R: ?Sized> {
+    inner: Arc<R>,
+    meter: RepoMeter,
+}
+
+impl<R: ScoreRepository + ?Sized> MeteredScoreRepo<R> {
+    pub fn new(inner: Arc<R>, metrics: Arc<Metrics>) -> Self {
+        Self {
+            inner,
+            meter: RepoMeter { repo: "scores", metrics },
+        }
+    }
+}
+
+impl<R: ScoreRepository + ?Sized> ScoreRepository for MeteredScoreRepo<R> {
+    fn save(&self, result: Score) {
+        self.meter.call("save", || self.inner.save(result))
+    }
+
+    fn get(&self, tx_id: &str) -> Option<Score> {
+        self.meter.call("get", || self.inner.get(tx_id))
+    }
+
+    fn query(&self, query: &ScoreQuery) -> Page<Score> {
+        self.meter.call("query", || self.inner.query(query))
+    }
+
+    fn count(&self, query: &ScoreQuery) -> usize {
+        self.meter.call("count", || self.inner.count(query))
+    }
+
+    fn save_batch(&self, results: Vec<Score>) {
+        self.meter.call("save_batch", || self.inner.save_batch(results))
+    }
+}
+
+/// Counts verdicts under `scorer="<name>"`, for the fraud rate of each model
+pub struct MeteredScorer<S> {
+    name: &'static str,
+    inner: S,
+    metrics: Arc<Metrics>,
+}
+
+impl<S: FraudScorer> MeteredScorer<S> {
+    pub fn new(name: &'static str, inner: S, metrics: Arc<Metrics>) -> Self {
+        Self { name, inner, metrics }
+    }
+
+    fn record(&self, is_fraud: bool) {
+        self.metrics.scores.with(&[self.name, if is_fraud { "true" } else { "false" }]).inc();
+    }
+}
+
+impl<S: FraudScorer> FraudScorer for MeteredScorer<S> {
+    fn is_fraud(&self, tx: &Transaction) -> bool {
+        let start = Instant::now();
+        let is_fraud = self.inner.is_fraud(tx);
+        self.metrics.scoring_latency.with(&[self.name]).observe_duration(start.elapsed());
+        self.record(is_fraud);
+        is_fraud
+    }
+
+    fn score_batch(&self, txs: &[Transaction]) -> Vec<Score> {
+        let start = Instant::now();
+        let scores = self.inner.score_batch(txs);
+        self.metrics.scoring_latency.with(&[self.name]).observe_duration(start.elapsed());
+        for score in &scores {
+            self.record(score.is_fraud);
+        }
+        scores
+    }
+}
@@ -0,0 +1,69 @@
+// src/metrics/mod.rs
+
+// Prometheus metrics of the fraud detection service. One `Metrics` is shared, behind an `Arc`, by
+// the pipeline, the dispatcher and the metered repositories and scorers, and served on `/metrics`.
+//
+// Fraud rate per scorer, in PromQL:
+//     sum by (scorer) (rate(fraud_detection_scores_total{is_fraud="true"}[5m]))
+//       / sum by (scorer) (rate(fraud_detection_scores_total[5m]))
+
+pub mod metered;
+pub mod registry;
+pub mod server;
+
+pub use metered::{MeteredScoreRepo, MeteredScorer, MeteredTransRepo};
+pub use registry::{Counter, Family, Gauge, Histogram};
+
+pub struct Metrics {
+    /// Transactions handled by a dispatcher worker, by outcome
+    pub transactions: Family<Counter>,
+    /// Items handled by a pipeline stage, forwarded or dropped
+    pub stage_items: Family<Counter>,
+    pub stage_latency: Family<Histogram>,
+    /// Items waiting in the input channel of a stage, or of the dispatcher
+    pub queue_depth: Family<Gauge>,
+    /// Scores produced, by scorer and verdict
+    pub scores: Family<Counter>,
+    pub scoring_latency: Family<Histogram>,
+    pub repo_operations: Family<Counter>,
+    pub repo_latency: Family<Histogram>,
+    /// Repository calls that failed on a database error
+    pub db_errors: Family<Counter>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            transactions: Family::new("fraud_detection_transactions_total", "Transactions handled by the dispatcher", &["outcome"]),
+            stage_items: Family::new("fraud_detection_stage_items_total", "Items handled by a pipeline stage", &["stage", "result"]),
+            stage_latency: Family::new("fraud_detection_stage_latency_seconds", "Time spent processing one item in a stage", &["stage"]),
+            queue_depth: Family::new("fraud_detection_queue_depth", "Items waiting in an input channel", &["stage"]),
+            scores: Family::new("fraud_detection_scores_total", "Transactions scored", &["scorer", "is_fraud"]),
+            scoring_latency: Family::new("fraud_detection_scoring_latency_seconds", "Time spent scoring one batch", &["scorer"]),
+            repo_operations: Family::new("fraud_detection_repo_operations_total", "Repository calls", &["repo", "op"]),
+            repo_latency: Family::new("fraud_detection_repo_latency_seconds", "Repository call duration", &["repo", "op"]),
+            db_errors: Family::new("fraud_detection_db_errors_total", "Repository calls failed on a database error", &["repo", "op"]),
+        }
+    }
+
+    /// Every family in the Prometheus text format
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+        self.transactions.encode(&mut out);
+        self.stage_items.encode(&mut out);
+        self.stage_latency.encode(&mut out);
+        self.queue_depth.encode(&mut out);
+        self.scores.encode(&mut out);
+        self.scoring_latency.encode(&mut out);
+        self.repo_operations.encode(&mut out);
+        self.repo_latency.encode(&mut out);
+        self.db_errors.encode(&mut out);
+        out
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,184 @@
+// src/metrics/registry.rs
+
+// Atomic counters, gauges and histograms, grouped in families of one name and several label values,
+// and their Prometheus text format (version 0.0.4).
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Seconds, from 100µs to 10s
+pub const LATENCY_BUCKETS: &[f64] = &[0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// A metric of a family, one per set of label values
+pub trait Metric: Send + Sync {
+    const TYPE: &'static str;
+
+    fn new(buckets: &'static [f64]) -> Self;
+    /// Writes the samples of this metric, `labels` is `k="v",...` or empty
+    fn encode(&self, name: &str, labels: &str, out: &mut String);
+}
+
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Metric for Counter {
+    const TYPE: &'static str = "counter";
+
+    fn new(_: &'static [f64]) -> Self {
+        Self::default()
+    }
+
+    fn encode(&self, name: &str, labels: &str, out: &mut String) {
+        let _ = writeln!(out, "{name}{} {}", braces(labels), self.get());
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Metric for Gauge {
+    const TYPE: &'static str = "gauge";
+
+    fn new(_: &'static [f64]) -> Self {
+        Self::default()
+    }
+
+    fn encode(&self, name: &str, labels: &str, out: &mut String) {
+        let _ = writeln!(out, "{name}{} {}", braces(labels), self.get());
+    }
+}
+
+#[derive(Debug)]
+pub struct Histogram {
+    /// Upper bounds, ascending
+    bounds: &'static [f64],
+    /// Per bucket, not cumulative; the last one is `+Inf`
+    counts: Vec<AtomicU64>,
+    /// f64 bits
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, value: f64) {
+        let bucket = self.bounds.iter().position(|b| value <= *b).unwrap_or(self.bounds.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        let _ = self.sum.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| Some((f64::from_bits(bits) + value).to_bits()));
+    }
+
+    pub fn observe_duration(&self, elapsed: Duration) {
+        self.observe(elapsed.as_secs_f64());
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().map(|c| c.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn sum(&self) -> f64 {
+        f64::from_bits(self.sum.load(Ordering::Relaxed))
+    }
+}
+
+impl Metric for Histogram {
+    const TYPE: &'static str = "histogram";
+
+    fn new(buckets: &'static [f64]) -> Self {
+        Self {
+            bounds: buckets,
+            counts: (0..=buckets.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    fn encode(&self, name: &str, labels: &str, out: &mut String) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            cumulative += count.load(Ordering::Relaxed);
+            let le = self.bounds.get(i).map_or("+Inf".to_string(), f64::to_string);
+            let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{le}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_sum{} {}", braces(labels), self.sum());
+        let _ = writeln!(out, "{name}_count{} {cumulative}", braces(labels));
+    }
+}
+
+/// All the metrics of one name, one per set of label values, created on first use
+pub struct Family<M> {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    buckets: &'static [f64],
+    metrics: RwLock<BTreeMap<Vec<String>, Arc<M>>>,
+}
+
+impl<M: Metric> Family<M> {
+    pub fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self::with_buckets(name, help, labels, LATENCY_BUCKETS)
+    }
+
+    /// Histograms only use `buckets`
+    pub fn with_buckets(name: &'static str, help: &'static str, labels: &'static [&'static str], buckets: &'static [f64]) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            buckets,
+            metrics: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// The metric for `values`, given in the order of the family's label names. Panics on a count mismatch.
+    /// Keep the returned handle on hot paths rather than looking it up for every sample.
+    pub fn with(&self, values: &[&str]) -> Arc<M> {
+        assert_eq!(values.len(), self.labels.len(), "{} takes labels {:?}", self.name, self.labels);
+        let key: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        if let Some(metric) = self.metrics.read().expect("metrics lock poisoned").get(&key) {
+            return metric.clone();
+        }
+        self.metrics.write().expect("metrics lock poisoned").entry(key).or_insert_with(|| Arc::new(M::new(self.buckets))).clone()
+    }
+
+    pub fn encode(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} {}", self.name, M::TYPE);
+        for (values, metric) in self.metrics.read().expect("metrics lock poisoned").iter() {
+            let labels: Vec<String> = self.labels.iter().zip(values).map(|(k, v)| format!("{k}=\"{}\"", escape(v))).collect();
+            metric.encode(self.name, &labels.join(","), out);
+        }
+    }
+}
+
+fn braces(labels: &str) -> String {
+    if labels.is_empty() { String::new() } else { format!("{{{labels}}}") }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,77 @@
+// src/metrics/server.rs
+
+// Just enough HTTP/1.1 for a Prometheus scrape: `GET /metrics`, one request per connection.
+
+use super::Metrics;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+/// Longest request head accepted, scrapers send a few hundred bytes
+const MAX_REQUEST: usize = 8 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves `metrics` on `addr` until the returned task is aborted.
+/// Returns the bound address, useful with port 0.
+pub async fn spawn(addr: impl ToSocketAddrs, metrics: Arc<Metrics>) -> io::Result<(SocketAddr, JoinHandle<()>)> {
+    let listener = TcpListener::bind(addr).await?;
+    let local = listener.local_addr()?;
+    info!(addr = %local, "Serving metrics on /metrics");
+    Ok((local, tokio::spawn(serve(listener, metrics))))
+}
+
+pub async fn serve(listener: TcpListener, metrics: Arc<Metrics>) {
+    loop {
+        match listener.accept().await {
+            Ok((conn, peer)) => {
+                let metrics = metrics.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = respond(conn, &metrics).await {
+                        debug!(%peer, error = %e, "Metrics request failed");
+                    }
+                });
+            }
+            Err(e) => debug!(error = %e, "Failed to accept metrics connection"),
+        }
+    }
+}
+
+async fn respond(mut conn: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    let head = tokio::time::timeout(READ_TIMEOUT, read_head(&mut conn))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "request head not received"))??;
+    let mut parts = head.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) if path.split('?').next() == Some("/metrics") => ("200 OK", metrics.encode()),
+        (Some("GET"), Some(_)) => ("404 Not Found", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "method not allowed\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    conn.write_all(response.as_bytes()).await?;
+    conn.shutdown().await
+}
+
+/// Everything up to the blank line ending the request head
+async fn read_head(conn: &mut TcpStream) -> io::Result<String> {
+    let mut buf = Vec::with_capacity(512);
+    let mut chunk = [0u8; 512];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_REQUEST {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "request head too large"));
+        }
+        let n = conn.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
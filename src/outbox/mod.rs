@@ -0,0 +1,138 @@
+// src/outbox/mod.rs
+
+// Transactional outbox: a fraud score and its alert are committed in the same SQL transaction, then
+// `OutboxRelay` publishes pending rows to a sink and marks them delivered.
+//
+// Delivery is at least once: a crash between `publish` and `mark_delivered` publishes the message again
+// on restart. Consumers deduplicate on `OutboxMessage::id`.
+
+pub mod sinks;
+
+use crate::domain::clock::now_millis;
+use crate::domain::repository::Outbox;
+use crate::domain::scoring::Score;
+use serde::{Deserialize, Serialize};
+use sinks::AlertSink;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+pub const FRAUD_ALERT_TOPIC: &str = "fraud_alert";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    pub id: i64,
+    pub topic: String,
+    /// Partitioning key, the transaction id for alerts
+    pub key: String,
+    /// JSON
+    pub payload: String,
+    /// ms since epoch
+    pub created_at: i64,
+    /// Publish attempts so far
+    pub attempts: u32,
+}
+
+/// Payload of a `FRAUD_ALERT_TOPIC` message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FraudAlert {
+    pub tx_id: String,
+    pub score: f64,
+    /// ms since epoch
+    pub flagged_at: i64,
+}
+
+impl FraudAlert {
+    pub fn from_score(score: &Score) -> Self {
+        Self {
+            tx_id: score.id.clone(),
+            score: score.score,
+            flagged_at: now_millis(),
+        }
+    }
+
+    /// A message ready to be enqueued, its id is assigned by the outbox
+    pub fn into_message(self) -> OutboxMessage {
+        OutboxMessage {
+            id: 0,
+            topic: FRAUD_ALERT_TOPIC.to_string(),
+            key: self.tx_id.clone(),
+            payload: serde_json::to_string(&self).expect("Failed to serialize alert"),
+            created_at: self.flagged_at,
+            attempts: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    /// Rows read per poll
+    pub batch_size: usize,
+    /// Pause between polls when the outbox is drained or the sink failed
+    pub poll_interval: Duration,
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+pub struct OutboxRelay<S: AlertSink> {
+    outbox: Arc<dyn Outbox>,
+    sink: S,
+    config: RelayConfig,
+}
+
+impl<S: AlertSink + 'static> OutboxRelay<S> {
+    pub fn new(outbox: Arc<dyn Outbox>, sink: S, config: RelayConfig) -> Self {
+        Self { outbox, sink, config }
+    }
+
+    /// Publishes one batch of pending messages in order, returns how many were delivered.
+    /// Stops at the first failure so a message is never delivered before an older one.
+    pub async fn relay_once(&self) -> usize {
+        let outbox = self.outbox.clone();
+        let limit = self.config.batch_size;
+        // Store access is blocking
+        let pending = tokio::task::spawn_blocking(move || outbox.pending(limit)).await.expect("Outbox read panicked");
+
+        let mut delivered = 0;
+        for message in pending {
+            match self.sink.publish(&message).await {
+                Ok(()) => {
+                    let outbox = self.outbox.clone();
+                    tokio::task::spawn_blocking(move || outbox.mark_delivered(message.id)).await.expect("Outbox write panicked");
+                    delivered += 1;
+                }
+                Err(e) => {
+                    warn!(id = message.id, key = %message.key, attempts = message.attempts + 1, error = %e, "Outbox publish failed");
+                    let outbox = self.outbox.clone();
+                    let error = e.to_string();
+                    tokio::task::spawn_blocking(move || outbox.record_failure(message.id, &error)).await.expect("Outbox write panicked");
+                    break;
+                }
+            }
+        }
+        if delivered > 0 {
+            debug!(delivered, "Outbox relayed");
+        }
+        delivered
+    }
+
+    /// Relays until the task is aborted, polling every `poll_interval` once the outbox is drained.
+    /// Keep a clone to drain the outbox with `relay_once` after aborting it.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if self.relay_once().await < self.config.batch_size {
+                    tokio::time::sleep(self.config.poll_interval).await;
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,122 @@
+// src/outbox/sinks.rs
+
+use super::OutboxMessage;
+use std::fmt;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkError(pub String);
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sink error: {}", self.0)
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+/// Where `OutboxRelay` publishes messages. `Ok` means the message is durably handed over:
+/// it is then marked delivered and never published again.
+pub trait AlertSink: Send + Sync {
+    fn publish(&self, message: &OutboxMessage) -> impl Future<Output = Result<(), SinkError>> + Send;
+}
+
+/// Appends one JSON line per message, synced to disk before returning
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl AlertSink for FileSink {
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        let mut line = serde_json::to_string(message).map_err(|e| SinkError(e.to_string()))?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| SinkError(format!("{}: {e}", self.path.display())))?;
+        file.write_all(line.as_bytes()).await.map_err(|e| SinkError(e.to_string()))?;
+        file.sync_data().await.map_err(|e| SinkError(e.to_string()))
+    }
+}
+
+/// Hands messages to an in-process consumer
+pub struct ChannelSink {
+    sender: mpsc::Sender<OutboxMessage>,
+}
+
+impl ChannelSink {
+    pub fn new(sender: mpsc::Sender<OutboxMessage>) -> Self {
+        Self { sender }
+    }
+}
+
+impl AlertSink for ChannelSink {
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        self.sender.send(message.clone()).await.map_err(|_| SinkError("channel closed".to_string()))
+    }
+}
+
+/// POSTs the message as JSON to a plain `http://host[:port]/path` URL, any 2xx counts as delivered.
+/// The `Idempotency-Key` header carries the outbox id for receivers that deduplicate.
+pub struct WebhookSink {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl WebhookSink {
+    /// TLS is not supported: put a local proxy in front of `https` endpoints
+    pub fn new(url: &str) -> Result<Self, SinkError> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| SinkError(format!("unsupported webhook url {url}")))?;
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| SinkError(format!("bad port in {url}")))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(SinkError(format!("missing host in {url}")));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: if path.is_empty() { "/".to_string() } else { path.to_string() },
+        })
+    }
+}
+
+impl AlertSink for WebhookSink {
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        let body = serde_json::to_string(message).map_err(|e| SinkError(e.to_string()))?;
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nIdempotency-Key: {}\r\nConnection: close\r\n\r\n{body}",
+            self.path,
+            self.host,
+            body.len(),
+            message.id
+        );
+
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await.map_err(|e| SinkError(e.to_string()))?;
+        stream.write_all(request.as_bytes()).await.map_err(|e| SinkError(e.to_string()))?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.map_err(|e| SinkError(e.to_string()))?;
+
+        // "HTTP/1.1 204 No Content"
+        let status_line = response.split(|b| *b == b'\n').next().unwrap_or_default();
+        let status = String::from_utf8_lossy(status_line);
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(SinkError(format!("webhook answered {}", status.trim()))),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// src/persistence/backend.rs
+
+// Storage selected by configuration, e.g. `--db redb:data.redb` on the command line
+
+use super::in_memory::{InMemoryScoreRepo, InMemoryStateRepo, InMemoryTimerStore, InMemoryTransactionRepo};
+use super::kv::RedbStore;
+use super::sqlite::{SqliteStore, SqliteViews, StoreConfig};
+use super::tokenizing::{TokenizingEventStore, TokenizingTransRepo};
+use crate::domain::repository::{EventStore, Outbox, RetentionStore, ScoreRepository, StateRepository, TimerStore, TransRepository};
+use crate::security::{ColumnEncryption, EncryptedColumn, KeyFile};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    InMemory,
+    Sqlite(String),
+    Redb(String),
+}
+
+impl Backend {
+    /// `memory`, `sqlite:<path>` or `redb:<path>`; a bare path is a SQLite database
+    pub fn parse(spec: &str) -> Self {
+        match spec.split_once(':') {
+            _ if spec == "memory" => Backend::InMemory,
+            Some(("sqlite", path)) => Backend::Sqlite(path.to_string()),
+            Some(("redb", path)) => Backend::Redb(path.to_string()),
+            _ => Backend::Sqlite(spec.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::InMemory => "memory",
+            Backend::Sqlite(_) => "sqlite",
+            Backend::Redb(_) => "redb",
+        }
+    }
+
+    /// Opens the transaction, score and state repositories, sharing one database
+    pub fn open(&self) -> Repositories {
+        self.open_with_keys(&KeyFile::default())
+    }
+
+    /// Same as `open`, account ids are tokenized when `keys` has a token key, and SQLite encrypts
+    /// the identifier columns when it has column keys. redb has no column encryption.
+    pub fn open_with_keys(&self, keys: &KeyFile) -> Repositories {
+        match self {
+            Backend::InMemory => Repositories {
+                transactions: tokenized(InMemoryTransactionRepo::new(), keys),
+                scores: Arc::new(InMemoryScoreRepo::new()),
+                states: Arc::new(InMemoryStateRepo::new()),
+                timers: Arc::new(InMemoryTimerStore::new()),
+                events: None,
+                outbox: None,
+                retention: None,
+            },
+            Backend::Sqlite(path) => {
+                let config = StoreConfig {
+                    outbox: true,
+                    encryption: sqlite_encryption(keys),
+                    ..StoreConfig::default()
+                };
+                let store = SqliteStore::open_with(path, config);
+                Repositories {
+                    transactions: tokenized(store.transactions(), keys),
+                    scores: Arc::new(store.scores()),
+                    states: Arc::new(store.states()),
+                    timers: Arc::new(store.timers()),
+                    events: Some(tokenized_events(store.events(), keys)),
+                    outbox: Some(Arc::new(store.outbox())),
+                    retention: Some(Arc::new(store.retention())),
+                }
+            }
+            Backend::Redb(path) => {
+                let store = RedbStore::open(path);
+                Repositories {
+                    transactions: tokenized(store.transactions(), keys),
+                    scores: Arc::new(store.scores()),
+                    // redb has no state or timer store, the lifecycle does not survive a restart there
+                    states: Arc::new(InMemoryStateRepo::new()),
+                    timers: Arc::new(InMemoryTimerStore::new()),
+                    events: None,
+                    outbox: None,
+                    retention: None,
+                }
+            }
+        }
+    }
+}
+
+fn tokenized<R: TransRepository + 'static>(repo: R, keys: &KeyFile) -> Arc<dyn TransRepository> {
+    match keys.tokenizer() {
+        Some(tokenizer) => Arc::new(TokenizingTransRepo::new(repo, tokenizer)),
+        None => Arc::new(repo),
+    }
+}
+
+fn tokenized_events<E: EventStore + 'static>(store: E, keys: &KeyFile) -> Arc<dyn EventStore> {
+    match keys.tokenizer() {
+        Some(tokenizer) => Arc::new(TokenizingEventStore::new(store, tokenizer)),
+        None => Arc::new(store),
+    }
+}
+
+/// Both identifier columns, the account id even with a token key: only `transactions` is tokenized, the
+/// other views of the store and its unit of work take raw account ids
+pub fn sqlite_encryption(keys: &KeyFile) -> Option<ColumnEncryption> {
+    keys.cipher().map(|cipher| ColumnEncryption::new(cipher, vec![EncryptedColumn::MerchantId, EncryptedColumn::AccountId]))
+}
+
+pub struct Repositories {
+    pub transactions: Arc<dyn TransRepository>,
+    pub scores: Arc<dyn ScoreRepository>,
+    /// Lifecycle state and transition history of each transaction
+    pub states: Arc<dyn StateRepository>,
+    /// Armed timers of a `TimerWheel` on `states`
+    pub timers: Arc<dyn TimerStore>,
+    /// Event streams of the decisions, with the same tokenization and encryption as `transactions`. Implemented on SQLite only.
+    pub events: Option<Arc<dyn EventStore>>,
+    /// Fraud alerts enqueued with their score, for an `OutboxRelay`. Implemented on SQLite only.
+    pub outbox: Option<Arc<dyn Outbox>>,
+    /// Expired rows for a `PurgeJob`, on the same connection as the repositories. Retention is implemented on SQLite only.
+    pub retention: Option<Arc<dyn RetentionStore>>,
+}
@@ -0,0 +1,136 @@
+// src/persistence/conformance.rs
+
+// Behavior every `TransRepository` / `ScoreRepository` must have, whatever the backend.
+// Run the suites from a test with a factory returning an empty repository:
+//
+//     #[test]
+//     fn my_repo_conforms() {
+//         conformance::trans_repository_suite(|| MyRepo::new());
+//     }
+//
+// Failures panic with the name of the broken property.
+
+use crate::domain::query::{ScoreQuery, TransactionQuery};
+use crate::domain::repository::{RepoError, SaveOutcome, ScoreRepository, TransRepository};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+
+const THREADS: usize = 8;
+const PER_THREAD: usize = 50;
+
+fn tx(id: &str, amount: f64) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount,
+        currency: "USD".to_string(),
+        merchant_id: "m-1".to_string(),
+        account_id: "acc-1".to_string(),
+    }
+}
+
+fn score(id: &str, score: f64) -> Score {
+    Score {
+        id: id.to_string(),
+        score,
+        is_fraud: score > 0.8,
+    }
+}
+
+pub fn trans_round_trip(repo: &dyn TransRepository) {
+    let original = tx("tx-1", 12.5);
+    repo.save(original.clone());
+    assert_eq!(repo.get("tx-1"), Some(original), "round trip: every field is stored");
+}
+
+pub fn trans_missing_id(repo: &dyn TransRepository) {
+    assert_eq!(repo.get("nope"), None, "missing id: get returns None");
+    assert_eq!(repo.count(&TransactionQuery::new()), 0, "missing id: an empty repository counts 0");
+}
+
+/// `save` overwrites, `save_idempotent` never does
+pub fn trans_overwrite(repo: &dyn TransRepository) {
+    repo.save(tx("tx-1", 1.0));
+    repo.save(tx("tx-1", 2.0));
+    assert_eq!(repo.get("tx-1"), Some(tx("tx-1", 2.0)), "overwrite: save replaces the previous version");
+    assert_eq!(repo.count(&TransactionQuery::new()), 1, "overwrite: one row per id");
+
+    assert_eq!(repo.save_idempotent(tx("tx-2", 1.0)), Ok(SaveOutcome::Inserted));
+    assert_eq!(repo.save_idempotent(tx("tx-2", 1.0)), Ok(SaveOutcome::Duplicate), "overwrite: a retry is a duplicate");
+    assert_eq!(
+        repo.save_idempotent(tx("tx-2", 9.0)),
+        Err(RepoError::Conflict { id: "tx-2".to_string() }),
+        "overwrite: another payload under a known id is a conflict"
+    );
+    assert_eq!(repo.get("tx-2"), Some(tx("tx-2", 1.0)), "overwrite: save_idempotent keeps the original");
+}
+
+/// Concurrent writers on distinct ids all land, concurrent idempotent saves of one id insert once
+pub fn trans_concurrency(repo: &dyn TransRepository) {
+    std::thread::scope(|s| {
+        for t in 0..THREADS {
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    repo.save(tx(&format!("tx-{t}-{i}"), i as f64 + 1.0));
+                }
+            });
+        }
+    });
+    assert_eq!(repo.count(&TransactionQuery::new()), THREADS * PER_THREAD, "concurrency: no write is lost");
+
+    let inserted: usize = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS).map(|_| s.spawn(|| repo.save_idempotent(tx("tx-shared", 5.0)))).collect();
+        handles.into_iter().map(|h| h.join().expect("writer panicked")).filter(|r| *r == Ok(SaveOutcome::Inserted)).count()
+    });
+    assert_eq!(inserted, 1, "concurrency: a racing retry is inserted once");
+}
+
+pub fn score_round_trip(repo: &dyn ScoreRepository) {
+    for original in [score("tx-1", 0.25), score("tx-2", 0.95)] {
+        repo.save(original.clone());
+        assert_eq!(repo.get(&original.id), Some(original), "round trip: every field is stored");
+    }
+}
+
+pub fn score_missing_id(repo: &dyn ScoreRepository) {
+    assert_eq!(repo.get("nope"), None, "missing id: get returns None");
+    assert_eq!(repo.count(&ScoreQuery::new()), 0, "missing id: an empty repository counts 0");
+}
+
+/// A rescored transaction keeps its latest score only
+pub fn score_overwrite(repo: &dyn ScoreRepository) {
+    repo.save(score("tx-1", 0.1));
+    repo.save(score("tx-1", 0.9));
+    assert_eq!(repo.get("tx-1"), Some(score("tx-1", 0.9)), "overwrite: save replaces the previous score");
+    repo.save_batch(vec![score("tx-1", 0.3), score("tx-2", 0.4)]);
+    assert_eq!(repo.get("tx-1"), Some(score("tx-1", 0.3)), "overwrite: save_batch replaces too");
+    assert_eq!(repo.count(&ScoreQuery::new()), 2, "overwrite: one row per id");
+}
+
+pub fn score_concurrency(repo: &dyn ScoreRepository) {
+    std::thread::scope(|s| {
+        for t in 0..THREADS {
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    repo.save(score(&format!("tx-{t}-{i}"), 0.5));
+                }
+            });
+        }
+    });
+    assert_eq!(repo.count(&ScoreQuery::new()), THREADS * PER_THREAD, "concurrency: no write is lost");
+}
+
+/// Runs every `TransRepository` check, each on a fresh repository from `make`
+pub fn trans_repository_suite<R: TransRepository>(make: impl Fn() -> R) {
+    trans_round_trip(&make());
+    trans_missing_id(&make());
+    trans_overwrite(&make());
+    trans_concurrency(&make());
+}
+
+/// Runs every `ScoreRepository` check, each on a fresh repository from `make`
+pub fn score_repository_suite<R: ScoreRepository>(make: impl Fn() -> R) {
+    score_round_trip(&make());
+    score_missing_id(&make());
+    score_overwrite(&make());
+    score_concurrency(&make());
+}
@@ -2,28 +2,282 @@
 
 use std::collections::HashMap;
 use std::sync::Mutex;
+use std::time::Duration;
 
-use crate::domain::repository::TransRepository;
+use crate::domain::clock::now_millis;
+use crate::domain::label::Label;
+use crate::domain::query::{Cursor, Page, ScoreQuery, TransactionQuery};
+use crate::domain::repository::{DEFAULT_DEDUP_WINDOW, LabelRepository, RepoError, SaveOutcome, ScoreRepository, StateRepository, TimerStore, TransRepository};
+use crate::domain::scoring::Score;
 use crate::domain::transaction::Transaction;
+use crate::state_machine::event::Event;
+use crate::state_machine::timers::Timer;
+use crate::state_machine::transitions::{StateId, TransitionRecord};
 
 pub struct InMemoryTransactionRepo {
-    store: Mutex<HashMap<String, Transaction>>,
+    // value: (transaction, received_at in ms)
+    store: Mutex<HashMap<String, (Transaction, i64)>>,
+    dedup_window: Duration,
 }
 
 impl InMemoryTransactionRepo {
     pub fn new() -> Self {
-        Self { store: Mutex::new(HashMap::new()) }
+        Self::with_dedup_window(DEFAULT_DEDUP_WINDOW)
+    }
+
+    pub fn with_dedup_window(dedup_window: Duration) -> Self {
+        Self {
+            store: Mutex::new(HashMap::new()),
+            dedup_window,
+        }
+    }
+}
+
+impl Default for InMemoryTransactionRepo {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl TransRepository for InMemoryTransactionRepo {
     fn save(&self, tx: Transaction) {
         let tx_id = tx.id.clone(); // keep id before moving tx
-        self.store.lock().unwrap().insert(tx_id.clone(), tx);
+        self.store.lock().unwrap().insert(tx_id.clone(), (tx, now_millis()));
         tracing::debug!(tx_id = %tx_id, "Saved transaction in memory");
     }
 
     fn get(&self, id: &str) -> Option<Transaction> {
-        self.store.lock().unwrap().get(id).cloned()
+        self.store.lock().unwrap().get(id).map(|(tx, _)| tx.clone())
+    }
+
+    fn save_idempotent(&self, tx: Transaction) -> Result<SaveOutcome, RepoError> {
+        let now = now_millis();
+        let mut store = self.store.lock().unwrap();
+
+        if let Some((existing, received_at)) = store.get(&tx.id)
+            && now - received_at < self.dedup_window.as_millis() as i64
+        {
+            if *existing == tx {
+                return Ok(SaveOutcome::Duplicate);
+            }
+            return Err(RepoError::Conflict { id: tx.id });
+        }
+
+        let tx_id = tx.id.clone();
+        store.insert(tx_id.clone(), (tx, now));
+        tracing::debug!(tx_id = %tx_id, "Saved transaction in memory");
+        Ok(SaveOutcome::Inserted)
+    }
+
+    fn query(&self, query: &TransactionQuery) -> Page<Transaction> {
+        let after = match &query.after {
+            Some(cursor) => match cursor.decode::<i64>() {
+                Some((at, id)) => Some((at, id.to_string())),
+                None => return Page::default(),
+            },
+            None => None,
+        };
+        let store = self.store.lock().unwrap();
+        let mut found: Vec<(i64, Transaction)> = store
+            .values()
+            .filter(|(tx, at)| query.matches(tx, *at))
+            .filter(|(tx, at)| after.as_ref().is_none_or(|(after_at, after_id)| (*at, &tx.id) > (*after_at, after_id)))
+            .map(|(tx, at)| (*at, tx.clone()))
+            .collect();
+        found.sort_by(|(a_at, a), (b_at, b)| (a_at, &a.id).cmp(&(b_at, &b.id)));
+
+        let page = Page::from_overfetch(found, query.page_size(), |(at, tx)| Cursor::new(at, &tx.id));
+        Page {
+            items: page.items.into_iter().map(|(_, tx)| tx).collect(),
+            next: page.next,
+        }
+    }
+
+    fn count(&self, query: &TransactionQuery) -> usize {
+        self.store.lock().unwrap().values().filter(|(tx, at)| query.matches(tx, *at)).count()
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryScoreRepo {
+    store: Mutex<HashMap<String, Score>>,
+}
+
+impl InMemoryScoreRepo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ScoreRepository for InMemoryScoreRepo {
+    fn save(&self, result: Score) {
+        let tx_id = result.id.clone();
+        self.store.lock().unwrap().insert(tx_id.clone(), result);
+        tracing::debug!(tx_id = %tx_id, "Saved scoring in memory");
+    }
+
+    fn get(&self, tx_id: &str) -> Option<Score> {
+        self.store.lock().unwrap().get(tx_id).cloned()
+    }
+
+    fn query(&self, query: &ScoreQuery) -> Page<Score> {
+        let after = match &query.after {
+            Some(cursor) => match cursor.decode::<f64>() {
+                Some((score, id)) => Some((score, id.to_string())),
+                None => return Page::default(),
+            },
+            None => None,
+        };
+        let store = self.store.lock().unwrap();
+        let mut found: Vec<Score> = store
+            .values()
+            .filter(|s| query.matches(s))
+            .filter(|s| after.as_ref().is_none_or(|(score, id)| s.score < *score || (s.score == *score && s.id > *id)))
+            .cloned()
+            .collect();
+        // Highest score first, then by id
+        found.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        Page::from_overfetch(found, query.page_size(), |s| Cursor::new(s.score, &s.id))
+    }
+
+    fn count(&self, query: &ScoreQuery) -> usize {
+        self.store.lock().unwrap().values().filter(|s| query.matches(s)).count()
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryStateRepo {
+    // Single lock so state and history always move together
+    inner: Mutex<StateStore>,
+}
+
+#[derive(Default)]
+struct StateStore {
+    states: HashMap<String, StateId>,
+    history: HashMap<String, Vec<TransitionRecord>>,
+}
+
+impl InMemoryStateRepo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StateRepository for InMemoryStateRepo {
+    fn save_state(&self, tx_id: &str, state: StateId) {
+        self.inner.lock().unwrap().states.insert(tx_id.to_string(), state);
+    }
+
+    fn get_state(&self, tx_id: &str) -> Option<StateId> {
+        self.inner.lock().unwrap().states.get(tx_id).copied()
+    }
+
+    fn record_transition(&self, record: TransitionRecord) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.states.insert(record.tx_id.clone(), record.to);
+        tracing::debug!(tx_id = %record.tx_id, from = %record.from, to = %record.to, "Recorded transition in memory");
+        inner.history.entry(record.tx_id.clone()).or_default().push(record);
+    }
+
+    fn record_transitions_if(&self, expected: Option<StateId>, records: &[TransitionRecord]) -> bool {
+        let (Some(first), Some(last)) = (records.first(), records.last()) else {
+            return true;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        if inner.states.get(&first.tx_id).copied() != expected {
+            return false;
+        }
+        inner.states.insert(last.tx_id.clone(), last.to);
+        inner.history.entry(first.tx_id.clone()).or_default().extend_from_slice(records);
+        true
+    }
+
+    fn history(&self, tx_id: &str) -> Vec<TransitionRecord> {
+        self.inner.lock().unwrap().history.get(tx_id).cloned().unwrap_or_default()
+    }
+
+    fn ids_in_state(&self, state: StateId) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        let mut ids: Vec<String> = inner.states.iter().filter(|(_, s)| **s == state).map(|(id, _)| id.clone()).collect();
+        ids.sort();
+        ids
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryTimerStore {
+    // (next id, timers)
+    inner: Mutex<(i64, Vec<Timer>)>,
+}
+
+impl InMemoryTimerStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TimerStore for InMemoryTimerStore {
+    fn schedule(&self, tx_id: &str, event: Event, due_at: i64) -> i64 {
+        let mut inner = self.inner.lock().unwrap();
+        inner.0 += 1;
+        let id = inner.0;
+        inner.1.push(Timer {
+            id,
+            tx_id: tx_id.to_string(),
+            event,
+            due_at,
+        });
+        id
+    }
+
+    fn cancel_all(&self, tx_id: &str) {
+        self.inner.lock().unwrap().1.retain(|t| t.tx_id != tx_id);
+    }
+
+    fn remove(&self, timer_id: i64) {
+        self.inner.lock().unwrap().1.retain(|t| t.id != timer_id);
+    }
+
+    fn due(&self, now: i64, limit: usize) -> Vec<Timer> {
+        let inner = self.inner.lock().unwrap();
+        let mut due: Vec<Timer> = inner.1.iter().filter(|t| t.due_at <= now).cloned().collect();
+        due.sort_by_key(|t| (t.due_at, t.id));
+        due.truncate(limit);
+        due
+    }
+
+    fn pending(&self, tx_id: &str) -> Vec<Timer> {
+        self.inner.lock().unwrap().1.iter().filter(|t| t.tx_id == tx_id).cloned().collect()
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryLabelRepo {
+    // insertion order, labels are append only
+    labels: Mutex<Vec<Label>>,
+}
+
+impl InMemoryLabelRepo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LabelRepository for InMemoryLabelRepo {
+    fn save(&self, label: Label) {
+        self.labels.lock().unwrap().push(label);
+    }
+
+    fn for_tx(&self, tx_id: &str) -> Vec<Label> {
+        self.labels.lock().unwrap().iter().filter(|l| l.tx_id == tx_id).cloned().collect()
+    }
+
+    fn since(&self, since: i64, limit: usize) -> Vec<Label> {
+        let labels = self.labels.lock().unwrap();
+        let mut found: Vec<Label> = labels.iter().filter(|l| l.labeled_at >= since).cloned().collect();
+        // stable sort keeps insertion order for equal timestamps
+        found.sort_by_key(|l| l.labeled_at);
+        found.truncate(limit);
+        found
     }
 }
@@ -0,0 +1,12 @@
+// src/persistence/kv/mod.rs
+
+// Repositories on redb, an embedded B-tree key-value store. Same semantics as the SQLite repositories
+// (and the same conformance suites), without SQL parsing and row encoding on every insert.
+
+pub mod scoring_repo;
+pub mod store;
+pub mod transaction_repo;
+
+pub use scoring_repo::RedbScoreRepo;
+pub use store::{KvConfig, RedbStore};
+pub use transaction_repo::RedbTransRepo;
@@ -0,0 +1,126 @@
+// src/persistence/kv/scoring_repo.rs
+
+use crate::domain::query::{Cursor, Page, ScoreQuery};
+use crate::domain::repository::ScoreRepository;
+use crate::domain::scoring::Score;
+use redb::{Database, ReadableDatabase, ReadableTable, ReadableTableMetadata, TableDefinition, WriteTransaction};
+use std::sync::Arc;
+use tracing::debug;
+
+// tx_id -> JSON `Score`
+const SCORES: TableDefinition<&str, &[u8]> = TableDefinition::new("scoring_results");
+// (desc_key(score), tx_id), the order of `query`
+const BY_SCORE: TableDefinition<(u64, &str), ()> = TableDefinition::new("scoring_results_by_score");
+
+pub struct RedbScoreRepo {
+    db: Arc<Database>,
+}
+
+impl RedbScoreRepo {
+    /// A view on a database owned by a `RedbStore`
+    pub(super) fn shared(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+}
+
+pub(super) fn create_tables(write: &WriteTransaction) {
+    write.open_table(SCORES).expect("Failed to create scoring_results table");
+    write.open_table(BY_SCORE).expect("Failed to create scoring_results index");
+}
+
+/// Keys sorting like the scores, highest first (f64 isn't a redb key)
+fn desc_key(score: f64) -> u64 {
+    let bits = score.to_bits();
+    let ascending = if bits >> 63 == 1 { !bits } else { bits | 1 << 63 };
+    !ascending
+}
+
+fn decode(bytes: &[u8]) -> Score {
+    serde_json::from_slice(bytes).expect("Failed to decode stored score")
+}
+
+/// Inserts or replaces `result`, keeping the index in step
+fn put(write: &WriteTransaction, result: &Score) {
+    let mut table = write.open_table(SCORES).expect("Failed to open scoring_results table");
+    let mut index = write.open_table(BY_SCORE).expect("Failed to open scoring_results index");
+    let previous = table.get(result.id.as_str()).expect("Failed to read score").map(|value| decode(value.value()));
+    if let Some(previous) = previous {
+        index.remove((desc_key(previous.score), result.id.as_str())).expect("Failed to update scoring_results index");
+    }
+    index.insert((desc_key(result.score), result.id.as_str()), ()).expect("Failed to index score");
+    let bytes = serde_json::to_vec(result).expect("Failed to encode score");
+    table.insert(result.id.as_str(), bytes.as_slice()).expect("Failed to insert score");
+}
+
+impl ScoreRepository for RedbScoreRepo {
+    fn save(&self, result: Score) {
+        let write = self.db.begin_write().expect("Failed to begin redb transaction");
+        put(&write, &result);
+        write.commit().expect("Failed to commit score");
+        debug!(tx_id = %result.id, "Saved scoring to redb");
+    }
+
+    fn get(&self, tx_id: &str) -> Option<Score> {
+        let read = self.db.begin_read().expect("Failed to begin redb read");
+        let table = read.open_table(SCORES).expect("Failed to open scoring_results table");
+        table.get(tx_id).expect("Failed to read score").map(|value| decode(value.value()))
+    }
+
+    fn save_batch(&self, results: Vec<Score>) {
+        let write = self.db.begin_write().expect("Failed to begin redb transaction");
+        for result in &results {
+            put(&write, result);
+        }
+        write.commit().expect("Failed to commit scoring batch");
+        debug!(count = results.len(), "Saved scoring batch to redb");
+    }
+
+    fn query(&self, query: &ScoreQuery) -> Page<Score> {
+        let after = match &query.after {
+            Some(cursor) => match cursor.decode::<f64>() {
+                Some((score, id)) => Some((desc_key(score), id.to_string())),
+                None => return Page::default(),
+            },
+            None => None,
+        };
+        let limit = query.page_size();
+        let start = after.as_ref().map_or(query.max_score.map_or(0, desc_key), |(key, _)| *key);
+
+        let read = self.db.begin_read().expect("Failed to begin redb read");
+        let table = read.open_table(SCORES).expect("Failed to open scoring_results table");
+        let index = read.open_table(BY_SCORE).expect("Failed to open scoring_results index");
+        let mut found = Vec::new();
+        for entry in index.range((start, "")..).expect("Failed to scan scores") {
+            let (key, _) = entry.expect("Failed to read scoring_results index");
+            let (score_key, id) = key.value();
+            if query.min_score.is_some_and(|min| score_key > desc_key(min)) {
+                break;
+            }
+            if after.as_ref().is_some_and(|(after_key, after_id)| (score_key, id) <= (*after_key, after_id.as_str())) {
+                continue;
+            }
+            let score = decode(table.get(id).expect("Failed to read score").expect("Index points to a missing score").value());
+            if query.matches(&score) {
+                found.push(score);
+                if found.len() > limit {
+                    break;
+                }
+            }
+        }
+        Page::from_overfetch(found, limit, |s: &Score| Cursor::new(s.score, &s.id))
+    }
+
+    fn count(&self, query: &ScoreQuery) -> usize {
+        let read = self.db.begin_read().expect("Failed to begin redb read");
+        let table = read.open_table(SCORES).expect("Failed to open scoring_results table");
+        if *query == ScoreQuery::new() {
+            return table.len().expect("Failed to count scores") as usize;
+        }
+        table
+            .iter()
+            .expect("Failed to scan scores")
+            .map(|entry| decode(entry.expect("Failed to read score").1.value()))
+            .filter(|score| query.matches(score))
+            .count()
+    }
+}
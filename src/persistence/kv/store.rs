@@ -0,0 +1,66 @@
+// src/persistence/kv/store.rs
+
+// A redb file can only be opened once per process: `RedbStore` owns the database and hands out
+// repository views sharing it, like `SqliteStore`.
+
+use super::{RedbScoreRepo, RedbTransRepo, scoring_repo, transaction_repo};
+use crate::domain::repository::DEFAULT_DEDUP_WINDOW;
+use redb::backends::InMemoryBackend;
+use redb::Database;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct KvConfig {
+    /// Page cache, in bytes
+    pub cache_size: usize,
+    pub dedup_window: Duration,
+}
+
+impl Default for KvConfig {
+    fn default() -> Self {
+        Self {
+            cache_size: 64 * 1024 * 1024,
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+        }
+    }
+}
+
+pub struct RedbStore {
+    db: Arc<Database>,
+    config: KvConfig,
+}
+
+impl RedbStore {
+    pub fn open(db_path: &str) -> Self {
+        Self::open_with(db_path, KvConfig::default())
+    }
+
+    pub fn open_with(db_path: &str, config: KvConfig) -> Self {
+        let db = Database::builder().set_cache_size(config.cache_size).create(db_path).expect("Failed to open redb database");
+        Self::init(db, config)
+    }
+
+    /// Nothing written to disk, for tests
+    pub fn in_memory() -> Self {
+        let db = Database::builder().create_with_backend(InMemoryBackend::new()).expect("Failed to create in-memory redb database");
+        Self::init(db, KvConfig::default())
+    }
+
+    fn init(db: Database, config: KvConfig) -> Self {
+        // Read transactions fail on tables that were never created
+        let write = db.begin_write().expect("Failed to begin redb transaction");
+        transaction_repo::create_tables(&write);
+        scoring_repo::create_tables(&write);
+        write.commit().expect("Failed to create redb tables");
+        Self { db: Arc::new(db), config }
+    }
+
+    pub fn transactions(&self) -> RedbTransRepo {
+        RedbTransRepo::shared(self.db.clone(), self.config.dedup_window)
+    }
+
+    pub fn scores(&self) -> RedbScoreRepo {
+        RedbScoreRepo::shared(self.db.clone())
+    }
+}
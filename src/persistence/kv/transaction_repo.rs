@@ -0,0 +1,167 @@
+// src/persistence/kv/transaction_repo.rs
+
+use crate::domain::clock::now_millis;
+use crate::domain::query::{Cursor, Page, TransactionQuery};
+use crate::domain::repository::{RepoError, SaveOutcome, TransRepository};
+use crate::domain::transaction::Transaction;
+use redb::{Database, ReadableDatabase, ReadableTable, Table, TableDefinition, WriteTransaction};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+// id -> JSON `Stored`
+const TRANSACTIONS: TableDefinition<&str, &[u8]> = TableDefinition::new("transactions");
+// (received_at, id), the order of `query`
+const BY_RECEIVED: TableDefinition<(i64, &str), ()> = TableDefinition::new("transactions_by_received");
+
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    tx: Transaction,
+    /// ms since epoch, drives the dedup window
+    received_at: i64,
+}
+
+pub struct RedbTransRepo {
+    db: Arc<Database>,
+    dedup_window: Duration,
+}
+
+impl RedbTransRepo {
+    /// A view on a database owned by a `RedbStore`
+    pub(super) fn shared(db: Arc<Database>, dedup_window: Duration) -> Self {
+        Self { db, dedup_window }
+    }
+}
+
+pub(super) fn create_tables(write: &WriteTransaction) {
+    write.open_table(TRANSACTIONS).expect("Failed to create transactions table");
+    write.open_table(BY_RECEIVED).expect("Failed to create transactions index");
+}
+
+fn decode(bytes: &[u8]) -> Stored {
+    serde_json::from_slice(bytes).expect("Failed to decode stored transaction")
+}
+
+fn load(table: &impl ReadableTable<&'static str, &'static [u8]>, id: &str) -> Option<Stored> {
+    table.get(id).expect("Failed to read transaction").map(|value| decode(value.value()))
+}
+
+/// Inserts or replaces `tx`, keeping the index in step
+fn put(write: &WriteTransaction, tx: Transaction, received_at: i64) {
+    let mut table = write.open_table(TRANSACTIONS).expect("Failed to open transactions table");
+    let mut index = write.open_table(BY_RECEIVED).expect("Failed to open transactions index");
+    if let Some(previous) = load(&table, &tx.id) {
+        index.remove((previous.received_at, tx.id.as_str())).expect("Failed to update transactions index");
+    }
+    index.insert((received_at, tx.id.as_str()), ()).expect("Failed to index transaction");
+    let id = tx.id.clone();
+    let bytes = serde_json::to_vec(&Stored { tx, received_at }).expect("Failed to encode transaction");
+    table.insert(id.as_str(), bytes.as_slice()).expect("Failed to insert transaction");
+}
+
+fn save_idempotent_on(write: &WriteTransaction, tx: Transaction, dedup_window: Duration, now: i64) -> Result<SaveOutcome, RepoError> {
+    let existing = {
+        let table: Table<&str, &[u8]> = write.open_table(TRANSACTIONS).expect("Failed to open transactions table");
+        load(&table, &tx.id)
+    };
+    if let Some(existing) = existing
+        && now - existing.received_at < dedup_window.as_millis() as i64
+    {
+        if existing.tx == tx {
+            return Ok(SaveOutcome::Duplicate);
+        }
+        return Err(RepoError::Conflict { id: tx.id });
+    }
+    debug!(tx_id = %tx.id, "Saved transaction to redb");
+    put(write, tx, now);
+    Ok(SaveOutcome::Inserted)
+}
+
+impl TransRepository for RedbTransRepo {
+    fn save(&self, tx: Transaction) {
+        let write = self.db.begin_write().expect("Failed to begin redb transaction");
+        let tx_id = tx.id.clone();
+        put(&write, tx, now_millis());
+        write.commit().expect("Failed to commit transaction");
+        debug!(tx_id = %tx_id, "Saved transaction to redb");
+    }
+
+    fn get(&self, id: &str) -> Option<Transaction> {
+        let read = self.db.begin_read().expect("Failed to begin redb read");
+        let table = read.open_table(TRANSACTIONS).expect("Failed to open transactions table");
+        load(&table, id).map(|stored| stored.tx)
+    }
+
+    fn save_idempotent(&self, tx: Transaction) -> Result<SaveOutcome, RepoError> {
+        // redb has a single writer: the read-compare-write can't race another save
+        let write = self.db.begin_write().expect("Failed to begin redb transaction");
+        let outcome = save_idempotent_on(&write, tx, self.dedup_window, now_millis());
+        if outcome == Ok(SaveOutcome::Inserted) {
+            write.commit().expect("Failed to commit transaction");
+        } else {
+            write.abort().expect("Failed to abort redb transaction");
+        }
+        outcome
+    }
+
+    fn save_idempotent_batch(&self, txs: Vec<Transaction>) -> Vec<Result<SaveOutcome, RepoError>> {
+        let now = now_millis();
+        // One commit (and one fsync) for the whole batch
+        let write = self.db.begin_write().expect("Failed to begin redb transaction");
+        let outcomes = txs.into_iter().map(|tx| save_idempotent_on(&write, tx, self.dedup_window, now)).collect();
+        write.commit().expect("Failed to commit transaction batch");
+        outcomes
+    }
+
+    fn query(&self, query: &TransactionQuery) -> Page<Transaction> {
+        let after = match &query.after {
+            Some(cursor) => match cursor.decode::<i64>() {
+                Some((at, id)) => Some((at, id.to_string())),
+                None => return Page::default(),
+            },
+            None => None,
+        };
+        let limit = query.page_size();
+        let start = after.as_ref().map_or(query.received_from.unwrap_or(i64::MIN), |(at, _)| *at);
+
+        let read = self.db.begin_read().expect("Failed to begin redb read");
+        let table = read.open_table(TRANSACTIONS).expect("Failed to open transactions table");
+        let index = read.open_table(BY_RECEIVED).expect("Failed to open transactions index");
+        let mut found = Vec::new();
+        for entry in index.range((start, "")..).expect("Failed to scan transactions") {
+            let (key, _) = entry.expect("Failed to read transactions index");
+            let (at, id) = key.value();
+            if query.received_to.is_some_and(|to| at >= to) {
+                break;
+            }
+            if after.as_ref().is_some_and(|(after_at, after_id)| (at, id) <= (*after_at, after_id.as_str())) {
+                continue;
+            }
+            let stored = load(&table, id).expect("Index points to a missing transaction");
+            if query.matches(&stored.tx, at) {
+                found.push(stored);
+                if found.len() > limit {
+                    break;
+                }
+            }
+        }
+
+        let page = Page::from_overfetch(found, limit, |s: &Stored| Cursor::new(s.received_at, &s.tx.id));
+        Page {
+            items: page.items.into_iter().map(|s| s.tx).collect(),
+            next: page.next,
+        }
+    }
+
+    fn count(&self, query: &TransactionQuery) -> usize {
+        let read = self.db.begin_read().expect("Failed to begin redb read");
+        let table = read.open_table(TRANSACTIONS).expect("Failed to open transactions table");
+        table
+            .iter()
+            .expect("Failed to scan transactions")
+            .map(|entry| decode(entry.expect("Failed to read transaction").1.value()))
+            .filter(|stored| query.matches(&stored.tx, stored.received_at))
+            .count()
+    }
+}
@@ -1,4 +1,8 @@
 // src/persistence/mod.rs
 
+pub mod backend;
+pub mod conformance;
 pub mod in_memory;
+pub mod kv;
 pub mod sqlite;
+pub mod tokenizing;
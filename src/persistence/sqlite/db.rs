@@ -0,0 +1,109 @@
+// src/persistence/sqlite/db.rs
+
+// Connection handle shared by the SQLite repositories: one writer connection, and optionally a pool of
+// read-only connections (see `SqlitePool`). Without readers every call goes through the writer.
+
+use rusqlite::Connection;
+use std::ops::Deref;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+#[derive(Clone)]
+pub(crate) struct Db {
+    writer: Arc<Mutex<Connection>>,
+    readers: Option<Arc<ReaderPool>>,
+}
+
+impl Db {
+    pub(crate) fn single(conn: Connection) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(conn)),
+            readers: None,
+        }
+    }
+
+    pub(crate) fn pooled(writer: Connection, readers: Vec<Connection>) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(writer)),
+            readers: Some(Arc::new(ReaderPool {
+                idle: Mutex::new(readers),
+                available: Condvar::new(),
+            })),
+        }
+    }
+
+    pub(crate) fn write(&self) -> MutexGuard<'_, Connection> {
+        self.writer.lock().unwrap()
+    }
+
+    /// A read-only connection when the pool has some, waiting for one to be free
+    pub(crate) fn read(&self) -> ReadGuard<'_> {
+        match &self.readers {
+            Some(pool) => ReadGuard::Pooled {
+                pool,
+                conn: Some(pool.checkout()),
+            },
+            None => ReadGuard::Writer(self.write()),
+        }
+    }
+
+    /// Idle read-only connections, 0 without a pool
+    pub(crate) fn idle_readers(&self) -> usize {
+        self.readers.as_ref().map_or(0, |pool| pool.idle.lock().unwrap().len())
+    }
+}
+
+pub(crate) struct ReaderPool {
+    idle: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+impl ReaderPool {
+    fn checkout(&self) -> Connection {
+        let mut idle = self.idle.lock().unwrap();
+        loop {
+            if let Some(conn) = idle.pop() {
+                return conn;
+            }
+            idle = self.available.wait(idle).unwrap();
+        }
+    }
+
+    fn checkin(&self, conn: Connection) {
+        self.idle.lock().unwrap().push(conn);
+        self.available.notify_one();
+    }
+}
+
+pub(crate) enum ReadGuard<'a> {
+    Writer(MutexGuard<'a, Connection>),
+    // `conn` is only taken back on drop
+    Pooled { pool: &'a ReaderPool, conn: Option<Connection> },
+}
+
+impl Deref for ReadGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            ReadGuard::Writer(conn) => conn,
+            ReadGuard::Pooled { conn, .. } => conn.as_ref().expect("reader already returned"),
+        }
+    }
+}
+
+/// Whether `table` has `column`, for the migrations adding columns to databases created before them
+pub(crate) fn has_column(conn: &Connection, table: &str, column: &str) -> bool {
+    conn.prepare_cached("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")
+        .and_then(|mut stmt| stmt.exists(rusqlite::params![table, column]))
+        .unwrap_or_else(|e| panic!("Failed to inspect {table}: {e}"))
+}
+
+impl Drop for ReadGuard<'_> {
+    fn drop(&mut self) {
+        if let ReadGuard::Pooled { pool, conn } = self
+            && let Some(conn) = conn.take()
+        {
+            pool.checkin(conn);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// src/persistence/sqlite/event_store.rs
+
+use super::db::Db;
+use crate::domain::clock::now_millis;
+use crate::domain::repository::{AppendError, EventStore};
+use crate::domain::transaction::Transaction;
+use crate::domain::tx_event::{RecordedEvent, Snapshot, TxEvent, TxView};
+use crate::security::ColumnEncryption;
+use rusqlite::{Connection, OptionalExtension, Row, TransactionBehavior, params};
+use tracing::debug;
+
+/// Append-only event streams. Rows of `events` can be neither updated nor deleted (triggers), and
+/// `UNIQUE (stream_id, version)` stops two writers from both appending at the same version.
+pub struct SQLiteEventStore {
+    db: Db,
+    encryption: Option<ColumnEncryption>,
+}
+
+impl SQLiteEventStore {
+    pub fn new(db_path: &str) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_tables(&conn);
+        Self::shared(Db::single(conn), None)
+    }
+
+    /// Same as `new`, the columns of `encryption` are also encrypted in the transactions that events and
+    /// snapshots embed, like in `SQLiteTransRepo::with_encryption`
+    pub fn with_encryption(db_path: &str, encryption: ColumnEncryption) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_tables(&conn);
+        Self::shared(Db::single(conn), Some(encryption))
+    }
+
+    /// A view on a connection owned by a `SqliteStore`
+    pub(super) fn shared(db: Db, encryption: Option<ColumnEncryption>) -> Self {
+        Self { db, encryption }
+    }
+
+    fn seal(&self, transaction: &mut Transaction) {
+        if let Some(encryption) = &self.encryption {
+            *transaction = encryption.encrypt_tx(transaction);
+        }
+    }
+
+    fn open(&self, transaction: &mut Transaction) {
+        if let Some(encryption) = &self.encryption {
+            *transaction = encryption.decrypt_tx(transaction.clone()).expect("Failed to decrypt event transaction");
+        }
+    }
+
+    fn row_to_event(&self, row: &Row) -> rusqlite::Result<RecordedEvent> {
+        let mut recorded = row_to_event(row)?;
+        if let TxEvent::TransactionReceived { transaction } = &mut recorded.event {
+            self.open(transaction);
+        }
+        Ok(recorded)
+    }
+}
+
+pub(super) fn create_tables(conn: &Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS events (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            stream_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            recorded_at INTEGER NOT NULL,
+            UNIQUE (stream_id, version)
+        );
+        CREATE INDEX IF NOT EXISTS idx_events_type ON events (event_type);
+        CREATE TRIGGER IF NOT EXISTS events_no_update BEFORE UPDATE ON events
+        BEGIN SELECT RAISE(ABORT, 'events are append only'); END;
+        CREATE TRIGGER IF NOT EXISTS events_no_delete BEFORE DELETE ON events
+        BEGIN SELECT RAISE(ABORT, 'events are append only'); END;
+        CREATE TABLE IF NOT EXISTS snapshots (
+            stream_id TEXT PRIMARY KEY,
+            version INTEGER NOT NULL,
+            view TEXT NOT NULL
+        );",
+    )
+    .expect("Failed to create event store tables");
+}
+
+fn row_to_event(row: &Row) -> rusqlite::Result<RecordedEvent> {
+    let payload: String = row.get(3)?;
+    Ok(RecordedEvent {
+        seq: row.get(0)?,
+        stream_id: row.get(1)?,
+        version: row.get::<_, i64>(2)? as u64,
+        event: serde_json::from_str(&payload).unwrap_or_else(|e| panic!("Corrupt event payload in DB: {e}")),
+        recorded_at: row.get(4)?,
+    })
+}
+
+fn stream_version(conn: &Connection, stream_id: &str) -> u64 {
+    conn.query_row("SELECT COALESCE(MAX(version), 0) FROM events WHERE stream_id = ?1", params![stream_id], |row| {
+        row.get::<_, i64>(0)
+    })
+    .expect("Failed to read stream version") as u64
+}
+
+impl EventStore for SQLiteEventStore {
+    fn append(&self, stream_id: &str, expected_version: u64, events: Vec<TxEvent>) -> Result<u64, AppendError> {
+        let mut conn = self.db.write();
+        // IMMEDIATE takes the write lock up front: other connections wait instead of racing on the version
+        let sql_tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).expect("Failed to begin transaction");
+
+        let actual = stream_version(&sql_tx, stream_id);
+        if actual != expected_version {
+            return Err(AppendError::VersionConflict {
+                stream_id: stream_id.to_string(),
+                expected: expected_version,
+                actual,
+            });
+        }
+
+        let now = now_millis();
+        let mut version = actual;
+        {
+            let mut stmt = sql_tx
+                .prepare_cached("INSERT INTO events (stream_id, version, event_type, payload, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+                .expect("Failed to prepare event insert");
+            for event in &events {
+                version += 1;
+                let mut event = event.clone();
+                if let TxEvent::TransactionReceived { transaction } = &mut event {
+                    self.seal(transaction);
+                }
+                let payload = serde_json::to_string(&event).expect("Failed to serialize event");
+                stmt.execute(params![stream_id, version as i64, event.kind(), payload, now])
+                    .expect("Failed to insert event");
+            }
+        }
+        sql_tx.commit().expect("Failed to commit events");
+        debug!(stream_id, version, appended = events.len(), "Appended events to SQLite");
+        Ok(version)
+    }
+
+    fn read_stream(&self, stream_id: &str, after_version: u64) -> Vec<RecordedEvent> {
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT seq, stream_id, version, payload, recorded_at FROM events
+                 WHERE stream_id = ?1 AND version > ?2 ORDER BY version",
+            )
+            .expect("Failed to prepare stream query");
+        stmt.query_map(params![stream_id, after_version as i64], |row| self.row_to_event(row))
+            .expect("Failed to query stream")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read events")
+    }
+
+    fn read_all(&self, after_seq: i64, limit: usize) -> Vec<RecordedEvent> {
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached("SELECT seq, stream_id, version, payload, recorded_at FROM events WHERE seq > ?1 ORDER BY seq LIMIT ?2")
+            .expect("Failed to prepare events query");
+        stmt.query_map(params![after_seq, limit as i64], |row| self.row_to_event(row))
+            .expect("Failed to query events")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read events")
+    }
+
+    fn save_snapshot(&self, snapshot: Snapshot) {
+        let conn = self.db.write();
+        let version = snapshot.view.version as i64;
+        let mut view = snapshot.view;
+        if let Some(transaction) = &mut view.transaction {
+            self.seal(transaction);
+        }
+        let view = serde_json::to_string(&view).expect("Failed to serialize snapshot");
+        conn.execute(
+            "INSERT INTO snapshots (stream_id, version, view) VALUES (?1, ?2, ?3)
+             ON CONFLICT (stream_id) DO UPDATE SET version = excluded.version, view = excluded.view
+             WHERE excluded.version > snapshots.version",
+            params![snapshot.stream_id, version, view],
+        )
+        .expect("Failed to save snapshot");
+    }
+
+    fn load_snapshot(&self, stream_id: &str) -> Option<Snapshot> {
+        let conn = self.db.read();
+        conn.query_row("SELECT view FROM snapshots WHERE stream_id = ?1", params![stream_id], |row| row.get::<_, String>(0))
+            .optional()
+            .expect("Failed to read snapshot")
+            .map(|view| {
+                let mut view: TxView = serde_json::from_str(&view).unwrap_or_else(|e| panic!("Corrupt snapshot in DB: {e}"));
+                if let Some(transaction) = &mut view.transaction {
+                    self.open(transaction);
+                }
+                Snapshot {
+                    stream_id: stream_id.to_string(),
+                    view,
+                }
+            })
+    }
+}
@@ -0,0 +1,106 @@
+// src/persistence/sqlite/export.rs
+
+use super::db::Db;
+use super::{scoring_repo, transaction_repo};
+use crate::domain::query::{Cursor, Page, TransactionQuery};
+use crate::domain::repository::ExportSource;
+use crate::export::ExportRow;
+use crate::security::{ColumnEncryption, EncryptedColumn};
+use rusqlite::Connection;
+use rusqlite::types::Value;
+use rusqlite::params_from_iter;
+
+/// `transactions` LEFT JOIN `scoring_results`: unscored transactions are exported too
+pub struct SQLiteExport {
+    db: Db,
+    encryption: Option<ColumnEncryption>,
+}
+
+impl SQLiteExport {
+    pub fn new(db_path: &str) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        transaction_repo::create_tables(&conn);
+        scoring_repo::create_tables(&conn);
+        Self::shared(Db::single(conn), None)
+    }
+
+    /// A view on a connection owned by a `SqliteStore`
+    pub(super) fn shared(db: Db, encryption: Option<ColumnEncryption>) -> Self {
+        Self { db, encryption }
+    }
+
+    fn decrypt(&self, mut row: ExportRow) -> ExportRow {
+        if let Some(encryption) = &self.encryption {
+            for (column, value) in [(EncryptedColumn::MerchantId, &mut row.merchant_id), (EncryptedColumn::AccountId, &mut row.account_id)] {
+                if encryption.encrypts(column) {
+                    *value = encryption.cipher.decrypt(column.name(), value).expect("Failed to decrypt export row");
+                }
+            }
+        }
+        row
+    }
+}
+
+impl ExportSource for SQLiteExport {
+    /// Filters on encrypted columns are applied after decryption: pages can come back shorter than the limit
+    fn export_page(&self, query: &TransactionQuery) -> Page<ExportRow> {
+        let mut sql_query = query.clone();
+        if let Some(encryption) = &self.encryption {
+            if encryption.encrypts(EncryptedColumn::MerchantId) {
+                sql_query.merchant_id = None;
+            }
+            if encryption.encrypts(EncryptedColumn::AccountId) {
+                sql_query.account_id = None;
+            }
+        }
+        let (mut clause, mut values) = transaction_repo::filters(&sql_query);
+        if let Some(cursor) = &query.after {
+            let Some((at, id)) = cursor.decode::<i64>() else {
+                return Page::default();
+            };
+            clause.push_str(" AND (received_at > ? OR (received_at = ? AND id > ?))");
+            values.extend([Value::Integer(at), Value::Integer(at), Value::Text(id.to_string())]);
+        }
+        let limit = query.page_size();
+        values.push(Value::Integer(limit as i64 + 1));
+
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT t.id, t.received_at, t.amount, t.currency, t.merchant_id, t.account_id, s.score, s.is_fraud, s.scored_at
+                 FROM transactions t LEFT JOIN scoring_results s ON s.tx_id = t.id
+                 WHERE {clause} ORDER BY t.received_at, t.id LIMIT ?"
+            ))
+            .expect("Failed to prepare export query");
+        let rows: Vec<ExportRow> = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(ExportRow {
+                    tx_id: row.get(0)?,
+                    received_at: row.get(1)?,
+                    amount: row.get(2)?,
+                    currency: row.get(3)?,
+                    merchant_id: row.get(4)?,
+                    account_id: row.get(5)?,
+                    score: row.get(6)?,
+                    is_fraud: row.get::<_, Option<i32>>(7)?.map(|f| f != 0),
+                    scored_at: row.get(8)?,
+                })
+            })
+            .expect("Failed to query export rows")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read export rows");
+        let page = Page::from_overfetch(rows, limit, |r: &ExportRow| Cursor::new(r.received_at, &r.tx_id));
+        Page {
+            items: page
+                .items
+                .into_iter()
+                .map(|row| self.decrypt(row))
+                .filter(|row| {
+                    query.merchant_id.as_ref().is_none_or(|m| *m == row.merchant_id)
+                        && query.account_id.as_ref().is_none_or(|a| *a == row.account_id)
+                })
+                .collect(),
+            next: page.next,
+        }
+    }
+}
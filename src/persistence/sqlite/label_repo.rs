@@ -0,0 +1,119 @@
+// src/persistence/sqlite/label_repo.rs
+
+use super::db::Db;
+use crate::domain::label::{Label, LabelOutcome};
+use crate::domain::repository::LabelRepository;
+use crate::domain::scoring::Score;
+use crate::state_machine::event::Event;
+use rusqlite::{Connection, Row, params};
+
+/// Post-settlement labels, joined with the original score at write time so
+/// training jobs can read them without touching the scores table
+pub struct SQLiteLabelRepo {
+    db: Db,
+}
+
+impl SQLiteLabelRepo {
+    pub fn new(db_path: &str) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_table(&conn);
+        Self::shared(Db::single(conn))
+    }
+
+    /// A view on a connection owned by a `SqliteStore`
+    pub(super) fn shared(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+pub(super) fn create_table(conn: &Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS labels (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            tx_id TEXT NOT NULL,
+            outcome TEXT NOT NULL,
+            source TEXT NOT NULL,
+            score REAL,
+            is_fraud INTEGER,
+            labeled_at INTEGER NOT NULL,
+            notes TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_labels_tx ON labels (tx_id);
+        CREATE INDEX IF NOT EXISTS idx_labels_at ON labels (labeled_at);",
+    )
+    .expect("Failed to create labels table");
+}
+
+fn row_to_label(row: &Row) -> rusqlite::Result<Label> {
+    let tx_id: String = row.get(0)?;
+    let outcome: String = row.get(1)?;
+    let source: String = row.get(2)?;
+    let score: Option<f64> = row.get(3)?;
+    let is_fraud: Option<bool> = row.get(4)?;
+    Ok(Label {
+        score: score.zip(is_fraud).map(|(score, is_fraud)| Score {
+            id: tx_id.clone(),
+            score,
+            is_fraud,
+        }),
+        tx_id,
+        outcome: LabelOutcome::from_name(&outcome).unwrap_or_else(|| panic!("Unknown label outcome in DB: {outcome}")),
+        source: Event::from_name(&source).unwrap_or_else(|| panic!("Unknown event in DB: {source}")),
+        labeled_at: row.get(5)?,
+        notes: row.get(6)?,
+    })
+}
+
+/// Meant to run inside the caller's SQL transaction when the label goes with other writes
+pub(super) fn insert_on(conn: &Connection, label: &Label) {
+    conn.prepare_cached(
+        "INSERT INTO labels (tx_id, outcome, source, score, is_fraud, labeled_at, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )
+    .expect("Failed to prepare label insert")
+    .execute(params![
+        label.tx_id,
+        label.outcome.name(),
+        label.source.name(),
+        label.score.as_ref().map(|s| s.score),
+        label.score.as_ref().map(|s| s.is_fraud),
+        label.labeled_at,
+        label.notes
+    ])
+    .expect("Failed to insert label");
+}
+
+impl LabelRepository for SQLiteLabelRepo {
+    fn save(&self, label: Label) {
+        let conn = self.db.write();
+        insert_on(&conn, &label);
+    }
+
+    fn for_tx(&self, tx_id: &str) -> Vec<Label> {
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT tx_id, outcome, source, score, is_fraud, labeled_at, notes
+                 FROM labels WHERE tx_id = ?1 ORDER BY seq",
+            )
+            .expect("Failed to prepare labels query");
+        stmt.query_map(params![tx_id], row_to_label)
+            .expect("Failed to query labels")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read labels")
+    }
+
+    fn since(&self, since: i64, limit: usize) -> Vec<Label> {
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT tx_id, outcome, source, score, is_fraud, labeled_at, notes
+                 FROM labels WHERE labeled_at >= ?1 ORDER BY labeled_at, seq LIMIT ?2",
+            )
+            .expect("Failed to prepare labels query");
+        stmt.query_map(params![since, limit as i64], row_to_label)
+            .expect("Failed to query labels")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read labels")
+    }
+}
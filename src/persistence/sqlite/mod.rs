@@ -1,5 +1,26 @@
+pub mod db;
+pub mod event_store;
+pub mod export;
+pub mod label_repo;
+pub mod outbox;
+pub mod pool;
+pub mod retention;
 pub mod scoring_repo;
+pub mod state_repo;
+pub mod store;
+pub mod timer_store;
 pub mod transaction_repo;
+pub mod views;
 
+pub use event_store::SQLiteEventStore;
+pub use export::SQLiteExport;
+pub use label_repo::SQLiteLabelRepo;
+pub use outbox::SQLiteOutbox;
+pub use pool::{PoolConfig, SqlitePool};
+pub use retention::SQLiteRetention;
 pub use scoring_repo::SQLiteScoreRepo;
+pub use state_repo::SQLiteStateRepo;
+pub use store::{SqliteStore, StoreConfig, UnitOfWork};
+pub use timer_store::SQLiteTimerStore;
 pub use transaction_repo::SQLiteTransRepo;
+pub use views::SqliteViews;
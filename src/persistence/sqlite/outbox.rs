@@ -0,0 +1,97 @@
+// src/persistence/sqlite/outbox.rs
+
+use super::db::Db;
+use crate::domain::clock::now_millis;
+use crate::domain::repository::Outbox;
+use crate::outbox::OutboxMessage;
+use rusqlite::{Connection, Row, params};
+use tracing::debug;
+
+/// Outbox table. Rows are enqueued by the repositories inside their own SQL transaction
+/// (see `SQLiteScoreRepo::with_outbox`) and kept after delivery for auditing.
+pub struct SQLiteOutbox {
+    db: Db,
+}
+
+pub(crate) fn create_table(conn: &Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS outbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            topic TEXT NOT NULL,
+            key TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            delivered_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_outbox_pending ON outbox (delivered_at, id);",
+    )
+    .expect("Failed to create outbox table");
+}
+
+/// Inserts `message` (its `id` is ignored) on `conn`, meant to run inside the caller's SQL transaction
+pub(crate) fn enqueue(conn: &Connection, message: &OutboxMessage, created_at: i64) {
+    conn.prepare_cached("INSERT INTO outbox (topic, key, payload, created_at) VALUES (?1, ?2, ?3, ?4)")
+        .expect("Failed to prepare outbox insert")
+        .execute(params![message.topic, message.key, message.payload, created_at])
+        .expect("Failed to enqueue outbox message");
+}
+
+impl SQLiteOutbox {
+    pub fn new(db_path: &str) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_table(&conn);
+        Self::shared(Db::single(conn))
+    }
+
+    pub(crate) fn shared(db: Db) -> Self {
+        Self { db }
+    }
+
+    /// Enqueues `message` on its own, for producers without a repository of their own
+    pub fn enqueue(&self, message: &OutboxMessage) {
+        let conn = self.db.write();
+        enqueue(&conn, message, now_millis());
+    }
+}
+
+fn row_to_message(row: &Row) -> rusqlite::Result<OutboxMessage> {
+    Ok(OutboxMessage {
+        id: row.get(0)?,
+        topic: row.get(1)?,
+        key: row.get(2)?,
+        payload: row.get(3)?,
+        created_at: row.get(4)?,
+        attempts: row.get(5)?,
+    })
+}
+
+impl Outbox for SQLiteOutbox {
+    fn pending(&self, limit: usize) -> Vec<OutboxMessage> {
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT id, topic, key, payload, created_at, attempts FROM outbox
+                 WHERE delivered_at IS NULL ORDER BY id LIMIT ?1",
+            )
+            .expect("Failed to prepare outbox query");
+        stmt.query_map(params![limit as i64], row_to_message)
+            .expect("Failed to query outbox")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read outbox")
+    }
+
+    fn mark_delivered(&self, id: i64) {
+        let conn = self.db.write();
+        conn.execute("UPDATE outbox SET delivered_at = ?2, attempts = attempts + 1 WHERE id = ?1", params![id, now_millis()])
+            .expect("Failed to mark outbox message");
+        debug!(id, "Outbox message delivered");
+    }
+
+    fn record_failure(&self, id: i64, error: &str) {
+        let conn = self.db.write();
+        conn.execute("UPDATE outbox SET attempts = attempts + 1, last_error = ?2 WHERE id = ?1", params![id, error])
+            .expect("Failed to record outbox failure");
+    }
+}
@@ -0,0 +1,95 @@
+// src/persistence/sqlite/pool.rs
+
+// One writer connection and a pool of read-only connections on a WAL database. SQLite allows a single
+// writer anyway; with WAL, readers see the last committed data without waiting for it, so `get`/`query`
+// calls from an API no longer queue behind the workers' inserts.
+
+use super::db::Db;
+use super::store::configure;
+use super::views::{self, Source, ViewSource};
+use crate::domain::repository::DEFAULT_DEDUP_WINDOW;
+use crate::security::ColumnEncryption;
+use rusqlite::{Connection, OpenFlags};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Read-only connections, readers wait when all are busy
+    pub readers: usize,
+    /// How long a connection waits for a lock before failing
+    pub busy_timeout: Duration,
+    /// Prepared statements kept per connection
+    pub statement_cache: usize,
+    /// Fraud scores also enqueue an alert, see `SQLiteScoreRepo::with_outbox`
+    pub outbox: bool,
+    pub dedup_window: Duration,
+    /// Transaction columns stored encrypted, see `SQLiteTransRepo::with_encryption`
+    pub encryption: Option<ColumnEncryption>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            readers: 4,
+            busy_timeout: Duration::from_secs(5),
+            statement_cache: 64,
+            outbox: false,
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+            encryption: None,
+        }
+    }
+}
+
+pub struct SqlitePool {
+    db: Db,
+    config: PoolConfig,
+}
+
+impl SqlitePool {
+    pub fn open(db_path: &str) -> Self {
+        Self::open_with(db_path, PoolConfig::default())
+    }
+
+    /// Panics on `:memory:`: every connection would see its own empty database
+    pub fn open_with(db_path: &str, config: PoolConfig) -> Self {
+        assert!(db_path != ":memory:", "SqlitePool needs a database file");
+        assert!(config.readers > 0, "SqlitePool needs at least one reader");
+
+        let writer = Connection::open(db_path).expect("Failed to open SQLite DB");
+        configure(&writer, config.busy_timeout, true);
+        writer.set_prepared_statement_cache_capacity(config.statement_cache);
+        // The schema must exist before read-only connections prepare statements on it
+        views::create_schema(&writer, config.outbox);
+
+        let readers = (0..config.readers)
+            .map(|_| {
+                let reader = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+                    .expect("Failed to open SQLite reader");
+                reader.busy_timeout(config.busy_timeout).expect("Failed to set busy timeout");
+                reader.set_prepared_statement_cache_capacity(config.statement_cache);
+                reader
+            })
+            .collect();
+
+        Self {
+            db: Db::pooled(writer, readers),
+            config,
+        }
+    }
+
+    /// Readers not in use right now
+    pub fn idle_readers(&self) -> usize {
+        self.db.idle_readers()
+    }
+}
+
+impl Source for SqlitePool {
+    fn view_source(&self) -> ViewSource<'_> {
+        ViewSource {
+            db: &self.db,
+            outbox: self.config.outbox,
+            dedup_window: self.config.dedup_window,
+            encryption: self.config.encryption.as_ref(),
+        }
+    }
+}
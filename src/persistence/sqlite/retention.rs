@@ -0,0 +1,145 @@
+// src/persistence/sqlite/retention.rs
+
+use super::db::Db;
+use super::views;
+use crate::domain::repository::RetentionStore;
+use crate::domain::retention::{ExpiredRow, RetentionTable};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use crate::state_machine::transitions::{StateId, standard_table};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use std::sync::LazyLock;
+use tracing::debug;
+
+// Fraud is decided by the score, so transactions are checked against scoring_results
+const NOT_FRAUD_TX: &str = "NOT EXISTS (SELECT 1 FROM scoring_results s WHERE s.tx_id = t.id AND s.is_fraud = 1)";
+
+// States a transaction can be purged in: the terminal ones of the lifecycle, e.g. not under review or disputed
+static TERMINAL_STATES: LazyLock<String> = LazyLock::new(|| {
+    let table = standard_table();
+    StateId::ALL.iter().filter(|s| table.is_terminal(**s)).map(|s| format!("'{}'", s.name())).collect::<Vec<_>>().join(", ")
+});
+
+/// Condition on the transaction id `column`: no state stored, or a terminal one
+fn settled(column: &str) -> String {
+    format!("NOT EXISTS (SELECT 1 FROM tx_states st WHERE st.tx_id = {column} AND st.state NOT IN ({}))", *TERMINAL_STATES)
+}
+
+/// Expired rows of the `transactions` and `scoring_results` tables, of settled transactions only: a row
+/// whose transaction is still in a non-terminal state is kept whatever its age.
+/// Purging a transaction also deletes its timers and its delivered outbox messages. Its state and
+/// transitions are kept so late feedback (a chargeback months later) still applies, and so are its labels.
+/// Rows are returned as stored: encrypted columns stay encrypted. The event store is append-only and is never purged.
+pub struct SQLiteRetention {
+    db: Db,
+}
+
+impl SQLiteRetention {
+    pub fn new(db_path: &str) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        views::create_schema(&conn, false);
+        Self::shared(Db::single(conn))
+    }
+
+    /// A view on a connection owned by a `SqliteStore`
+    pub(super) fn shared(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+/// Deletes the rows of `name` whose `key` is in `ids` and that match `condition`
+fn delete_where(conn: &Connection, name: &str, key: &str, ids: &[String], condition: &str) -> usize {
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    conn.execute(
+        &format!("DELETE FROM {name} WHERE {key} IN ({placeholders}) AND {condition}"),
+        params_from_iter(ids.iter().map(|id| Value::Text(id.clone()))),
+    )
+    .expect("Failed to purge rows")
+}
+
+fn has_table(conn: &Connection, name: &str) -> bool {
+    conn.query_row("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1", params![name], |_| Ok(()))
+        .optional()
+        .expect("Failed to read schema")
+        .is_some()
+}
+
+impl RetentionStore for SQLiteRetention {
+    fn expired(&self, table: RetentionTable, before: i64, keep_fraud: bool, limit: usize) -> Vec<ExpiredRow> {
+        let conn = self.db.read();
+        match table {
+            RetentionTable::Transactions => {
+                let fraud = if keep_fraud { format!("AND {NOT_FRAUD_TX}") } else { String::new() };
+                let settled = settled("t.id");
+                let mut stmt = conn
+                    .prepare(&format!(
+                        "SELECT t.id, t.amount, t.currency, t.merchant_id, t.account_id, t.received_at FROM transactions t
+                         WHERE t.received_at < ?1 AND {settled} {fraud} ORDER BY t.received_at, t.id LIMIT ?2"
+                    ))
+                    .expect("Failed to prepare expired transactions query");
+                stmt.query_map(params![before, limit as i64], |row| {
+                    Ok(ExpiredRow::Transaction {
+                        transaction: Transaction {
+                            id: row.get(0)?,
+                            amount: row.get(1)?,
+                            currency: row.get(2)?,
+                            merchant_id: row.get(3)?,
+                            account_id: row.get(4)?,
+                        },
+                        received_at: row.get(5)?,
+                    })
+                })
+                .expect("Failed to query expired transactions")
+                .collect::<Result<_, _>>()
+                .expect("Failed to read expired transactions")
+            }
+            RetentionTable::Scores => {
+                let fraud = if keep_fraud { "AND r.is_fraud = 0" } else { "" };
+                let settled = settled("r.tx_id");
+                let mut stmt = conn
+                    .prepare(&format!(
+                        "SELECT r.tx_id, r.score, r.is_fraud, r.scored_at FROM scoring_results r
+                         WHERE r.scored_at < ?1 AND {settled} {fraud} ORDER BY r.scored_at, r.tx_id LIMIT ?2"
+                    ))
+                    .expect("Failed to prepare expired scores query");
+                stmt.query_map(params![before, limit as i64], |row| {
+                    Ok(ExpiredRow::Score {
+                        score: Score {
+                            id: row.get(0)?,
+                            score: row.get(1)?,
+                            is_fraud: row.get::<_, i32>(2)? != 0,
+                        },
+                        scored_at: row.get(3)?,
+                    })
+                })
+                .expect("Failed to query expired scores")
+                .collect::<Result<_, _>>()
+                .expect("Failed to read expired scores")
+            }
+        }
+    }
+
+    fn delete(&self, table: RetentionTable, ids: &[String]) -> usize {
+        if ids.is_empty() {
+            return 0;
+        }
+        let mut conn = self.db.write();
+        let sql_tx = conn.transaction().expect("Failed to begin transaction");
+        let deleted = match table {
+            RetentionTable::Transactions => {
+                let deleted = delete_where(&sql_tx, "transactions", "id", ids, "1 = 1");
+                delete_where(&sql_tx, "timers", "tx_id", ids, "1 = 1");
+                // Only created by stores opened with an outbox, pending alerts still have to go out
+                if has_table(&sql_tx, "outbox") {
+                    delete_where(&sql_tx, "outbox", "key", ids, "delivered_at IS NOT NULL");
+                }
+                deleted
+            }
+            RetentionTable::Scores => delete_where(&sql_tx, "scoring_results", "tx_id", ids, "1 = 1"),
+        };
+        sql_tx.commit().expect("Failed to commit purge");
+        debug!(table = table.name(), deleted, "Purged rows");
+        deleted
+    }
+}
@@ -1,48 +1,123 @@
 // src/persistence/sqlite/scoring_repo.rs
 
+use super::db::{Db, has_column};
+use super::outbox::{self, SQLiteOutbox};
+use crate::domain::clock::now_millis;
+use crate::domain::query::{Cursor, Page, ScoreQuery};
 use crate::domain::repository::ScoreRepository;
 use crate::domain::scoring::Score;
-use rusqlite::{Connection, params};
-use std::sync::Mutex;
+use crate::outbox::FraudAlert;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
 use tracing::debug;
 
 pub struct SQLiteScoreRepo {
     // conn: Connection, Not thread safe
-    conn: Mutex<Connection>,
+    // Shared with the outbox so both are written in one SQL transaction
+    db: Db,
+    outbox: bool,
 }
 // SQLiteTransactionRepo
 impl SQLiteScoreRepo {
     pub fn new(db_path: &str) -> Self {
+        Self::open(db_path, false)
+    }
+
+    /// Same as `new`, and a fraud score also enqueues a `FraudAlert` in the outbox table, in the same
+    /// SQL transaction, unless the transaction was already scored as fraud. Publish them with `outbox()`
+    /// and an `OutboxRelay`.
+    pub fn with_outbox(db_path: &str) -> Self {
+        Self::open(db_path, true)
+    }
+
+    /// The outbox sharing this repository's connection
+    pub fn outbox(&self) -> SQLiteOutbox {
+        SQLiteOutbox::shared(self.db.clone())
+    }
+
+    fn open(db_path: &str, with_outbox: bool) -> Self {
         let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_tables(&conn);
+        if with_outbox {
+            outbox::create_table(&conn);
+        }
+        Self::shared(Db::single(conn), with_outbox)
+    }
 
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS scoring_results (
-                tx_id TEXT PRIMARY KEY,
-                score REAL NOT NULL,
-                is_fraud INTEGER NOT NULL
-            )",
-            [],
-        )
-        .expect("Failed to create scoring_results table");
+    /// A view on a connection owned by a `SqliteStore`
+    pub(super) fn shared(db: Db, with_outbox: bool) -> Self {
+        Self { db, outbox: with_outbox }
+    }
+}
+
+pub(super) fn create_tables(conn: &Connection) {
+    // scored_at (ms since epoch) drives retention
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scoring_results (
+            tx_id TEXT PRIMARY KEY,
+            score REAL NOT NULL,
+            is_fraud INTEGER NOT NULL,
+            scored_at INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .expect("Failed to create scoring_results table");
+    add_scored_at(conn);
+    // Review queue order, with or without the fraud filter
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_scoring_results_score ON scoring_results (score DESC, tx_id);
+        CREATE INDEX IF NOT EXISTS idx_scoring_results_fraud ON scoring_results (is_fraud, score DESC, tx_id);
+        CREATE INDEX IF NOT EXISTS idx_scoring_results_scored ON scoring_results (scored_at, tx_id);",
+    )
+    .expect("Failed to create scoring_results indexes");
+}
+
+// Databases created before scored_at existed: their scores start their retention period now
+fn add_scored_at(conn: &Connection) {
+    if !has_column(conn, "scoring_results", "scored_at") {
+        conn.execute_batch("ALTER TABLE scoring_results ADD COLUMN scored_at INTEGER NOT NULL DEFAULT 0")
+            .expect("Failed to add scored_at");
+        conn.execute("UPDATE scoring_results SET scored_at = ?1", params![now_millis()])
+            .expect("Failed to backfill scored_at");
+    }
+}
 
-        Self { conn: Mutex::new(conn) }
+/// Saves `result`, a rescore updates the row only if the score changed and keeps its `scored_at`.
+/// The alert is enqueued when a fraud score is new or turns a clean one into fraud, not on every save.
+pub(super) fn insert(conn: &Connection, result: &Score, with_outbox: bool) {
+    let now = now_millis();
+    let was_fraud: Option<bool> = conn
+        .prepare_cached("SELECT is_fraud FROM scoring_results WHERE tx_id = ?1")
+        .expect("Failed to prepare scoring lookup")
+        .query_row(params![result.id], |row| row.get::<_, i32>(0))
+        .optional()
+        .expect("Failed to read scoring result")
+        .map(|is_fraud| is_fraud != 0);
+    conn.prepare_cached(
+        "INSERT INTO scoring_results (tx_id, score, is_fraud, scored_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (tx_id) DO UPDATE SET score = excluded.score, is_fraud = excluded.is_fraud
+         WHERE score IS NOT excluded.score OR is_fraud IS NOT excluded.is_fraud",
+    )
+    .expect("Failed to prepare scoring upsert")
+    .execute(params![result.id, result.score, result.is_fraud as i32, now])
+    .expect("Failed to save scoring result");
+    if with_outbox && result.is_fraud && was_fraud != Some(true) {
+        outbox::enqueue(conn, &FraudAlert::from_score(result).into_message(), now);
     }
 }
 
 impl ScoreRepository for SQLiteScoreRepo {
     fn save(&self, result: Score) {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO scoring_results (tx_id, score, is_fraud) VALUES (?1, ?2, ?3)",
-            params![result.id, result.score, result.is_fraud as i32],
-        )
-        .expect("Failed to insert scoring result");
+        let mut conn = self.db.write();
+        let sql_tx = conn.transaction().expect("Failed to begin transaction");
+        insert(&sql_tx, &result, self.outbox);
+        sql_tx.commit().expect("Failed to commit scoring result");
         debug!(tx_id = %result.id, "Saved scoring to SQLite");
     }
 
     fn get(&self, id: &str) -> Option<Score> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT id, score, is_fraud FROM scoring_results WHERE id = ?1").ok()?;
+        let conn = self.db.read();
+        let mut stmt = conn.prepare_cached("SELECT tx_id, score, is_fraud FROM scoring_results WHERE tx_id = ?1").ok()?;
 
         let mut rows = stmt.query(params![id]).ok()?;
 
@@ -57,4 +132,70 @@ impl ScoreRepository for SQLiteScoreRepo {
             None
         }
     }
+
+    fn save_batch(&self, results: Vec<Score>) {
+        let mut conn = self.db.write();
+        let sql_tx = conn.transaction().expect("Failed to begin transaction");
+        for result in &results {
+            insert(&sql_tx, result, self.outbox);
+        }
+        sql_tx.commit().expect("Failed to commit scoring batch");
+        debug!(count = results.len(), "Saved scoring batch to SQLite");
+    }
+
+    fn query(&self, query: &ScoreQuery) -> Page<Score> {
+        let (mut clause, mut values) = filters(query);
+        if let Some(cursor) = &query.after {
+            let Some((score, id)) = cursor.decode::<f64>() else {
+                return Page::default();
+            };
+            clause.push_str(" AND (score < ? OR (score = ? AND tx_id > ?))");
+            values.extend([Value::Real(score), Value::Real(score), Value::Text(id.to_string())]);
+        }
+        let limit = query.page_size();
+        values.push(Value::Integer(limit as i64 + 1));
+
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached(&format!("SELECT tx_id, score, is_fraud FROM scoring_results WHERE {clause} ORDER BY score DESC, tx_id LIMIT ?"))
+            .expect("Failed to prepare scoring query");
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(Score {
+                    id: row.get(0)?,
+                    score: row.get(1)?,
+                    is_fraud: row.get::<_, i32>(2)? != 0,
+                })
+            })
+            .expect("Failed to query scoring results")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read scoring results");
+        Page::from_overfetch(rows, limit, |s: &Score| Cursor::new(s.score, &s.id))
+    }
+
+    fn count(&self, query: &ScoreQuery) -> usize {
+        let (clause, values) = filters(query);
+        let conn = self.db.read();
+        conn.query_row(&format!("SELECT COUNT(*) FROM scoring_results WHERE {clause}"), params_from_iter(values), |row| row.get::<_, i64>(0))
+            .expect("Failed to count scoring results") as usize
+    }
+}
+
+/// WHERE clause and parameters for the filters of `query`
+fn filters(query: &ScoreQuery) -> (String, Vec<Value>) {
+    let mut clause = String::from("1 = 1");
+    let mut values = Vec::new();
+    if let Some(is_fraud) = query.is_fraud {
+        clause.push_str(" AND is_fraud = ?");
+        values.push(Value::Integer(is_fraud as i64));
+    }
+    if let Some(min) = query.min_score {
+        clause.push_str(" AND score >= ?");
+        values.push(Value::Real(min));
+    }
+    if let Some(max) = query.max_score {
+        clause.push_str(" AND score <= ?");
+        values.push(Value::Real(max));
+    }
+    (clause, values)
 }
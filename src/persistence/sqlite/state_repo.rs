@@ -0,0 +1,154 @@
+// src/persistence/sqlite/state_repo.rs
+
+use super::db::Db;
+use crate::domain::repository::StateRepository;
+use crate::state_machine::event::Event;
+use crate::state_machine::transitions::{StateId, TransitionRecord};
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior, params};
+use tracing::debug;
+
+/// Current state per transaction plus an append-only transition history
+pub struct SQLiteStateRepo {
+    db: Db,
+}
+
+impl SQLiteStateRepo {
+    pub fn new(db_path: &str) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_tables(&conn);
+        Self::shared(Db::single(conn))
+    }
+
+    /// A view on a connection owned by a `SqliteStore`
+    pub(super) fn shared(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+pub(super) fn create_tables(conn: &Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tx_states (
+            tx_id TEXT PRIMARY KEY,
+            state TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_tx_states_state ON tx_states (state);
+        CREATE TABLE IF NOT EXISTS state_transitions (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            tx_id TEXT NOT NULL,
+            from_state TEXT NOT NULL,
+            to_state TEXT NOT NULL,
+            event TEXT NOT NULL,
+            at INTEGER NOT NULL,
+            actor TEXT,
+            notes TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_state_transitions_tx ON state_transitions (tx_id, seq);",
+    )
+    .expect("Failed to create state tables");
+}
+
+fn parse_state(name: String) -> StateId {
+    StateId::from_name(&name).unwrap_or_else(|| panic!("Unknown state in DB: {name}"))
+}
+
+impl StateRepository for SQLiteStateRepo {
+    fn save_state(&self, tx_id: &str, state: StateId) {
+        let conn = self.db.write();
+        save_state_on(&conn, tx_id, state);
+    }
+
+    fn get_state(&self, tx_id: &str) -> Option<StateId> {
+        state_on(&self.db.read(), tx_id)
+    }
+
+    fn record_transition(&self, record: TransitionRecord) {
+        let mut conn = self.db.write();
+        let sql_tx = conn.transaction().expect("Failed to begin transaction");
+        record_on(&sql_tx, &record);
+        sql_tx.commit().expect("Failed to commit transition");
+        debug!(tx_id = %record.tx_id, from = %record.from, to = %record.to, "Recorded transition to SQLite");
+    }
+
+    fn record_transitions_if(&self, expected: Option<StateId>, records: &[TransitionRecord]) -> bool {
+        let mut conn = self.db.write();
+        // Takes the write lock before the check, so no other connection moves the state in between
+        let sql_tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).expect("Failed to begin transaction");
+        if !record_if_on(&sql_tx, expected, records) {
+            return false;
+        }
+        sql_tx.commit().expect("Failed to commit transitions");
+        true
+    }
+
+    fn history(&self, tx_id: &str) -> Vec<TransitionRecord> {
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached("SELECT tx_id, from_state, to_state, event, at, actor, notes FROM state_transitions WHERE tx_id = ?1 ORDER BY seq")
+            .expect("Failed to prepare history query");
+        stmt.query_map(params![tx_id], |row| {
+            let event: String = row.get(3)?;
+            Ok(TransitionRecord {
+                tx_id: row.get(0)?,
+                from: parse_state(row.get(1)?),
+                to: parse_state(row.get(2)?),
+                event: Event::from_name(&event).unwrap_or_else(|| panic!("Unknown event in DB: {event}")),
+                at: row.get(4)?,
+                actor: row.get(5)?,
+                notes: row.get(6)?,
+            })
+        })
+        .expect("Failed to query history")
+        .collect::<Result<_, _>>()
+        .expect("Failed to read history")
+    }
+
+    fn ids_in_state(&self, state: StateId) -> Vec<String> {
+        let conn = self.db.read();
+        let mut stmt = conn.prepare_cached("SELECT tx_id FROM tx_states WHERE state = ?1 ORDER BY tx_id").expect("Failed to prepare state query");
+        stmt.query_map(params![state.name()], |row| row.get(0))
+            .expect("Failed to query states")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read states")
+    }
+}
+
+fn state_on(conn: &Connection, tx_id: &str) -> Option<StateId> {
+    conn.prepare_cached("SELECT state FROM tx_states WHERE tx_id = ?1")
+        .expect("Failed to prepare state query")
+        .query_row(params![tx_id], |row| row.get::<_, String>(0))
+        .optional()
+        .expect("Failed to read state")
+        .map(parse_state)
+}
+
+pub(super) fn save_state_on(conn: &Connection, tx_id: &str, state: StateId) {
+    conn.prepare_cached("INSERT OR REPLACE INTO tx_states (tx_id, state) VALUES (?1, ?2)")
+        .expect("Failed to prepare state upsert")
+        .execute(params![tx_id, state.name()])
+        .expect("Failed to save state");
+}
+
+/// Appends `record` and moves the current state, meant to run inside the caller's SQL transaction
+pub(super) fn record_on(conn: &Connection, record: &TransitionRecord) {
+    conn.prepare_cached("INSERT INTO state_transitions (tx_id, from_state, to_state, event, at, actor, notes) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")
+        .expect("Failed to prepare transition insert")
+        .execute(params![record.tx_id, record.from.name(), record.to.name(), record.event.name(), record.at, record.actor, record.notes])
+        .expect("Failed to insert transition");
+    save_state_on(conn, &record.tx_id, record.to);
+}
+
+/// `record_on` for each of `records` if the current state is still `expected`, inside the caller's SQL transaction
+pub(super) fn record_if_on(conn: &Connection, expected: Option<StateId>, records: &[TransitionRecord]) -> bool {
+    let Some(first) = records.first() else {
+        return true;
+    };
+    let current = state_on(conn, &first.tx_id);
+    if current != expected {
+        debug!(tx_id = %first.tx_id, ?expected, ?current, "Stored state moved, transitions not recorded");
+        return false;
+    }
+    for record in records {
+        record_on(conn, record);
+    }
+    true
+}
@@ -0,0 +1,162 @@
+// src/persistence/sqlite/store.rs
+
+// One SQLite database, one connection, several repository views. Opening `SQLiteTransRepo` and
+// `SQLiteScoreRepo` separately on the same file gives two connections that can't share a SQL transaction;
+// `SqliteStore` hands out views on a single connection plus a unit of work committing several writes at once.
+
+use super::db::Db;
+use super::views::{self, Source, ViewSource};
+use super::{label_repo, scoring_repo, state_repo, transaction_repo};
+use crate::domain::clock::now_millis;
+use crate::domain::label::Label;
+use crate::domain::repository::{DEFAULT_DEDUP_WINDOW, FeedbackStore, RepoError, SaveOutcome};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use crate::state_machine::transitions::{StateId, TransitionRecord};
+use crate::security::ColumnEncryption;
+use rusqlite::Connection;
+use std::time::Duration;
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+pub struct StoreConfig {
+    /// How long a writer waits for a lock held by another connection before failing
+    pub busy_timeout: Duration,
+    /// Write-ahead log: readers no longer block the writer (no effect on `:memory:`)
+    pub wal: bool,
+    /// Fraud scores also enqueue an alert, see `SQLiteScoreRepo::with_outbox`
+    pub outbox: bool,
+    pub dedup_window: Duration,
+    /// Transaction columns stored encrypted, see `SQLiteTransRepo::with_encryption`
+    pub encryption: Option<ColumnEncryption>,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self {
+            busy_timeout: Duration::from_secs(5),
+            wal: true,
+            outbox: false,
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+            encryption: None,
+        }
+    }
+}
+
+pub struct SqliteStore {
+    db: Db,
+    config: StoreConfig,
+}
+
+impl SqliteStore {
+    pub fn open(db_path: &str) -> Self {
+        Self::open_with(db_path, StoreConfig::default())
+    }
+
+    pub fn open_with(db_path: &str, config: StoreConfig) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        configure(&conn, config.busy_timeout, config.wal);
+
+        views::create_schema(&conn, config.outbox);
+
+        Self { db: Db::single(conn), config }
+    }
+
+    /// "wal", "memory", "delete"...
+    pub fn journal_mode(&self) -> String {
+        let conn = self.db.read();
+        conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).expect("Failed to read journal mode")
+    }
+
+    /// Runs `work` in one SQL transaction: committed if it returns `Ok`, rolled back if it returns `Err`.
+    /// The views of this store wait for the unit of work to finish.
+    pub fn unit_of_work<T, E>(&self, work: impl FnOnce(&UnitOfWork) -> Result<T, E>) -> Result<T, E> {
+        let mut conn = self.db.write();
+        let sql_tx = conn.transaction().expect("Failed to begin transaction");
+        let uow = UnitOfWork {
+            conn: &sql_tx,
+            config: &self.config,
+            now: now_millis(),
+        };
+        let result = work(&uow)?;
+        sql_tx.commit().expect("Failed to commit unit of work");
+        debug!("Committed unit of work");
+        Ok(result)
+    }
+}
+
+impl Source for SqliteStore {
+    fn view_source(&self) -> ViewSource<'_> {
+        ViewSource {
+            db: &self.db,
+            outbox: self.config.outbox,
+            dedup_window: self.config.dedup_window,
+            encryption: self.config.encryption.as_ref(),
+        }
+    }
+}
+
+/// The transition and the label are written in one unit of work
+impl FeedbackStore for SqliteStore {
+    fn record_feedback(&self, record: TransitionRecord, label: Label) -> bool {
+        self.unit_of_work(|uow| {
+            if !uow.record_transition_if(Some(record.from), &record) {
+                return Err(());
+            }
+            uow.save_label(&label);
+            Ok(())
+        })
+        .is_ok()
+    }
+}
+
+pub(super) fn configure(conn: &Connection, busy_timeout: Duration, wal: bool) {
+    conn.busy_timeout(busy_timeout).expect("Failed to set busy timeout");
+    if wal {
+        // Answers with the mode in effect, "memory" for in-memory databases
+        let mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0)).expect("Failed to enable WAL");
+        if mode == "wal" {
+            // Safe with WAL: a crash may lose the last commits, never corrupt the file
+            conn.pragma_update(None, "synchronous", "NORMAL").expect("Failed to set synchronous mode");
+        }
+    }
+}
+
+/// Writes of one `SqliteStore::unit_of_work`, all committed or none
+pub struct UnitOfWork<'a> {
+    conn: &'a Connection,
+    config: &'a StoreConfig,
+    now: i64,
+}
+
+impl UnitOfWork<'_> {
+    pub fn save_transaction(&self, tx: &Transaction) {
+        transaction_repo::insert(self.conn, tx, self.now, self.config.encryption.as_ref());
+    }
+
+    /// Same rules as `TransRepository::save_idempotent`
+    pub fn save_idempotent(&self, tx: Transaction) -> Result<SaveOutcome, RepoError> {
+        transaction_repo::save_idempotent_on(self.conn, tx, self.config.dedup_window, self.now, self.config.encryption.as_ref())
+    }
+
+    pub fn save_score(&self, score: &Score) {
+        scoring_repo::insert(self.conn, score, self.config.outbox);
+    }
+
+    pub fn save_state(&self, tx_id: &str, state: StateId) {
+        state_repo::save_state_on(self.conn, tx_id, state);
+    }
+
+    pub fn record_transition(&self, record: &TransitionRecord) {
+        state_repo::record_on(self.conn, record);
+    }
+
+    /// Same check as `StateRepository::record_transition_if`
+    pub fn record_transition_if(&self, expected: Option<StateId>, record: &TransitionRecord) -> bool {
+        state_repo::record_if_on(self.conn, expected, std::slice::from_ref(record))
+    }
+
+    pub fn save_label(&self, label: &Label) {
+        label_repo::insert_on(self.conn, label);
+    }
+}
@@ -0,0 +1,90 @@
+// src/persistence/sqlite/timer_store.rs
+
+use super::db::Db;
+use crate::domain::repository::TimerStore;
+use crate::state_machine::event::Event;
+use crate::state_machine::timers::Timer;
+use rusqlite::{Connection, Row, params};
+
+/// Durable timers: pending rows survive a restart and fire on the next tick
+pub struct SQLiteTimerStore {
+    db: Db,
+}
+
+impl SQLiteTimerStore {
+    pub fn new(db_path: &str) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_table(&conn);
+        Self::shared(Db::single(conn))
+    }
+
+    /// A view on a connection owned by a `SqliteStore`
+    pub(super) fn shared(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+pub(super) fn create_table(conn: &Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS timers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            tx_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            due_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_timers_due_at ON timers (due_at);
+        CREATE INDEX IF NOT EXISTS idx_timers_tx ON timers (tx_id);",
+    )
+    .expect("Failed to create timers table");
+}
+
+fn row_to_timer(row: &Row) -> rusqlite::Result<Timer> {
+    let event: String = row.get(2)?;
+    Ok(Timer {
+        id: row.get(0)?,
+        tx_id: row.get(1)?,
+        event: Event::from_name(&event).unwrap_or_else(|| panic!("Unknown event in DB: {event}")),
+        due_at: row.get(3)?,
+    })
+}
+
+impl TimerStore for SQLiteTimerStore {
+    fn schedule(&self, tx_id: &str, event: Event, due_at: i64) -> i64 {
+        let conn = self.db.write();
+        conn.execute("INSERT INTO timers (tx_id, event, due_at) VALUES (?1, ?2, ?3)", params![tx_id, event.name(), due_at])
+            .expect("Failed to schedule timer");
+        conn.last_insert_rowid()
+    }
+
+    fn cancel_all(&self, tx_id: &str) {
+        let conn = self.db.write();
+        conn.execute("DELETE FROM timers WHERE tx_id = ?1", params![tx_id]).expect("Failed to cancel timers");
+    }
+
+    fn remove(&self, timer_id: i64) {
+        let conn = self.db.write();
+        conn.execute("DELETE FROM timers WHERE id = ?1", params![timer_id]).expect("Failed to remove timer");
+    }
+
+    fn due(&self, now: i64, limit: usize) -> Vec<Timer> {
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached("SELECT id, tx_id, event, due_at FROM timers WHERE due_at <= ?1 ORDER BY due_at, id LIMIT ?2")
+            .expect("Failed to prepare due timers query");
+        stmt.query_map(params![now, limit as i64], row_to_timer)
+            .expect("Failed to query due timers")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read timers")
+    }
+
+    fn pending(&self, tx_id: &str) -> Vec<Timer> {
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached("SELECT id, tx_id, event, due_at FROM timers WHERE tx_id = ?1 ORDER BY due_at, id")
+            .expect("Failed to prepare pending timers query");
+        stmt.query_map(params![tx_id], row_to_timer)
+            .expect("Failed to query pending timers")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read timers")
+    }
+}
@@ -1,48 +1,231 @@
 // src/persistence/sqlite.rs
 
-use crate::domain::repository::TransRepository;
+use super::db::{Db, has_column};
+use crate::domain::clock::now_millis;
+use crate::domain::query::{Cursor, Page, TransactionQuery};
+use crate::domain::repository::{DEFAULT_DEDUP_WINDOW, RepoError, SaveOutcome, TransRepository};
 use crate::domain::transaction::Transaction;
-use rusqlite::{Connection, /*Result,*/ params};
-use std::sync::Mutex;
+use crate::security::{ColumnEncryption, EncryptedColumn};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, /*Result,*/ params, params_from_iter};
+use std::time::Duration;
 use tracing::debug;
 
 /// SQLite-based implementation of TransactionRepository
 pub struct SQLiteTransRepo {
-    conn: Mutex<Connection>,
+    db: Db,
+    dedup_window: Duration,
+    encryption: Option<ColumnEncryption>,
 }
 
 impl SQLiteTransRepo {
     /// Initialize a new SQLiteTransactionRepo with a DB file or in-memory DB
     pub fn new(db_path: &str) -> Self {
+        Self::with_dedup_window(db_path, DEFAULT_DEDUP_WINDOW)
+    }
+
+    /// Same as `new` but with a custom window for `save_idempotent`
+    pub fn with_dedup_window(db_path: &str, dedup_window: Duration) -> Self {
         let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_tables(&conn);
+        Self::shared(Db::single(conn), dedup_window, None)
+    }
 
-        // Create table if it doesn't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS transactions (
-                id TEXT PRIMARY KEY,
-                amount REAL NOT NULL,
-                currency TEXT NOT NULL
-            )",
-            [],
-        )
-        .expect("Failed to create table");
+    /// Same as `new`, and the columns of `encryption` are stored encrypted.
+    /// Filtering on an encrypted column decrypts and scans the rows matching the other filters.
+    pub fn with_encryption(db_path: &str, encryption: ColumnEncryption) -> Self {
+        let conn = Connection::open(db_path).expect("Failed to open SQLite DB");
+        create_tables(&conn);
+        Self::shared(Db::single(conn), DEFAULT_DEDUP_WINDOW, Some(encryption))
+    }
+
+    /// A view on a connection owned by a `SqliteStore`
+    pub(super) fn shared(db: Db, dedup_window: Duration, encryption: Option<ColumnEncryption>) -> Self {
+        Self { db, dedup_window, encryption }
+    }
+
+    /// Re-encrypts, `batch_size` rows per SQL transaction, the rows written in clear or with an older key.
+    /// Run it after adding a key to the key file; returns the number of rows rewritten.
+    pub fn reencrypt(&self, batch_size: usize) -> usize {
+        let Some(encryption) = &self.encryption else { return 0 };
+        let mut rewritten = 0;
+        let mut after = String::new();
+        loop {
+            let mut conn = self.db.write();
+            let sql_tx = conn.transaction().expect("Failed to begin transaction");
+            let rows: Vec<Transaction> = sql_tx
+                .prepare_cached("SELECT id, amount, currency, merchant_id, account_id FROM transactions WHERE id > ?1 ORDER BY id LIMIT ?2")
+                .expect("Failed to prepare re-encryption scan")
+                .query_map(params![after, batch_size.max(1) as i64], row_to_tx)
+                .expect("Failed to scan transactions")
+                .collect::<Result<_, _>>()
+                .expect("Failed to read transactions");
+            let Some(last) = rows.last() else { break };
+            after = last.id.clone();
+            for stored in rows {
+                let stale = encryption.columns.iter().any(|c| encryption.cipher.needs_reencryption(column_value(&stored, *c)));
+                if stale {
+                    let tx = decrypt(Some(encryption), stored);
+                    let sealed = encryption.encrypt_tx(&tx);
+                    sql_tx
+                        .execute(
+                            "UPDATE transactions SET merchant_id = ?2, account_id = ?3 WHERE id = ?1",
+                            params![sealed.id, sealed.merchant_id, sealed.account_id],
+                        )
+                        .expect("Failed to re-encrypt transaction");
+                    rewritten += 1;
+                }
+            }
+            sql_tx.commit().expect("Failed to commit re-encryption batch");
+        }
+        debug!(rewritten, "Re-encrypted transactions");
+        rewritten
+    }
+
+    /// Transactions matching the SQL-side filters of `query`, decrypted, with the cursor of the page
+    fn fetch(&self, query: &TransactionQuery) -> Page<(Transaction, i64)> {
+        let (mut clause, mut values) = filters(query);
+        if let Some(cursor) = &query.after {
+            let Some((at, id)) = cursor.decode::<i64>() else {
+                return Page::default();
+            };
+            clause.push_str(" AND (received_at > ? OR (received_at = ? AND id > ?))");
+            values.extend([Value::Integer(at), Value::Integer(at), Value::Text(id.to_string())]);
+        }
+        let limit = query.page_size();
+        values.push(Value::Integer(limit as i64 + 1));
+
+        let conn = self.db.read();
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT id, amount, currency, merchant_id, account_id, received_at FROM transactions WHERE {clause} ORDER BY received_at, id LIMIT ?"
+            ))
+            .expect("Failed to prepare transaction query");
+        let rows: Vec<(Transaction, i64)> = stmt
+            .query_map(params_from_iter(values), |row| Ok((row_to_tx(row)?, row.get(5)?)))
+            .expect("Failed to query transactions")
+            .collect::<Result<_, _>>()
+            .expect("Failed to read transactions");
+        let rows = rows.into_iter().map(|(tx, at)| (decrypt(self.encryption.as_ref(), tx), at)).collect();
+        Page::from_overfetch(rows, limit, |(tx, at)| Cursor::new(at, &tx.id))
+    }
+
+    /// `query` without its filters on encrypted columns, `None` when it has none
+    fn without_encrypted_filters(&self, query: &TransactionQuery) -> Option<TransactionQuery> {
+        let encryption = self.encryption.as_ref()?;
+        let mut sql_query = query.clone();
+        if encryption.encrypts(EncryptedColumn::MerchantId) {
+            sql_query.merchant_id = None;
+        }
+        if encryption.encrypts(EncryptedColumn::AccountId) {
+            sql_query.account_id = None;
+        }
+        (sql_query != *query).then_some(sql_query)
+    }
+
+    /// Rows matching every filter of `query` after decryption, pages of `SCAN_PAGE` from SQLite at a time
+    fn scan(&self, query: &TransactionQuery, mut sql_query: TransactionQuery, max: usize) -> Vec<(Transaction, i64)> {
+        sql_query.limit = SCAN_PAGE;
+        let mut found = Vec::new();
+        loop {
+            let page = self.fetch(&sql_query);
+            found.extend(page.items.into_iter().filter(|(tx, at)| query.matches(tx, *at)));
+            match page.next {
+                Some(cursor) if found.len() < max => sql_query.after = Some(cursor),
+                _ => break,
+            }
+        }
+        found.truncate(max);
+        found
+    }
+}
+
+/// Rows read per round when filtering on encrypted columns
+const SCAN_PAGE: usize = 500;
+
+fn row_to_tx(row: &rusqlite::Row) -> rusqlite::Result<Transaction> {
+    Ok(Transaction {
+        id: row.get(0)?,
+        amount: row.get(1)?,
+        currency: row.get(2)?,
+        merchant_id: row.get(3)?,
+        account_id: row.get(4)?,
+    })
+}
+
+fn column_value(tx: &Transaction, column: EncryptedColumn) -> &str {
+    match column {
+        EncryptedColumn::MerchantId => &tx.merchant_id,
+        EncryptedColumn::AccountId => &tx.account_id,
+    }
+}
+
+fn decrypt(encryption: Option<&ColumnEncryption>, stored: Transaction) -> Transaction {
+    match encryption {
+        Some(encryption) => encryption.decrypt_tx(stored).expect("Failed to decrypt transaction"),
+        None => stored,
+    }
+}
+
+pub(super) fn create_tables(conn: &Connection) {
+    // Create table if it doesn't exist
+    // received_at (ms since epoch) drives the dedup window
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transactions (
+            id TEXT PRIMARY KEY,
+            amount REAL NOT NULL,
+            currency TEXT NOT NULL,
+            merchant_id TEXT NOT NULL DEFAULT '',
+            account_id TEXT NOT NULL DEFAULT '',
+            received_at INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .expect("Failed to create table");
+    add_received_at(conn);
+    add_identifier_columns(conn);
 
-        Self { conn: Mutex::new(conn) }
+    // Keyset pagination and the usual dashboard filters
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_transactions_received ON transactions (received_at, id);
+        CREATE INDEX IF NOT EXISTS idx_transactions_currency ON transactions (currency, received_at, id);
+        CREATE INDEX IF NOT EXISTS idx_transactions_merchant ON transactions (merchant_id, received_at, id);
+        CREATE INDEX IF NOT EXISTS idx_transactions_account ON transactions (account_id, received_at, id);",
+    )
+    .expect("Failed to create transaction indexes");
+}
+
+// Databases created before received_at existed: their rows start their dedup window and retention period now
+fn add_received_at(conn: &Connection) {
+    if !has_column(conn, "transactions", "received_at") {
+        conn.execute_batch("ALTER TABLE transactions ADD COLUMN received_at INTEGER NOT NULL DEFAULT 0")
+            .expect("Failed to add received_at");
+        conn.execute("UPDATE transactions SET received_at = ?1", params![now_millis()])
+            .expect("Failed to backfill received_at");
+    }
+}
+
+// Databases created before the merchant and account ids existed: their rows keep empty identifiers
+fn add_identifier_columns(conn: &Connection) {
+    for column in ["merchant_id", "account_id"] {
+        if !has_column(conn, "transactions", column) {
+            conn.execute_batch(&format!("ALTER TABLE transactions ADD COLUMN {column} TEXT NOT NULL DEFAULT ''"))
+                .expect("Failed to add identifier column");
+        }
     }
 }
 
 impl TransRepository for SQLiteTransRepo {
     fn save(&self, tx: Transaction) {
-        let conn = self.conn.lock().unwrap();
-        conn.execute("INSERT OR REPLACE INTO transactions (id, amount, currency) VALUES (?1, ?2, ?3)", params![tx.id, tx.amount, tx.currency])
-            .expect("Failed to insert transaction");
+        let conn = self.db.write();
+        insert(&conn, &tx, now_millis(), self.encryption.as_ref());
 
         debug!(tx_id = %tx.id, "Saved transaction to SQLite");
     }
 
     fn get(&self, id: &str) -> Option<Transaction> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT id, amount, currency FROM transactions WHERE id = ?1").ok()?;
+        let conn = self.db.read();
+        let mut stmt = conn.prepare_cached("SELECT id, amount, currency, merchant_id, account_id FROM transactions WHERE id = ?1").ok()?;
 
         let mut rows = stmt.query(params![id]).ok()?;
 
@@ -51,10 +234,136 @@ impl TransRepository for SQLiteTransRepo {
                 id: row.get(0).unwrap(),
                 amount: row.get(1).unwrap(),
                 currency: row.get(2).unwrap(),
+                merchant_id: row.get(3).unwrap(),
+                account_id: row.get(4).unwrap(),
             };
-            Some(tx)
+            Some(decrypt(self.encryption.as_ref(), tx))
         } else {
             None
         }
     }
+
+    fn save_idempotent(&self, tx: Transaction) -> Result<SaveOutcome, RepoError> {
+        // The lock is held for the whole read-compare-write so two workers can't both insert
+        let conn = self.db.write();
+        save_idempotent_on(&conn, tx, self.dedup_window, now_millis(), self.encryption.as_ref())
+    }
+
+    fn save_idempotent_batch(&self, txs: Vec<Transaction>) -> Vec<Result<SaveOutcome, RepoError>> {
+        let now = now_millis();
+        let mut conn = self.db.write();
+
+        // One SQLite transaction for the whole batch: a single fsync instead of one per row
+        let sql_tx = conn.transaction().expect("Failed to begin transaction");
+        let outcomes = txs
+            .into_iter()
+            .map(|tx| save_idempotent_on(&sql_tx, tx, self.dedup_window, now, self.encryption.as_ref()))
+            .collect();
+        sql_tx.commit().expect("Failed to commit transaction batch");
+        outcomes
+    }
+
+    fn query(&self, query: &TransactionQuery) -> Page<Transaction> {
+        let page = match self.without_encrypted_filters(query) {
+            None => self.fetch(query),
+            Some(sql_query) => {
+                let limit = query.page_size();
+                Page::from_overfetch(self.scan(query, sql_query, limit + 1), limit, |(tx, at)| Cursor::new(at, &tx.id))
+            }
+        };
+        Page {
+            items: page.items.into_iter().map(|(tx, _)| tx).collect(),
+            next: page.next,
+        }
+    }
+
+    fn count(&self, query: &TransactionQuery) -> usize {
+        if let Some(mut sql_query) = self.without_encrypted_filters(query) {
+            sql_query.after = None;
+            return self.scan(query, sql_query, usize::MAX).len();
+        }
+        let (clause, values) = filters(query);
+        let conn = self.db.read();
+        conn.query_row(&format!("SELECT COUNT(*) FROM transactions WHERE {clause}"), params_from_iter(values), |row| row.get::<_, i64>(0))
+            .expect("Failed to count transactions") as usize
+    }
+}
+
+/// WHERE clause and parameters for the filters of `query`
+pub(super) fn filters(query: &TransactionQuery) -> (String, Vec<Value>) {
+    let mut clause = String::from("1 = 1");
+    let mut values = Vec::new();
+    if let Some(from) = query.received_from {
+        clause.push_str(" AND received_at >= ?");
+        values.push(Value::Integer(from));
+    }
+    if let Some(to) = query.received_to {
+        clause.push_str(" AND received_at < ?");
+        values.push(Value::Integer(to));
+    }
+    for (column, filter) in [("currency", &query.currency), ("merchant_id", &query.merchant_id), ("account_id", &query.account_id)] {
+        if let Some(value) = filter {
+            clause.push_str(&format!(" AND {column} = ?"));
+            values.push(Value::Text(value.clone()));
+        }
+    }
+    (clause, values)
+}
+
+pub(super) fn save_idempotent_on(
+    conn: &Connection,
+    tx: Transaction,
+    dedup_window: Duration,
+    now: i64,
+    encryption: Option<&ColumnEncryption>,
+) -> Result<SaveOutcome, RepoError> {
+    let existing = conn
+        .query_row(
+            "SELECT id, amount, currency, merchant_id, account_id, received_at FROM transactions WHERE id = ?1",
+            params![tx.id],
+            |row| {
+                Ok((
+                    Transaction {
+                        id: row.get(0)?,
+                        amount: row.get(1)?,
+                        currency: row.get(2)?,
+                        merchant_id: row.get(3)?,
+                        account_id: row.get(4)?,
+                    },
+                    row.get::<_, i64>(5)?,
+                ))
+            },
+        )
+        .optional()
+        .expect("Failed to read transaction");
+
+    if let Some((existing, received_at)) = existing
+        && now - received_at < dedup_window.as_millis() as i64
+    {
+        if decrypt(encryption, existing) == tx {
+            return Ok(SaveOutcome::Duplicate);
+        }
+        return Err(RepoError::Conflict { id: tx.id });
+    }
+
+    insert(conn, &tx, now, encryption);
+
+    debug!(tx_id = %tx.id, "Saved transaction to SQLite");
+    Ok(SaveOutcome::Inserted)
+}
+
+pub(super) fn insert(conn: &Connection, tx: &Transaction, received_at: i64, encryption: Option<&ColumnEncryption>) {
+    let sealed;
+    let tx = match encryption {
+        Some(encryption) => {
+            sealed = encryption.encrypt_tx(tx);
+            &sealed
+        }
+        None => tx,
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO transactions (id, amount, currency, merchant_id, account_id, received_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![tx.id, tx.amount, tx.currency, tx.merchant_id, tx.account_id, received_at],
+    )
+    .expect("Failed to insert transaction");
 }
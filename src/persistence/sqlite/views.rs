@@ -0,0 +1,93 @@
+// src/persistence/sqlite/views.rs
+
+// Repository views on the connections of one database, shared by `SqliteStore` and `SqlitePool`.
+// Every view of a store or pool sees the writes of the others, and joins their SQL transactions.
+
+use super::db::Db;
+use super::{
+    SQLiteEventStore, SQLiteExport, SQLiteLabelRepo, SQLiteOutbox, SQLiteRetention, SQLiteScoreRepo, SQLiteStateRepo, SQLiteTimerStore, SQLiteTransRepo, event_store,
+    label_repo, outbox, scoring_repo, state_repo, timer_store, transaction_repo,
+};
+use crate::security::ColumnEncryption;
+use rusqlite::Connection;
+use std::time::Duration;
+
+mod sealed {
+    use super::{ColumnEncryption, Db, Duration};
+
+    /// The connections of a `SqliteStore` or `SqlitePool` and what the views take from its config
+    pub struct ViewSource<'a> {
+        pub(in crate::persistence::sqlite) db: &'a Db,
+        pub(in crate::persistence::sqlite) outbox: bool,
+        pub(in crate::persistence::sqlite) dedup_window: Duration,
+        pub(in crate::persistence::sqlite) encryption: Option<&'a ColumnEncryption>,
+    }
+
+    pub trait Source {
+        fn view_source(&self) -> ViewSource<'_>;
+    }
+}
+
+pub(super) use sealed::{Source, ViewSource};
+
+/// The repositories of a `SqliteStore` or a `SqlitePool`
+pub trait SqliteViews: Source {
+    fn transactions(&self) -> SQLiteTransRepo {
+        let source = self.view_source();
+        SQLiteTransRepo::shared(source.db.clone(), source.dedup_window, source.encryption.cloned())
+    }
+
+    fn scores(&self) -> SQLiteScoreRepo {
+        let source = self.view_source();
+        SQLiteScoreRepo::shared(source.db.clone(), source.outbox)
+    }
+
+    fn states(&self) -> SQLiteStateRepo {
+        SQLiteStateRepo::shared(self.view_source().db.clone())
+    }
+
+    /// Durable timers for a `TimerWheel`
+    fn timers(&self) -> SQLiteTimerStore {
+        SQLiteTimerStore::shared(self.view_source().db.clone())
+    }
+
+    fn labels(&self) -> SQLiteLabelRepo {
+        SQLiteLabelRepo::shared(self.view_source().db.clone())
+    }
+
+    fn events(&self) -> SQLiteEventStore {
+        let source = self.view_source();
+        SQLiteEventStore::shared(source.db.clone(), source.encryption.cloned())
+    }
+
+    /// Only fed when opened with the `outbox` option
+    fn outbox(&self) -> SQLiteOutbox {
+        SQLiteOutbox::shared(self.view_source().db.clone())
+    }
+
+    /// Transactions joined with their score, for `export::export`
+    fn export(&self) -> SQLiteExport {
+        let source = self.view_source();
+        SQLiteExport::shared(source.db.clone(), source.encryption.cloned())
+    }
+
+    /// Expired transactions and scores, for a `PurgeJob`
+    fn retention(&self) -> SQLiteRetention {
+        SQLiteRetention::shared(self.view_source().db.clone())
+    }
+}
+
+impl<T: Source> SqliteViews for T {}
+
+/// Tables of every view, the outbox only when asked for
+pub(super) fn create_schema(conn: &Connection, with_outbox: bool) {
+    transaction_repo::create_tables(conn);
+    scoring_repo::create_tables(conn);
+    state_repo::create_tables(conn);
+    timer_store::create_table(conn);
+    label_repo::create_table(conn);
+    event_store::create_tables(conn);
+    if with_outbox {
+        outbox::create_table(conn);
+    }
+}
@@ -0,0 +1,108 @@
+// src/persistence/tokenizing.rs
+
+use crate::domain::query::{Page, TransactionQuery};
+use crate::domain::repository::{AppendError, EventStore, RepoError, SaveOutcome, TransRepository};
+use crate::domain::transaction::Transaction;
+use crate::domain::tx_event::{RecordedEvent, Snapshot, TxEvent};
+use crate::security::Tokenizer;
+
+fn tokenize(tokenizer: &Tokenizer, mut tx: Transaction) -> Transaction {
+    if !tx.account_id.is_empty() {
+        tx.account_id = tokenizer.tokenize(&tx.account_id);
+    }
+    tx
+}
+
+/// Replaces account ids (card numbers included) by tokens before they reach `inner`, whatever the backend.
+/// Reads return the tokens; `query` filters take the raw account id and tokenize it.
+pub struct TokenizingTransRepo<R> {
+    inner: R,
+    tokenizer: Tokenizer,
+}
+
+impl<R: TransRepository> TokenizingTransRepo<R> {
+    pub fn new(inner: R, tokenizer: Tokenizer) -> Self {
+        Self { inner, tokenizer }
+    }
+
+    fn tokenize(&self, tx: Transaction) -> Transaction {
+        tokenize(&self.tokenizer, tx)
+    }
+
+    fn tokenize_query(&self, query: &TransactionQuery) -> TransactionQuery {
+        let mut query = query.clone();
+        query.account_id = query.account_id.map(|account| self.tokenizer.tokenize(&account));
+        query
+    }
+}
+
+impl<R: TransRepository> TransRepository for TokenizingTransRepo<R> {
+    fn save(&self, tx: Transaction) {
+        self.inner.save(self.tokenize(tx));
+    }
+
+    fn get(&self, id: &str) -> Option<Transaction> {
+        self.inner.get(id)
+    }
+
+    fn save_idempotent(&self, tx: Transaction) -> Result<SaveOutcome, RepoError> {
+        // Tokens are deterministic: a retry still compares equal
+        self.inner.save_idempotent(self.tokenize(tx))
+    }
+
+    fn save_idempotent_batch(&self, txs: Vec<Transaction>) -> Vec<Result<SaveOutcome, RepoError>> {
+        self.inner.save_idempotent_batch(txs.into_iter().map(|tx| self.tokenize(tx)).collect())
+    }
+
+    fn query(&self, query: &TransactionQuery) -> Page<Transaction> {
+        self.inner.query(&self.tokenize_query(query))
+    }
+
+    fn count(&self, query: &TransactionQuery) -> usize {
+        self.inner.count(&self.tokenize_query(query))
+    }
+}
+
+/// Same tokenization for the transactions embedded in events, so the event log holds the ids the
+/// transaction repository holds. Snapshots are built from events already tokenized.
+pub struct TokenizingEventStore<E> {
+    inner: E,
+    tokenizer: Tokenizer,
+}
+
+impl<E: EventStore> TokenizingEventStore<E> {
+    pub fn new(inner: E, tokenizer: Tokenizer) -> Self {
+        Self { inner, tokenizer }
+    }
+}
+
+impl<E: EventStore> EventStore for TokenizingEventStore<E> {
+    fn append(&self, stream_id: &str, expected_version: u64, events: Vec<TxEvent>) -> Result<u64, AppendError> {
+        let events = events
+            .into_iter()
+            .map(|event| match event {
+                TxEvent::TransactionReceived { transaction } => TxEvent::TransactionReceived {
+                    transaction: tokenize(&self.tokenizer, transaction),
+                },
+                other => other,
+            })
+            .collect();
+        self.inner.append(stream_id, expected_version, events)
+    }
+
+    fn read_stream(&self, stream_id: &str, after_version: u64) -> Vec<RecordedEvent> {
+        self.inner.read_stream(stream_id, after_version)
+    }
+
+    fn read_all(&self, after_seq: i64, limit: usize) -> Vec<RecordedEvent> {
+        self.inner.read_all(after_seq, limit)
+    }
+
+    fn save_snapshot(&self, snapshot: Snapshot) {
+        self.inner.save_snapshot(snapshot);
+    }
+
+    fn load_snapshot(&self, stream_id: &str) -> Option<Snapshot> {
+        self.inner.load_snapshot(stream_id)
+    }
+}
@@ -0,0 +1,246 @@
+// src/pipeline/mod.rs
+
+// Staged pipeline: the library version of the actors in examples/09_proto.rs.
+//
+//   submit() -> [ch] -> stage 1 (n workers) -> [ch] -> stage 2 (m workers) -> ... -> [ch] -> output
+//
+// - Every link is a bounded mpsc channel, so a slow stage applies back-pressure upstream.
+// - A stage with several workers shares its input receiver between them.
+// - Shutdown closes the input, then each stage drains its queue and exits, which closes the next
+//   link. Stages therefore stop in order and nothing in flight is lost.
+// - Items travel with the span current when they were submitted: each stage processes an item in a
+//   `stage` span child of it, so one transaction's trace shows the time spent in every stage.
+
+pub mod stage;
+pub mod stages;
+
+pub use stage::{Stage, StageMetrics, StageStats};
+
+use crate::metrics::{Counter, Gauge, Histogram, Metrics};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{Instrument, Span, debug, info_span, warn};
+
+/// An item and the span it belongs to
+type Traced<T> = (T, Span);
+
+/// Returned by `Pipeline::submit` once the pipeline is shutting down
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineClosed;
+
+impl fmt::Display for PipelineClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pipeline is closed")
+    }
+}
+
+impl std::error::Error for PipelineClosed {}
+
+struct StageRuntime {
+    metrics: Arc<StageMetrics>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+pub struct PipelineBuilder<I, T> {
+    input: mpsc::Sender<Traced<I>>,
+    rx: mpsc::Receiver<Traced<T>>,
+    capacity: usize,
+    stages: Vec<StageRuntime>,
+    metrics: Option<Arc<Metrics>>,
+}
+
+/// The Prometheus metrics of one stage, looked up once
+struct StageMeters {
+    forwarded: Arc<Counter>,
+    dropped: Arc<Counter>,
+    latency: Arc<Histogram>,
+    queue_depth: Arc<Gauge>,
+}
+
+impl StageMeters {
+    fn new(metrics: &Metrics, stage: &'static str) -> Self {
+        Self {
+            forwarded: metrics.stage_items.with(&[stage, "forwarded"]),
+            dropped: metrics.stage_items.with(&[stage, "dropped"]),
+            latency: metrics.stage_latency.with(&[stage]),
+            queue_depth: metrics.queue_depth.with(&[stage]),
+        }
+    }
+}
+
+impl<I: Send + 'static, T: Send + 'static> PipelineBuilder<I, T> {
+    /// Stages appended after this call also report to `metrics`
+    pub fn metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Appends `stage` running on `workers` concurrent tasks
+    pub fn stage<S: Stage<In = T>>(self, stage: S, workers: usize) -> PipelineBuilder<I, S::Out> {
+        let workers = workers.max(1);
+        let (out_tx, out_rx) = mpsc::channel(self.capacity);
+        let metrics = Arc::new(StageMetrics::new(stage.name(), workers));
+        let stage = Arc::new(stage);
+        let rx = Arc::new(Mutex::new(self.rx));
+        let meters = self.metrics.as_ref().map(|m| Arc::new(StageMeters::new(m, stage.name())));
+
+        let handles = (0..workers)
+            .map(|worker_id| tokio::spawn(run_worker(stage.clone(), worker_id, rx.clone(), out_tx.clone(), metrics.clone(), meters.clone())))
+            .collect();
+
+        let mut stages = self.stages;
+        stages.push(StageRuntime { metrics, handles });
+
+        PipelineBuilder {
+            input: self.input,
+            rx: out_rx,
+            capacity: self.capacity,
+            stages,
+            metrics: self.metrics,
+        }
+    }
+
+    /// Returns the running pipeline and the receiver of the last stage's output.
+    /// Dropping the receiver is fine, the last stage then discards its output.
+    pub fn build(self) -> (Pipeline<I>, PipelineOutput<T>) {
+        let pipeline = Pipeline {
+            input: self.input,
+            stages: self.stages,
+        };
+        (pipeline, PipelineOutput { rx: self.rx })
+    }
+}
+
+/// Receiving end of the last stage
+pub struct PipelineOutput<T> {
+    rx: mpsc::Receiver<Traced<T>>,
+}
+
+impl<T> PipelineOutput<T> {
+    /// The next output, `None` once the pipeline has shut down and the queue is empty
+    pub async fn recv(&mut self) -> Option<T> {
+        self.recv_traced().await.map(|(item, _)| item)
+    }
+
+    /// Same as `recv`, with the span the item was submitted in
+    pub async fn recv_traced(&mut self) -> Option<(T, Span)> {
+        self.rx.recv().await
+    }
+}
+
+/// Cloneable handle feeding a pipeline from other tasks, see `Pipeline::sender`
+pub struct PipelineSender<I> {
+    input: mpsc::Sender<Traced<I>>,
+}
+
+impl<I> Clone for PipelineSender<I> {
+    fn clone(&self) -> Self {
+        Self { input: self.input.clone() }
+    }
+}
+
+impl<I: Send + 'static> PipelineSender<I> {
+    /// Same as `Pipeline::submit`
+    pub async fn send(&self, item: I) -> Result<(), PipelineClosed> {
+        self.input.send((item, Span::current())).await.map_err(|_| PipelineClosed)
+    }
+}
+
+async fn run_worker<S: Stage>(
+    stage: Arc<S>,
+    worker_id: usize,
+    rx: Arc<Mutex<mpsc::Receiver<Traced<S::In>>>>,
+    out: mpsc::Sender<Traced<S::Out>>,
+    metrics: Arc<StageMetrics>,
+    meters: Option<Arc<StageMeters>>,
+) {
+    let mut downstream_open = true;
+    loop {
+        // Only hold the lock while waiting for the next item, not while processing it
+        let item = {
+            let mut rx = rx.lock().await;
+            let item = rx.recv().await;
+            if let Some(meters) = &meters {
+                meters.queue_depth.set(rx.len() as i64);
+            }
+            item
+        };
+        let Some((item, span)) = item else { break };
+
+        let start = Instant::now();
+        let output = stage.process(item).instrument(info_span!(parent: &span, "stage", stage = stage.name())).await;
+        let elapsed = start.elapsed();
+        metrics.record(elapsed, output.is_some());
+        if let Some(meters) = &meters {
+            meters.latency.observe_duration(elapsed);
+            if output.is_some() { meters.forwarded.inc() } else { meters.dropped.inc() }
+        }
+
+        if let Some(output) = output
+            && downstream_open
+            && out.send((output, span)).await.is_err()
+        {
+            // Nobody listens to the output anymore: keep draining the input so upstream can finish
+            warn!(stage = stage.name(), "Downstream closed, discarding output");
+            downstream_open = false;
+        }
+    }
+    debug!(stage = stage.name(), worker_id, "Stage worker stopped");
+}
+
+/// A running pipeline accepting items of type `I`
+pub struct Pipeline<I> {
+    input: mpsc::Sender<Traced<I>>,
+    stages: Vec<StageRuntime>,
+}
+
+impl<I: Send + 'static> Pipeline<I> {
+    /// Starts a pipeline whose links all hold up to `capacity` items
+    pub fn builder(capacity: usize) -> PipelineBuilder<I, I> {
+        let (input, rx) = mpsc::channel(capacity.max(1));
+        PipelineBuilder {
+            input,
+            rx,
+            capacity: capacity.max(1),
+            stages: Vec::new(),
+            metrics: None,
+        }
+    }
+
+    /// Sends an item to the first stage, waiting while its queue is full.
+    /// The item is processed under the span current at the call, see `telemetry::transaction_span`.
+    pub async fn submit(&self, item: I) -> Result<(), PipelineClosed> {
+        self.input.send((item, Span::current())).await.map_err(|_| PipelineClosed)
+    }
+
+    /// A cloneable handle to feed the pipeline from other tasks.
+    /// Shutdown waits for every clone to be dropped.
+    pub fn sender(&self) -> PipelineSender<I> {
+        PipelineSender { input: self.input.clone() }
+    }
+
+    pub fn stats(&self) -> Vec<StageStats> {
+        self.stages.iter().map(|s| s.metrics.snapshot()).collect()
+    }
+
+    /// Closes the input and waits for every stage to drain, first stage first
+    pub async fn shutdown(self) -> Vec<StageStats> {
+        drop(self.input);
+
+        let mut stats = Vec::with_capacity(self.stages.len());
+        for stage in self.stages {
+            for handle in stage.handles {
+                if let Err(e) = handle.await {
+                    warn!(error = %e, "Stage worker panicked");
+                }
+            }
+            let snapshot = stage.metrics.snapshot();
+            debug!(stage = snapshot.name, processed = snapshot.processed, dropped = snapshot.dropped, "Stage stopped");
+            stats.push(snapshot);
+        }
+        stats
+    }
+}
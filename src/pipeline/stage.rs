@@ -0,0 +1,81 @@
+// src/pipeline/stage.rs
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// One step of the pipeline.
+/// Each worker of a stage calls `process` on the items it pulls from the stage's input channel.
+/// Returning `None` drops the item (rejected, duplicate...), the stage is expected to log why.
+pub trait Stage: Send + Sync + 'static {
+    type In: Send + 'static;
+    type Out: Send + 'static;
+
+    fn name(&self) -> &'static str;
+    fn process(&self, input: Self::In) -> impl Future<Output = Option<Self::Out>> + Send;
+}
+
+/// Counters shared by all the workers of one stage
+#[derive(Debug)]
+pub struct StageMetrics {
+    name: &'static str,
+    workers: usize,
+    started: Instant,
+    processed: AtomicU64,
+    dropped: AtomicU64,
+    busy_nanos: AtomicU64,
+}
+
+impl StageMetrics {
+    pub(crate) fn new(name: &'static str, workers: usize) -> Self {
+        Self {
+            name,
+            workers,
+            started: Instant::now(),
+            processed: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            busy_nanos: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn record(&self, elapsed: Duration, forwarded: bool) {
+        self.busy_nanos.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        if forwarded {
+            self.processed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> StageStats {
+        let processed = self.processed.load(Ordering::Relaxed);
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        let handled = processed + dropped;
+        let busy_nanos = self.busy_nanos.load(Ordering::Relaxed);
+        let elapsed = self.started.elapsed().as_secs_f64();
+
+        StageStats {
+            name: self.name,
+            workers: self.workers,
+            processed,
+            dropped,
+            avg_latency: Duration::from_nanos(busy_nanos.checked_div(handled).unwrap_or(0)),
+            throughput: if elapsed > 0.0 { handled as f64 / elapsed } else { 0.0 },
+        }
+    }
+}
+
+/// Point-in-time view of a stage
+#[derive(Debug, Clone)]
+pub struct StageStats {
+    pub name: &'static str,
+    pub workers: usize,
+    /// Items forwarded to the next stage
+    pub processed: u64,
+    /// Items the stage decided to drop
+    pub dropped: u64,
+    /// Mean time spent in `Stage::process`
+    pub avg_latency: Duration,
+    /// Items handled per second since the stage started
+    pub throughput: f64,
+}
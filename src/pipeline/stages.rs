@@ -0,0 +1,239 @@
+// src/pipeline/stages.rs
+
+// The fraud detection stages: validation -> persistence -> scoring -> score persistence -> lifecycle
+// A retry keeps flowing after persistence with the score of its first attempt, which reaches the
+// output without being scored or saved again.
+// Repository calls block on SQLite, the stages run them on the blocking pool like `scoring_pool` does.
+// With an event store the stages also append what they decided to each transaction's stream; the
+// lifecycle's decisions are appended by a `LifecycleEvents` observer on the transition table.
+
+use super::stage::Stage;
+use crate::domain::fraud_scorer::FraudScorer;
+use crate::domain::repository::{EventStore, SaveOutcome, ScoreRepository, TransRepository};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use crate::domain::tx_event::{TxEvent, append_latest};
+use crate::state_machine::timers::TimerWheel;
+use std::sync::Arc;
+use tracing::{Span, debug, info, warn};
+
+/// Runs blocking `work` off the async workers, in the span of the item being processed.
+/// A panic drops the item.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    let span = Span::current();
+    match tokio::task::spawn_blocking(move || span.in_scope(work)).await {
+        Ok(out) => Some(out),
+        Err(e) => {
+            warn!(error = %e, "Repository call panicked");
+            None
+        }
+    }
+}
+
+/// Appends `events` to the stream of `tx_id`, a failure is logged and does not stop the item
+fn record_events(store: &dyn EventStore, tx_id: &str, events: Vec<TxEvent>) {
+    if let Err(e) = append_latest(store, tx_id, events) {
+        warn!(tx_id, error = %e, "Events not recorded");
+    }
+}
+
+/// Rejects malformed transactions before anything is written
+pub struct ValidationStage;
+
+impl Stage for ValidationStage {
+    type In = Transaction;
+    type Out = Transaction;
+
+    fn name(&self) -> &'static str {
+        "validation"
+    }
+
+    async fn process(&self, tx: Transaction) -> Option<Transaction> {
+        match tx.validate() {
+            Ok(()) => Some(tx),
+            Err(e) => {
+                warn!(tx_id = %tx.id, reason = e.reason, "Transaction rejected by validation");
+                None
+            }
+        }
+    }
+}
+
+/// Output of `PersistenceStage`
+#[derive(Debug, Clone)]
+pub enum ToScore {
+    New(Transaction),
+    /// A retry, with the score of its first attempt
+    Retry(Score),
+}
+
+/// Output of `ScoringStage`
+#[derive(Debug, Clone)]
+pub enum Scored {
+    New(Score),
+    /// Already stored by the first attempt
+    Retry(Score),
+}
+
+/// Saves the transaction, conflicts stop here.
+/// Retries are forwarded with their original score, or scored if the first attempt stopped before its score was saved.
+pub struct PersistenceStage<TR: ?Sized, SR: ?Sized> {
+    tx_repo: Arc<TR>,
+    score_repo: Arc<SR>,
+    events: Option<Arc<dyn EventStore>>,
+}
+
+impl<TR: ?Sized, SR: ?Sized> PersistenceStage<TR, SR> {
+    pub fn new(tx_repo: Arc<TR>, score_repo: Arc<SR>) -> Self {
+        Self { tx_repo, score_repo, events: None }
+    }
+
+    /// Appends `TransactionReceived` and `Validated` for each new transaction
+    pub fn with_events(mut self, events: Arc<dyn EventStore>) -> Self {
+        self.events = Some(events);
+        self
+    }
+}
+
+impl<TR: TransRepository + ?Sized + 'static, SR: ScoreRepository + ?Sized + 'static> Stage for PersistenceStage<TR, SR> {
+    type In = Transaction;
+    type Out = ToScore;
+
+    fn name(&self) -> &'static str {
+        "persistence"
+    }
+
+    async fn process(&self, tx: Transaction) -> Option<ToScore> {
+        let (tx_repo, score_repo, events) = (self.tx_repo.clone(), self.score_repo.clone(), self.events.clone());
+        blocking(move || match tx_repo.save_idempotent(tx.clone()) {
+            Ok(SaveOutcome::Inserted) => {
+                debug!(tx_id = %tx.id, "Transaction saved");
+                if let Some(events) = &events {
+                    // Validation ran in the previous stage
+                    record_events(events.as_ref(), &tx.id, vec![TxEvent::TransactionReceived { transaction: tx.clone() }, TxEvent::Validated]);
+                }
+                Some(ToScore::New(tx))
+            }
+            Ok(SaveOutcome::Duplicate) => match score_repo.get(&tx.id) {
+                Some(original) => {
+                    info!(tx_id = %tx.id, "Duplicate transaction, returning original score");
+                    Some(ToScore::Retry(original))
+                }
+                None => {
+                    info!(tx_id = %tx.id, "Duplicate transaction without score, scoring it");
+                    Some(ToScore::New(tx))
+                }
+            },
+            Err(e) => {
+                warn!(tx_id = %tx.id, error = %e, "Transaction rejected");
+                None
+            }
+        })
+        .await
+        .flatten()
+    }
+}
+
+pub struct ScoringStage<S> {
+    scorer: Arc<S>,
+}
+
+impl<S> ScoringStage<S> {
+    pub fn new(scorer: Arc<S>) -> Self {
+        Self { scorer }
+    }
+}
+
+impl<S: FraudScorer + Send + Sync + 'static> Stage for ScoringStage<S> {
+    type In = ToScore;
+    type Out = Scored;
+
+    fn name(&self) -> &'static str {
+        "scoring"
+    }
+
+    async fn process(&self, item: ToScore) -> Option<Scored> {
+        match item {
+            ToScore::New(tx) => self.scorer.score_batch(std::slice::from_ref(&tx)).pop().map(Scored::New),
+            ToScore::Retry(original) => Some(Scored::Retry(original)),
+        }
+    }
+}
+
+pub struct ScorePersistenceStage<SR: ?Sized> {
+    score_repo: Arc<SR>,
+    /// Store and name of the model recorded with each score
+    events: Option<(Arc<dyn EventStore>, String)>,
+}
+
+impl<SR: ?Sized> ScorePersistenceStage<SR> {
+    pub fn new(score_repo: Arc<SR>) -> Self {
+        Self { score_repo, events: None }
+    }
+
+    /// Appends `Scored` for each new score, with `model` as the model that produced it
+    pub fn with_events(mut self, events: Arc<dyn EventStore>, model: impl Into<String>) -> Self {
+        self.events = Some((events, model.into()));
+        self
+    }
+}
+
+impl<SR: ScoreRepository + ?Sized + 'static> Stage for ScorePersistenceStage<SR> {
+    type In = Scored;
+    type Out = Score;
+
+    fn name(&self) -> &'static str {
+        "score_persistence"
+    }
+
+    async fn process(&self, item: Scored) -> Option<Score> {
+        match item {
+            Scored::New(result) => {
+                let (score_repo, events) = (self.score_repo.clone(), self.events.clone());
+                let saved = result.clone();
+                blocking(move || {
+                    score_repo.save(saved.clone());
+                    if let Some((events, model)) = events {
+                        let id = saved.id.clone();
+                        record_events(events.as_ref(), &id, vec![TxEvent::Scored { score: saved, model }]);
+                    }
+                })
+                .await?;
+                info!(?result, "Scoring result saved");
+                Some(result)
+            }
+            Scored::Retry(original) => Some(original),
+        }
+    }
+}
+
+/// Records the state machine path of each decision, so post-settlement feedback and reviews find the
+/// transaction in `Persisted` or `FlaggedAsFraud` with the timers of that state armed. Retries keep
+/// the state of their first attempt.
+pub struct LifecycleStage {
+    wheel: Arc<TimerWheel>,
+}
+
+impl LifecycleStage {
+    pub fn new(wheel: Arc<TimerWheel>) -> Self {
+        Self { wheel }
+    }
+}
+
+impl Stage for LifecycleStage {
+    type In = Score;
+    type Out = Score;
+
+    fn name(&self) -> &'static str {
+        "lifecycle"
+    }
+
+    async fn process(&self, score: Score) -> Option<Score> {
+        let wheel = self.wheel.clone();
+        let decided = score.clone();
+        if let Some(Err(e)) = blocking(move || wheel.record_decision(&decided)).await {
+            warn!(tx_id = %score.id, error = %e, "Decision not recorded in the state machine");
+        }
+        Some(score)
+    }
+}
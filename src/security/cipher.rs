@@ -0,0 +1,152 @@
+// src/security/cipher.rs
+
+// AES-256-GCM encryption of single column values. A stored value reads `enc:<key id>:<hex nonce + ciphertext>`:
+// the key id lets old rows be decrypted after a rotation, and `reencrypt` jobs find them.
+
+use super::keys::{Key, hex_decode, hex_encode};
+use crate::domain::transaction::Transaction;
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use std::fmt;
+use std::sync::Arc;
+
+const PREFIX: &str = "enc:";
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CipherError {
+    /// Encrypted with a key that isn't in the key file anymore
+    UnknownKey(String),
+    Malformed,
+    /// Wrong key, tampered value, or a value moved to another column
+    Decrypt,
+}
+
+impl fmt::Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherError::UnknownKey(id) => write!(f, "value encrypted with unknown key {id}"),
+            CipherError::Malformed => write!(f, "malformed encrypted value"),
+            CipherError::Decrypt => write!(f, "failed to decrypt value"),
+        }
+    }
+}
+
+impl std::error::Error for CipherError {}
+
+pub struct ColumnCipher {
+    // Oldest first, the last one encrypts
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl ColumnCipher {
+    /// Panics without keys
+    pub fn new(keys: Vec<Key>) -> Self {
+        assert!(!keys.is_empty(), "ColumnCipher needs at least one key");
+        Self {
+            keys: keys.into_iter().map(|k| (k.id, Aes256Gcm::new(&k.bytes.into()))).collect(),
+        }
+    }
+
+    pub fn active_key(&self) -> &str {
+        &self.keys.last().expect("at least one key").0
+    }
+
+    /// `column` is authenticated with the value: a ciphertext copied to another column won't decrypt
+    pub fn encrypt(&self, column: &str, plaintext: &str) -> String {
+        let (id, cipher) = self.keys.last().expect("at least one key");
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let sealed = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext.as_bytes(), aad: column.as_bytes() })
+            .expect("AES-GCM encryption failed");
+        format!("{PREFIX}{id}:{}{}", hex_encode(&nonce), hex_encode(&sealed))
+    }
+
+    /// Values without the `enc:` prefix were written before encryption was enabled and are returned as is
+    pub fn decrypt(&self, column: &str, stored: &str) -> Result<String, CipherError> {
+        let Some(rest) = stored.strip_prefix(PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let (id, hex) = rest.split_once(':').ok_or(CipherError::Malformed)?;
+        let (_, cipher) = self.keys.iter().find(|(k, _)| k == id).ok_or_else(|| CipherError::UnknownKey(id.to_string()))?;
+        let bytes = hex_decode(hex).filter(|b| b.len() > NONCE_LEN).ok_or(CipherError::Malformed)?;
+        let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+        let plain = cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: column.as_bytes() })
+            .map_err(|_| CipherError::Decrypt)?;
+        String::from_utf8(plain).map_err(|_| CipherError::Malformed)
+    }
+
+    /// Plain, or encrypted with an older key
+    pub fn needs_reencryption(&self, stored: &str) -> bool {
+        !stored.starts_with(&format!("{PREFIX}{}:", self.active_key()))
+    }
+}
+
+impl fmt::Debug for ColumnCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<&str> = self.keys.iter().map(|(id, _)| id.as_str()).collect();
+        f.debug_struct("ColumnCipher").field("keys", &ids).finish()
+    }
+}
+
+/// Transaction columns that can be encrypted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptedColumn {
+    MerchantId,
+    AccountId,
+}
+
+impl EncryptedColumn {
+    pub fn name(self) -> &'static str {
+        match self {
+            EncryptedColumn::MerchantId => "merchant_id",
+            EncryptedColumn::AccountId => "account_id",
+        }
+    }
+}
+
+/// Which transaction columns a repository encrypts, and with what
+#[derive(Debug, Clone)]
+pub struct ColumnEncryption {
+    pub cipher: Arc<ColumnCipher>,
+    pub columns: Vec<EncryptedColumn>,
+}
+
+impl ColumnEncryption {
+    pub fn new(cipher: ColumnCipher, columns: Vec<EncryptedColumn>) -> Self {
+        Self {
+            cipher: Arc::new(cipher),
+            columns,
+        }
+    }
+
+    pub fn encrypts(&self, column: EncryptedColumn) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// `tx` as stored, with the designated columns encrypted
+    pub fn encrypt_tx(&self, tx: &Transaction) -> Transaction {
+        let mut sealed = tx.clone();
+        for column in &self.columns {
+            let value = field(&mut sealed, *column);
+            *value = self.cipher.encrypt(column.name(), value);
+        }
+        sealed
+    }
+
+    pub fn decrypt_tx(&self, mut tx: Transaction) -> Result<Transaction, CipherError> {
+        for column in &self.columns {
+            let value = field(&mut tx, *column);
+            *value = self.cipher.decrypt(column.name(), value)?;
+        }
+        Ok(tx)
+    }
+}
+
+fn field(tx: &mut Transaction, column: EncryptedColumn) -> &mut String {
+    match column {
+        EncryptedColumn::MerchantId => &mut tx.merchant_id,
+        EncryptedColumn::AccountId => &mut tx.account_id,
+    }
+}
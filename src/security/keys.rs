@@ -0,0 +1,154 @@
+// src/security/keys.rs
+
+// Local key file, meant for development and tests. One key per line:
+//
+//     # <kind> <id> <64 hex chars>
+//     token t1 5f1c...
+//     column k1 9a0b...
+//     column k2 77e3...
+//
+// `token` is the HMAC key of the `Tokenizer`; it can't be rotated without re-tokenizing every row.
+// `column` keys encrypt columns: the last one encrypts, all of them decrypt. Rotating appends a key.
+
+use super::cipher::ColumnCipher;
+use super::tokenize::Tokenizer;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+pub const KEY_LEN: usize = 32;
+
+#[derive(Clone)]
+pub struct Key {
+    pub id: String,
+    pub(crate) bytes: [u8; KEY_LEN],
+}
+
+impl Key {
+    pub fn generate(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            bytes: rand::random(),
+        }
+    }
+}
+
+// Never print key material
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Key").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub enum KeyError {
+    Io(io::Error),
+    Invalid { line: usize, reason: &'static str },
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::Io(e) => write!(f, "failed to read key file: {e}"),
+            KeyError::Invalid { line, reason } => write!(f, "invalid key file, line {line}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+impl From<io::Error> for KeyError {
+    fn from(e: io::Error) -> Self {
+        KeyError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KeyFile {
+    pub token: Option<Key>,
+    /// Oldest first, the last one is active
+    pub column: Vec<Key>,
+}
+
+impl KeyFile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KeyError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, KeyError> {
+        let mut keys = KeyFile::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason| KeyError::Invalid { line: n + 1, reason };
+            let [kind, id, hex] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(invalid("expected <kind> <id> <hex key>"));
+            };
+            let bytes = hex_decode(hex).and_then(|b| <[u8; KEY_LEN]>::try_from(b).ok()).ok_or_else(|| invalid("key must be 64 hex chars"))?;
+            let key = Key { id: id.to_string(), bytes };
+            match kind {
+                "token" if keys.token.is_none() => keys.token = Some(key),
+                "token" => return Err(invalid("only one token key")),
+                "column" if keys.column.iter().any(|k| k.id == id) => return Err(invalid("duplicate column key id")),
+                "column" => keys.column.push(key),
+                _ => return Err(invalid("kind must be token or column")),
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Creates `path` with a token key and a first column key, readable by its owner only
+    pub fn generate(path: impl AsRef<Path>) -> Result<Self, KeyError> {
+        let keys = KeyFile {
+            token: Some(Key::generate("t1")),
+            column: vec![Key::generate("k1")],
+        };
+        let mut text = String::from("# <kind> <id> <hex key>, the last column key encrypts\n");
+        for (kind, key) in keys.token.iter().map(|k| ("token", k)).chain(keys.column.iter().map(|k| ("column", k))) {
+            text.push_str(&format!("{kind} {} {}\n", key.id, hex_encode(&key.bytes)));
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(path)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_data()?;
+        Ok(keys)
+    }
+
+    /// Appends a new column key to `path`, which becomes the active one. Returns its id.
+    pub fn rotate(path: impl AsRef<Path>) -> Result<String, KeyError> {
+        let keys = Self::load(&path)?;
+        let id = format!("k{}", keys.column.len() + 1);
+        let key = Key::generate(&id);
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        writeln!(file, "column {} {}", key.id, hex_encode(&key.bytes))?;
+        file.sync_data()?;
+        Ok(id)
+    }
+
+    pub fn tokenizer(&self) -> Option<Tokenizer> {
+        self.token.as_ref().map(Tokenizer::new)
+    }
+
+    pub fn cipher(&self) -> Option<ColumnCipher> {
+        (!self.column.is_empty()).then(|| ColumnCipher::new(self.column.clone()))
+    }
+}
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub(crate) fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
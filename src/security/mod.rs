@@ -0,0 +1,20 @@
+// src/security/mod.rs
+
+// Protection of sensitive transaction data at rest: tokenization of card numbers and account ids before
+// persistence (`TokenizingTransRepo`), AES-GCM encryption of designated SQLite columns with key rotation,
+// and masking of personal data in the logs.
+//
+// Covered at rest with SQLite: the merchant and account ids of `transactions` and of the transactions
+// embedded in events and snapshots. Not covered: outbox alerts, whose key and payload hold the
+// transaction id and score only, and the redb backend, which tokenizes account ids when a token key
+// exists but stores merchant ids in clear.
+
+pub mod cipher;
+pub mod keys;
+pub mod redact;
+pub mod tokenize;
+
+pub use cipher::{CipherError, ColumnCipher, ColumnEncryption, EncryptedColumn};
+pub use keys::{Key, KeyError, KeyFile};
+pub use redact::RedactingFields;
+pub use tokenize::Tokenizer;
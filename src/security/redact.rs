@@ -0,0 +1,240 @@
+// src/security/redact.rs
+
+// Keeps personal data out of the logs. Values are recognized by their shape, whatever the field they are in:
+// card numbers (13 to 19 digits passing Luhn, spaces and dashes allowed), emails, IPv4 and IPv6 addresses.
+// Fields known to hold such data (`account_id`...) are masked without looking at them.
+
+use super::tokenize::looks_like_pan;
+use std::borrow::Cow;
+use std::fmt;
+use std::net::IpAddr;
+use tracing::field::{Field, Visit};
+use tracing_subscriber::field::{MakeVisitor, VisitFmt, VisitOutput};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Fields masked by `RedactingFields::default()`
+pub const DEFAULT_MASKED_FIELDS: &[&str] = &["account_id", "card_number", "pan", "email", "ip", "ip_address"];
+
+/// `****1111`: the last 4 characters of values long enough to stay anonymous without them, `****` otherwise
+pub fn mask_tail(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.is_empty() {
+        String::new()
+    } else if chars.len() < 8 {
+        "****".to_string()
+    } else {
+        format!("****{}", chars[chars.len() - 4..].iter().collect::<String>())
+    }
+}
+
+/// `text` with card numbers, emails and IP addresses masked
+pub fn redact_text(text: &str) -> Cow<'_, str> {
+    if !text.bytes().any(|b| b.is_ascii_digit() || b == b'@' || b == b':') {
+        return Cow::Borrowed(text);
+    }
+    let masked = mask_words(&mask_pans(text));
+    if masked == text { Cow::Borrowed(text) } else { Cow::Owned(masked) }
+}
+
+fn mask_pans(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+    let mut prev: Option<char> = None;
+    while let Some((start, c)) = chars.next() {
+        if !c.is_ascii_digit() || prev.is_some_and(|p| p.is_alphanumeric()) {
+            out.push(c);
+            prev = Some(c);
+            continue;
+        }
+        // Digits, with single spaces or dashes between them
+        let mut end = start + 1;
+        let bytes = text.as_bytes();
+        while end < bytes.len() {
+            let b = bytes[end];
+            if b.is_ascii_digit() || ((b == b' ' || b == b'-') && bytes.get(end + 1).is_some_and(u8::is_ascii_digit)) {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+        let run = &text[start..end];
+        if looks_like_pan(run) {
+            let digits: String = run.chars().filter(char::is_ascii_digit).collect();
+            out.push_str(&mask_tail(&digits));
+        } else {
+            out.push_str(run);
+        }
+        while chars.peek().is_some_and(|(i, _)| *i < end) {
+            chars.next();
+        }
+        prev = run.chars().last();
+    }
+    out
+}
+
+fn mask_words(text: &str) -> String {
+    let is_separator = |c: char| c.is_whitespace() || "\"'`,;=()[]{}<>".contains(c);
+    let mut out = String::with_capacity(text.len());
+    let mut word_start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        if !is_separator(c) {
+            word_start.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            out.push_str(&mask_word(&text[start..i]));
+        }
+        if i < text.len() {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn mask_word(word: &str) -> Cow<'_, str> {
+    // A word ending a sentence
+    let trimmed = word.trim_end_matches('.');
+    let rest = &word[trimmed.len()..];
+    if let Some((local, domain)) = trimmed.split_once('@')
+        && is_email(local, domain)
+    {
+        return Cow::Owned(format!("***@{domain}{rest}"));
+    }
+    if let Some(ip) = parse_ip(trimmed) {
+        return Cow::Owned(format!("{}{rest}", mask_ip(ip)));
+    }
+    Cow::Borrowed(word)
+}
+
+fn is_email(local: &str, domain: &str) -> bool {
+    !local.is_empty()
+        && local.chars().all(|c| c.is_alphanumeric() || "._%+-".contains(c))
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain.chars().all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+}
+
+/// An address, or `<address>:<port>`
+fn parse_ip(word: &str) -> Option<IpAddr> {
+    word.parse().ok().or_else(|| word.parse::<std::net::SocketAddr>().ok().map(|s| s.ip()))
+}
+
+/// The network part only: `192.168.*.*`, `2001:db8:*`
+fn mask_ip(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, _, _] = v4.octets();
+            format!("{a}.{b}.*.*")
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            format!("{:x}:{:x}:*", segments[0], segments[1])
+        }
+    }
+}
+
+/// Formats event and span fields for a `fmt` layer with personal data masked:
+/// `fmt::layer().fmt_fields(RedactingFields::default())`, or `redact::layer()`
+#[derive(Debug, Clone)]
+pub struct RedactingFields {
+    masked: Vec<String>,
+}
+
+impl RedactingFields {
+    /// Only the values of `fields` are masked entirely, the others are scanned
+    pub fn new<S: Into<String>>(fields: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            masked: fields.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl Default for RedactingFields {
+    fn default() -> Self {
+        Self::new(DEFAULT_MASKED_FIELDS.iter().copied())
+    }
+}
+
+/// A `fmt` layer writing redacted fields
+pub fn layer<S>() -> tracing_subscriber::fmt::Layer<S, RedactingFields>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_subscriber::fmt::layer().fmt_fields(RedactingFields::default())
+}
+
+impl<'a> MakeVisitor<Writer<'a>> for RedactingFields {
+    type Visitor = RedactingVisitor<'a>;
+
+    fn make_visitor(&self, writer: Writer<'a>) -> Self::Visitor {
+        RedactingVisitor {
+            writer,
+            masked: self.masked.clone(),
+            first: true,
+            result: Ok(()),
+        }
+    }
+}
+
+// What `DefaultFields` writes, `message name=value name=value`, values redacted
+pub struct RedactingVisitor<'a> {
+    writer: Writer<'a>,
+    masked: Vec<String>,
+    first: bool,
+    result: fmt::Result,
+}
+
+impl RedactingVisitor<'_> {
+    fn write(&mut self, field: &Field, value: &str) {
+        if self.result.is_err() {
+            return;
+        }
+        // Bridged `log` records
+        if field.name().starts_with("log.") {
+            return;
+        }
+        let separator = if self.first { "" } else { " " };
+        self.first = false;
+        self.result = match field.name() {
+            "message" => write!(self.writer, "{separator}{value}"),
+            name => write!(self.writer, "{separator}{name}={value}"),
+        };
+    }
+
+    fn is_masked(&self, field: &Field) -> bool {
+        self.masked.iter().any(|m| m == field.name())
+    }
+}
+
+impl Visit for RedactingVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if self.is_masked(field) {
+            self.write(field, &format!("{:?}", mask_tail(value)));
+        } else {
+            self.write(field, &format!("{:?}", redact_text(value)));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if self.is_masked(field) {
+            let text = format!("{value:?}");
+            self.write(field, &mask_tail(text.trim_matches('"')));
+        } else {
+            self.write(field, &redact_text(&format!("{value:?}")));
+        }
+    }
+}
+
+impl VisitOutput<fmt::Result> for RedactingVisitor<'_> {
+    fn finish(self) -> fmt::Result {
+        self.result
+    }
+}
+
+impl VisitFmt for RedactingVisitor<'_> {
+    fn writer(&mut self) -> &mut dyn fmt::Write {
+        &mut self.writer
+    }
+}
@@ -0,0 +1,74 @@
+// src/security/tokenize.rs
+
+// Keyed, deterministic tokens: the same input always gives the same token, so tokenized columns can still
+// be joined and filtered on, but the original value can't be recovered without brute-forcing the HMAC key.
+
+use super::keys::{Key, hex_encode};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub struct Tokenizer {
+    mac: HmacSha256,
+}
+
+impl Tokenizer {
+    pub fn new(key: &Key) -> Self {
+        Self {
+            mac: HmacSha256::new_from_slice(&key.bytes).expect("HMAC takes any key length"),
+        }
+    }
+
+    fn digest(&self, value: &str) -> [u8; 32] {
+        let mut mac = self.mac.clone();
+        mac.update(value.as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    /// `tok_` and 128 bits of HMAC-SHA256, for any value
+    pub fn token(&self, value: &str) -> String {
+        format!("tok_{}", hex_encode(&self.digest(value)[..16]))
+    }
+
+    /// Same length as `pan` and the same last 4 digits, so receipts and support tools keep working.
+    /// The token always fails the Luhn check and can't be mistaken for a real card number.
+    pub fn pan_token(&self, pan: &str) -> String {
+        let digits: Vec<u8> = pan.bytes().filter(u8::is_ascii_digit).map(|b| b - b'0').collect();
+        let keep = digits.len().saturating_sub(4);
+        let digest = self.digest(pan);
+        let mut token: Vec<u8> = (0..keep).map(|i| digest[i % digest.len()] % 10).chain(digits[keep..].iter().copied()).collect();
+        if luhn_valid(&token) && keep > 0 {
+            // Changing any single digit breaks the checksum
+            token[0] = (token[0] + 1) % 10;
+        }
+        token.into_iter().map(|d| char::from(b'0' + d)).collect()
+    }
+
+    /// `pan_token` for card numbers, `token` for anything else
+    pub fn tokenize(&self, value: &str) -> String {
+        if looks_like_pan(value) { self.pan_token(value) } else { self.token(value) }
+    }
+}
+
+/// 13 to 19 digits (spaces and dashes allowed) passing the Luhn check
+pub fn looks_like_pan(value: &str) -> bool {
+    if !value.chars().all(|c| c.is_ascii_digit() || c == ' ' || c == '-') {
+        return false;
+    }
+    let digits: Vec<u8> = value.bytes().filter(u8::is_ascii_digit).map(|b| b - b'0').collect();
+    (13..=19).contains(&digits.len()) && luhn_valid(&digits)
+}
+
+fn luhn_valid(digits: &[u8]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            let d = d as u32;
+            if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
@@ -0,0 +1,118 @@
+// src/state_machine/async_fsm.rs
+
+// One async state machine per transaction, ML scoring delegated to a `ScoringPool`.
+//
+//   Validated -> Enriched --(score received)--> Persisted | FlaggedAsFraud
+//                         --(timeout / worker gone)--> fallback decision
+//
+// Waiting for the score suspends only this transaction's task.
+// Dropping the future (client disconnected, request aborted...) drops the oneshot receiver,
+// which cancels the pending scoring request.
+
+use super::transitions::StateId;
+use crate::domain::fraud_scorer::{FraudScorer, RuleBasedScorer};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use crate::workers::scoring_pool::ScoringPool;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{Instrument, debug, info, info_span, warn};
+
+/// What to decide when no score came back in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FallbackPolicy {
+    Approve,
+    Flag,
+    /// Use `RuleBasedScorer` locally
+    Rules,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FallbackReason {
+    Timeout,
+    WorkerUnavailable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Clean(Score),
+    Fraud(Score),
+    Fallback { reason: FallbackReason, is_fraud: bool },
+}
+
+impl Decision {
+    pub fn is_fraud(&self) -> bool {
+        match self {
+            Decision::Clean(_) => false,
+            Decision::Fraud(_) => true,
+            Decision::Fallback { is_fraud, .. } => *is_fraud,
+        }
+    }
+
+    /// Final state in the transition table
+    pub fn state(&self) -> StateId {
+        if self.is_fraud() { StateId::FlaggedAsFraud } else { StateId::Persisted }
+    }
+
+    pub fn state_name(&self) -> &'static str {
+        self.state().name()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FsmConfig {
+    /// Maximum time to get a score, queueing included
+    pub score_timeout: Duration,
+    pub fallback: FallbackPolicy,
+}
+
+impl Default for FsmConfig {
+    fn default() -> Self {
+        Self {
+            score_timeout: Duration::from_millis(500),
+            fallback: FallbackPolicy::Rules,
+        }
+    }
+}
+
+/// Runs one transaction through its state machine, in a child span of the caller's
+pub async fn run_transaction(tx: Transaction, pool: &ScoringPool, config: &FsmConfig) -> Decision {
+    let span = info_span!("stage", stage = "state_machine");
+    transition(tx, pool, config).instrument(span).await
+}
+
+async fn transition(tx: Transaction, pool: &ScoringPool, config: &FsmConfig) -> Decision {
+    let tx_id = tx.id.clone();
+    debug!(tx_id = %tx_id, "State: Validated -> Enriched");
+
+    // Kept for a rules-based fallback, the original goes to the worker
+    let local_copy = tx.clone();
+
+    let outcome = timeout(config.score_timeout, async {
+        let score_rx = pool.request(tx).await.ok()?;
+        score_rx.await.ok()
+    })
+    .await;
+
+    let decision = match outcome {
+        Ok(Some(score)) if score.is_fraud => Decision::Fraud(score),
+        Ok(Some(score)) => Decision::Clean(score),
+        Ok(None) => fallback(FallbackReason::WorkerUnavailable, &local_copy, config.fallback),
+        Err(_elapsed) => fallback(FallbackReason::Timeout, &local_copy, config.fallback),
+    };
+
+    if let Decision::Fallback { reason, .. } = &decision {
+        warn!(tx_id = %tx_id, ?reason, policy = ?config.fallback, "No score from worker, using fallback");
+    }
+    info!(tx_id = %tx_id, to = decision.state_name(), "State: Enriched -> {}", decision.state_name());
+    decision
+}
+
+fn fallback(reason: FallbackReason, tx: &Transaction, policy: FallbackPolicy) -> Decision {
+    let is_fraud = match policy {
+        FallbackPolicy::Approve => false,
+        FallbackPolicy::Flag => true,
+        FallbackPolicy::Rules => RuleBasedScorer.is_fraud(tx),
+    };
+    Decision::Fallback { reason, is_fraud }
+}
@@ -1,6 +1,60 @@
 // src/state_machine/event.rs
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Event {
     Process,
+    /// The scorer answered, the score travels in the `TransitionContext`
+    Scored,
+    /// The transaction and its score have been written
+    Persist,
+
+    // Manual review, the analyst id and notes travel in the `TransitionContext`
+    AssignToAnalyst,
+    ConfirmFraud,
+    Clear,
+    Escalate,
+    /// Nobody acted on the case in time
+    ReviewTimeout,
+
+    // Post-settlement feedback, arrives days after the decision
+    Chargeback,
+    Refund,
+    CustomerDisputed,
+}
+
+impl Event {
+    pub const ALL: [Event; 11] = [
+        Event::Process,
+        Event::Scored,
+        Event::Persist,
+        Event::AssignToAnalyst,
+        Event::ConfirmFraud,
+        Event::Clear,
+        Event::Escalate,
+        Event::ReviewTimeout,
+        Event::Chargeback,
+        Event::Refund,
+        Event::CustomerDisputed,
+    ];
+
+    /// Inverse of `name`, used when loading stored events
+    pub fn from_name(name: &str) -> Option<Event> {
+        Event::ALL.into_iter().find(|e| e.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Process => "Process",
+            Event::Scored => "Scored",
+            Event::Persist => "Persist",
+            Event::AssignToAnalyst => "AssignToAnalyst",
+            Event::ConfirmFraud => "ConfirmFraud",
+            Event::Clear => "Clear",
+            Event::Escalate => "Escalate",
+            Event::ReviewTimeout => "ReviewTimeout",
+            Event::Chargeback => "Chargeback",
+            Event::Refund => "Refund",
+            Event::CustomerDisputed => "CustomerDisputed",
+        }
+    }
 }
@@ -0,0 +1,102 @@
+// src/state_machine/feedback.rs
+
+// Post-settlement feedback: chargebacks, refunds and customer disputes arrive days after a transaction
+// was settled. Each one reopens the stored state machine and writes a label linking the outcome to the
+// score the pipeline gave at the time. With a `FeedbackStore` the transition and the label are written
+// together; repositories opened separately write them one after the other.
+
+use super::event::Event;
+use super::transitions::{StateMachine, TransitionContext, TransitionError, TransitionRecord, TransitionTable};
+use crate::domain::label::{Label, LabelOutcome};
+use crate::domain::repository::{FeedbackStore, LabelRepository, ScoreRepository, StateRepository};
+use std::fmt;
+use std::sync::Arc;
+use tracing::info;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedbackError {
+    /// Only `Chargeback`, `Refund` and `CustomerDisputed` carry feedback
+    NotFeedback(Event),
+    /// No state was ever stored for this transaction
+    UnknownTransaction(String),
+    Transition(TransitionError),
+}
+
+impl fmt::Display for FeedbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedbackError::NotFeedback(event) => write!(f, "{} is not a post-settlement event", event.name()),
+            FeedbackError::UnknownTransaction(id) => write!(f, "no state stored for transaction {id}"),
+            FeedbackError::Transition(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for FeedbackError {}
+
+impl From<TransitionError> for FeedbackError {
+    fn from(e: TransitionError) -> Self {
+        FeedbackError::Transition(e)
+    }
+}
+
+/// Transition then label, for repositories that share no SQL transaction
+struct SeparateWrites {
+    states: Arc<dyn StateRepository>,
+    labels: Arc<dyn LabelRepository>,
+}
+
+impl FeedbackStore for SeparateWrites {
+    fn record_feedback(&self, record: TransitionRecord, label: Label) -> bool {
+        if !self.states.record_transition_if(Some(record.from), record) {
+            return false;
+        }
+        self.labels.save(label);
+        true
+    }
+}
+
+pub struct SettlementFeedback {
+    table: Arc<TransitionTable>,
+    states: Arc<dyn StateRepository>,
+    scores: Arc<dyn ScoreRepository>,
+    writes: Arc<dyn FeedbackStore>,
+}
+
+impl SettlementFeedback {
+    pub fn new(table: Arc<TransitionTable>, states: Arc<dyn StateRepository>, scores: Arc<dyn ScoreRepository>, labels: Arc<dyn LabelRepository>) -> Self {
+        let writes = Arc::new(SeparateWrites { states: states.clone(), labels });
+        Self::with_store(table, states, scores, writes)
+    }
+
+    /// Writes each transition and its label through `store`, e.g. a `SqliteStore` whose `states()` is `states`
+    pub fn with_store(table: Arc<TransitionTable>, states: Arc<dyn StateRepository>, scores: Arc<dyn ScoreRepository>, store: Arc<dyn FeedbackStore>) -> Self {
+        Self { table, states, scores, writes: store }
+    }
+
+    /// Applies a post-settlement `event` to the stored state of `ctx.tx_id`, records the transition
+    /// and saves the resulting label. Nothing is written on error.
+    pub fn apply(&self, event: Event, ctx: &TransitionContext) -> Result<Label, FeedbackError> {
+        let outcome = LabelOutcome::from_event(event).ok_or(FeedbackError::NotFeedback(event))?;
+        let mut fsm = StateMachine::load(self.table.clone(), self.states.as_ref(), &ctx.tx_id)
+            .ok_or_else(|| FeedbackError::UnknownTransaction(ctx.tx_id.clone()))?;
+        let score = self.scores.get(&ctx.tx_id);
+        let mut recorded = None;
+        // A concurrent feedback or review that moved the state first turns this one into `Stale`
+        let to = fsm.fire_with(event, ctx, |record| {
+            let label = Label {
+                tx_id: ctx.tx_id.clone(),
+                outcome,
+                source: event,
+                score,
+                labeled_at: record.at,
+                notes: ctx.notes.clone(),
+            };
+            recorded = Some(label.clone());
+            self.writes.record_feedback(record.clone(), label)
+        })?;
+        let label = recorded.expect("fire_with writes before applying");
+        info!(tx_id = %label.tx_id, outcome = outcome.name(), %to, right = ?label.score_was_right(), "Label recorded");
+        Ok(label)
+    }
+}
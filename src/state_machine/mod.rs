@@ -1,4 +1,10 @@
 // src/state_machine/mod.rs
 
+pub mod async_fsm;
 pub mod event;
+pub mod feedback;
+pub mod observer;
 pub mod state;
+pub mod timers;
+pub mod transitions;
+pub mod typestate;
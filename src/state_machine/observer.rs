@@ -0,0 +1,85 @@
+// src/state_machine/observer.rs
+
+// Hooks on state transitions. Observers are registered on a `TransitionTable` (`observe`) and called by
+// `StateMachine::fire` after every applied transition, in this order: `on_exit(from)`, `on_transition`,
+// `on_enter(to)`. Rejected transitions do not reach observers, nor do stale ones `fire_and_record` could not store.
+//
+// Hooks run synchronously on the caller's thread: keep them cheap, hand slow work to a channel.
+
+use super::event::Event;
+use super::transitions::{StateId, TransitionRecord};
+use crate::domain::repository::EventStore;
+use crate::domain::tx_event::{TxEvent, append_latest};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+pub trait TransitionObserver: Send + Sync {
+    fn on_exit(&self, _tx_id: &str, _state: StateId) {}
+    fn on_enter(&self, _tx_id: &str, _state: StateId) {}
+    fn on_transition(&self, _record: &TransitionRecord) {}
+}
+
+/// Keeps every transition it sees, for audits and tests
+#[derive(Default)]
+pub struct RecordingObserver {
+    records: Mutex<Vec<TransitionRecord>>,
+}
+
+impl RecordingObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn records(&self) -> Vec<TransitionRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+impl TransitionObserver for RecordingObserver {
+    fn on_transition(&self, record: &TransitionRecord) {
+        self.records.lock().unwrap().push(record.clone());
+    }
+}
+
+/// Appends the lifecycle's decisions to the event stream of each transaction: flags, reviews and
+/// post-settlement feedback. Observers only see transitions that were stored.
+pub struct LifecycleEvents {
+    store: Arc<dyn EventStore>,
+}
+
+impl LifecycleEvents {
+    pub fn new(store: Arc<dyn EventStore>) -> Self {
+        Self { store }
+    }
+}
+
+/// The event recording `record`, none for the steps that carry no decision
+fn lifecycle_event(record: &TransitionRecord) -> Option<TxEvent> {
+    let notes = record.notes.clone();
+    let reviewed = |confirmed_fraud| TxEvent::Reviewed {
+        analyst_id: record.actor.clone().unwrap_or_default(),
+        confirmed_fraud,
+        notes: notes.clone(),
+    };
+    match record.event {
+        Event::Persist if record.to == StateId::FlaggedAsFraud => Some(TxEvent::Flagged {
+            reason: notes.clone().unwrap_or_else(|| "scored as fraud".to_string()),
+        }),
+        Event::ConfirmFraud => Some(reviewed(true)),
+        Event::Clear | Event::ReviewTimeout => Some(reviewed(false)),
+        Event::Chargeback => Some(TxEvent::ChargebackReceived { notes }),
+        Event::Refund => Some(TxEvent::RefundReceived { notes }),
+        Event::CustomerDisputed => Some(TxEvent::DisputeOpened { notes }),
+        _ => None,
+    }
+}
+
+impl TransitionObserver for LifecycleEvents {
+    fn on_transition(&self, record: &TransitionRecord) {
+        if let Some(event) = lifecycle_event(record)
+            && let Err(e) = append_latest(self.store.as_ref(), &record.tx_id, vec![event])
+        {
+            warn!(tx_id = %record.tx_id, error = %e, "Lifecycle event not recorded");
+        }
+    }
+}
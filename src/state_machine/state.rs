@@ -1,26 +1,65 @@
 // src/state_machine/state.rs
 
+// Boxed state objects kept for the code written against them. They hold no transitions of their own:
+// `handle` fires the event through `standard_table()`, the single source of truth of the lifecycle.
+
 use super::event::Event;
+use super::transitions::{StateId, TransitionContext, TransitionTable, standard_table};
 use crate::domain::fraud_scorer::FraudScorer;
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
 use std::any::Any;
 use std::fmt::Debug;
+use std::sync::LazyLock;
+use tracing::{info, warn};
+
+static TABLE: LazyLock<TransitionTable> = LazyLock::new(standard_table);
 
 pub trait State: Debug + Any {
+    /// Applies `event` through `standard_table()`, stays in place when the table has no transition for it
     fn handle(self: Box<Self>, input: Event) -> Box<dyn State>;
-    fn name(&self) -> &'static str;
+    fn id(&self) -> StateId;
+    fn name(&self) -> &'static str {
+        self.id().name()
+    }
     fn as_any(self: Box<Self>) -> Box<dyn Any>; // needed for downcast
 }
 
+/// The state object of `id`
+pub fn boxed(id: StateId) -> Box<dyn State> {
+    match id {
+        StateId::Validated => Box::new(Validated),
+        StateId::Enriched => Box::new(Enriched),
+        StateId::Scored => Box::new(Scored),
+        StateId::Persisted => Box::new(Persisted),
+        StateId::FlaggedAsFraud => Box::new(FlaggedAsFraud),
+        other => Box::new(Lifecycle(other)),
+    }
+}
+
+fn step(state: Box<dyn State>, event: Event, ctx: &TransitionContext) -> Box<dyn State> {
+    let from = state.id();
+    match TABLE.fire(from, event, ctx) {
+        Ok(to) => {
+            info!(tx_id = %ctx.tx_id, %from, %to, reason = event.name(), "Transition");
+            boxed(to)
+        }
+        Err(e) => {
+            warn!(tx_id = %ctx.tx_id, error = %e, "Event ignored");
+            state
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Validated;
 impl State for Validated {
-    fn handle(self: Box<Self>, _event: Event) -> Box<dyn State> {
-        println!("State: Validated -> Enriched");
-        Box::new(Enriched)
+    fn handle(self: Box<Self>, event: Event) -> Box<dyn State> {
+        step(self, event, &TransitionContext::default())
     }
 
-    fn name(&self) -> &'static str {
-        "Validated"
+    fn id(&self) -> StateId {
+        StateId::Validated
     }
 
     fn as_any(self: Box<Self>) -> Box<dyn Any> {
@@ -28,46 +67,49 @@ impl State for Validated {
     }
 }
 
-// #[derive(Debug)]
-// pub struct Enriched;
-// impl State for Enriched {
-//     fn handle(self: Box<Self>, _event: Event) -> Box<dyn State> {
-//         println!("State: Enriched -> Persisted");
-//         Box::new(Persisted)
-//     }
-
-//     fn name(&self) -> &'static str {
-//         "Enriched"
-//     }
-// }
-
 #[derive(Debug)]
 pub struct Enriched;
 
-// use crate::domain::fraud_scorer::FraudScorer;
-use crate::domain::transaction::Transaction;
-
 impl Enriched {
+    /// Scores `tx` then persists it: `Scored` and `Persist` fired through the table
+    // Boxed to chain with `State::handle` after `as_any().downcast()`
+    #[allow(clippy::boxed_local)]
     pub fn handle_with_scorer(self: Box<Self>, tx: &Transaction, scorer: &dyn FraudScorer) -> Box<dyn State> {
-        if scorer.is_fraud(tx) {
-            println!("State: Enriched -> FlaggedAsFraud");
-            Box::new(FlaggedAsFraud)
-        } else {
-            println!("State: Enriched -> Persisted");
-            Box::new(Persisted)
-        }
+        let is_fraud = scorer.is_fraud(tx);
+        let ctx = TransitionContext::new(tx.id.clone()).with_score(Score {
+            id: tx.id.clone(),
+            score: if is_fraud { 1.0 } else { 0.0 },
+            is_fraud,
+        });
+        let scored = step(self, Event::Scored, &ctx);
+        step(scored, Event::Persist, &ctx)
     }
 }
 
 impl State for Enriched {
-    fn handle(self: Box<Self>, _event: Event) -> Box<dyn State> {
-        // Should never be used in this version
-        println!("Enriched: call handle_with_scorer instead.");
+    fn handle(self: Box<Self>, event: Event) -> Box<dyn State> {
+        step(self, event, &TransitionContext::default())
+    }
+
+    fn id(&self) -> StateId {
+        StateId::Enriched
+    }
+
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
         self
     }
+}
 
-    fn name(&self) -> &'static str {
-        "Enriched"
+/// `Persist` needs the score: without it the guards reject the event, see `Enriched::handle_with_scorer`
+#[derive(Debug)]
+pub struct Scored;
+impl State for Scored {
+    fn handle(self: Box<Self>, event: Event) -> Box<dyn State> {
+        step(self, event, &TransitionContext::default())
+    }
+
+    fn id(&self) -> StateId {
+        StateId::Scored
     }
 
     fn as_any(self: Box<Self>) -> Box<dyn Any> {
@@ -78,13 +120,12 @@ impl State for Enriched {
 #[derive(Debug)]
 pub struct Persisted;
 impl State for Persisted {
-    fn handle(self: Box<Self>, _event: Event) -> Box<dyn State> {
-        println!("State: Persisted (final state reached)");
-        self
+    fn handle(self: Box<Self>, event: Event) -> Box<dyn State> {
+        step(self, event, &TransitionContext::default())
     }
 
-    fn name(&self) -> &'static str {
-        "Persisted"
+    fn id(&self) -> StateId {
+        StateId::Persisted
     }
 
     fn as_any(self: Box<Self>) -> Box<dyn Any> {
@@ -95,13 +136,29 @@ impl State for Persisted {
 #[derive(Debug)]
 pub struct FlaggedAsFraud;
 impl State for FlaggedAsFraud {
-    fn handle(self: Box<Self>, _event: Event) -> Box<dyn State> {
-        println!("State: FlaggedAsFraud (final state reached)");
+    fn handle(self: Box<Self>, event: Event) -> Box<dyn State> {
+        step(self, event, &TransitionContext::default())
+    }
+
+    fn id(&self) -> StateId {
+        StateId::FlaggedAsFraud
+    }
+
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
         self
     }
+}
 
-    fn name(&self) -> &'static str {
-        "FlaggedAsFraud"
+/// Review and post-settlement states, which never had a state object of their own
+#[derive(Debug)]
+pub struct Lifecycle(pub StateId);
+impl State for Lifecycle {
+    fn handle(self: Box<Self>, event: Event) -> Box<dyn State> {
+        step(self, event, &TransitionContext::default())
+    }
+
+    fn id(&self) -> StateId {
+        self.0
     }
 
     fn as_any(self: Box<Self>) -> Box<dyn Any> {
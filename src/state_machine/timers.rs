@@ -0,0 +1,142 @@
+// src/state_machine/timers.rs
+
+// Scheduled events: entering some states arms a timer (e.g. UnderReview -> ReviewTimeout after 24h),
+// leaving the state disarms it. Timers live in a `TimerStore`, so with the SQLite store they survive
+// restarts: overdue timers simply fire on the first tick after startup.
+//
+// A timer is removed only after its event was applied. If the process dies in between, the timer fires
+// again and is discarded, because its event is no longer legal from the new state. A timer racing an
+// analyst loses if the analyst's transition is stored first: its write checks the state it was loaded in.
+
+use super::event::Event;
+use super::transitions::{StateId, StateMachine, TransitionContext, TransitionError, TransitionRecord, TransitionTable};
+use crate::domain::clock::now_millis;
+use crate::domain::repository::{StateRepository, TimerStore};
+use crate::domain::scoring::Score;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Actor recorded on transitions triggered by a timer
+pub const TIMER_ACTOR: &str = "timer";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timer {
+    pub id: i64,
+    pub tx_id: String,
+    pub event: Event,
+    /// Milliseconds since the Unix epoch
+    pub due_at: i64,
+}
+
+/// Fire `event` if the transaction is still in `state` after `after`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimerRule {
+    pub state: StateId,
+    pub event: Event,
+    pub after: Duration,
+}
+
+/// Auto-approve a review nobody worked on within 24h, escalate a flag nobody picked up within 1h
+pub fn default_timer_rules() -> Vec<TimerRule> {
+    vec![
+        TimerRule {
+            state: StateId::UnderReview,
+            event: Event::ReviewTimeout,
+            after: Duration::from_secs(24 * 60 * 60),
+        },
+        TimerRule {
+            state: StateId::FlaggedAsFraud,
+            event: Event::Escalate,
+            after: Duration::from_secs(60 * 60),
+        },
+    ]
+}
+
+pub struct TimerWheel {
+    table: Arc<TransitionTable>,
+    states: Arc<dyn StateRepository>,
+    timers: Arc<dyn TimerStore>,
+    rules: Vec<TimerRule>,
+}
+
+impl TimerWheel {
+    pub fn new(table: Arc<TransitionTable>, states: Arc<dyn StateRepository>, timers: Arc<dyn TimerStore>, rules: Vec<TimerRule>) -> Self {
+        Self { table, states, timers, rules }
+    }
+
+    /// Disarms the timers of the state that was left and arms those of the state entered.
+    /// Call it after every recorded transition (`fire` does it for you).
+    pub fn on_transition(&self, record: &TransitionRecord) {
+        self.timers.cancel_all(&record.tx_id);
+        for rule in self.rules.iter().filter(|r| r.state == record.to) {
+            let due_at = record.at + rule.after.as_millis() as i64;
+            self.timers.schedule(&record.tx_id, rule.event, due_at);
+            debug!(tx_id = %record.tx_id, event = rule.event.name(), due_at, "Timer armed");
+        }
+    }
+
+    /// Applies `event` to the stored state of `ctx.tx_id` (initial state if unknown), records it and re-arms timers.
+    /// `TransitionError::Stale` when another writer moved the state after it was loaded.
+    pub fn fire(&self, event: Event, ctx: &TransitionContext) -> Result<StateId, TransitionError> {
+        let mut fsm = StateMachine::load(self.table.clone(), self.states.as_ref(), &ctx.tx_id).unwrap_or_else(|| StateMachine::new(self.table.clone()));
+        let to = fsm.fire_and_record(event, ctx, self.states.as_ref())?;
+        if let Some(record) = fsm.history().last() {
+            self.on_transition(record);
+        }
+        Ok(to)
+    }
+
+    /// Records the pipeline's decision for `score` (see `StateMachine::record_decision`) and arms the
+    /// timers of the state it ends in. A retry changes nothing.
+    pub fn record_decision(&self, score: &Score) -> Result<StateId, TransitionError> {
+        let applied = StateMachine::record_decision(self.table.clone(), self.states.as_ref(), score)?;
+        match applied.last() {
+            Some(record) => {
+                self.on_transition(record);
+                Ok(record.to)
+            }
+            None => Ok(self.states.get_state(&score.id).unwrap_or_default()),
+        }
+    }
+
+    /// Fires every timer due at `now`, returns the number of transitions applied
+    pub fn fire_due(&self, now: i64) -> usize {
+        let mut applied = 0;
+        loop {
+            let due = self.timers.due(now, 100);
+            if due.is_empty() {
+                break;
+            }
+            for timer in due {
+                let ctx = TransitionContext::new(timer.tx_id.clone()).by(TIMER_ACTOR);
+                match self.fire(timer.event, &ctx) {
+                    Ok(to) => {
+                        info!(tx_id = %timer.tx_id, event = timer.event.name(), %to, "Timer fired");
+                        applied += 1;
+                    }
+                    Err(e) => debug!(tx_id = %timer.tx_id, error = %e, "Stale timer discarded"),
+                }
+                // Already gone when `fire` re-armed the timers, needed for stale ones
+                self.timers.remove(timer.id);
+            }
+        }
+        applied
+    }
+
+    /// Checks for due timers every `tick`
+    pub fn spawn(self: Arc<Self>, tick: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(tick);
+            loop {
+                ticker.tick().await;
+                let wheel = self.clone();
+                // Store access is blocking
+                if let Err(e) = tokio::task::spawn_blocking(move || wheel.fire_due(now_millis())).await {
+                    warn!(error = %e, "Timer tick panicked");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,513 @@
+// src/state_machine/transitions.rs
+
+// Declarative transition table: (state, event) -> state, with optional guard and action.
+//
+// Rules are looked up by (from, event) and tried in declaration order, the first rule whose guard
+// passes wins. A (state, event) pair without any rule is an illegal transition.
+// The table can be checked at startup (`validate`) and exported as a diagram (`to_mermaid`, `to_dot`).
+
+use super::event::Event;
+use super::observer::TransitionObserver;
+use crate::domain::clock::now_millis;
+use crate::domain::repository::StateRepository;
+use crate::domain::scoring::Score;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StateId {
+    #[default]
+    Validated,
+    Enriched,
+    Scored,
+    Persisted,
+    FlaggedAsFraud,
+    UnderReview,
+    ConfirmedFraud,
+    Cleared,
+    Disputed,
+    ChargedBack,
+    Refunded,
+}
+
+impl StateId {
+    pub const ALL: [StateId; 11] = [
+        StateId::Validated,
+        StateId::Enriched,
+        StateId::Scored,
+        StateId::Persisted,
+        StateId::FlaggedAsFraud,
+        StateId::UnderReview,
+        StateId::ConfirmedFraud,
+        StateId::Cleared,
+        StateId::Disputed,
+        StateId::ChargedBack,
+        StateId::Refunded,
+    ];
+
+    /// Same names as the `State` implementations in `state_machine::state`
+    pub fn name(&self) -> &'static str {
+        match self {
+            StateId::Validated => "Validated",
+            StateId::Enriched => "Enriched",
+            StateId::Scored => "Scored",
+            StateId::Persisted => "Persisted",
+            StateId::FlaggedAsFraud => "FlaggedAsFraud",
+            StateId::UnderReview => "UnderReview",
+            StateId::ConfirmedFraud => "ConfirmedFraud",
+            StateId::Cleared => "Cleared",
+            StateId::Disputed => "Disputed",
+            StateId::ChargedBack => "ChargedBack",
+            StateId::Refunded => "Refunded",
+        }
+    }
+
+    /// Inverse of `name`, used when loading stored states
+    pub fn from_name(name: &str) -> Option<StateId> {
+        StateId::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+impl fmt::Display for StateId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Data available to guards and actions while firing an event
+#[derive(Debug, Clone, Default)]
+pub struct TransitionContext {
+    pub tx_id: String,
+    pub score: Option<Score>,
+    /// Who triggered the event: analyst id, or a system name for automatic events
+    pub actor: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl TransitionContext {
+    pub fn new(tx_id: impl Into<String>) -> Self {
+        Self {
+            tx_id: tx_id.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_score(mut self, score: Score) -> Self {
+        self.score = Some(score);
+        self
+    }
+
+    pub fn by(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    pub fn with_notes(mut self, notes: impl Into<String>) -> Self {
+        self.notes = Some(notes.into());
+        self
+    }
+}
+
+/// One applied transition, with who did it and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransitionRecord {
+    pub tx_id: String,
+    pub from: StateId,
+    pub to: StateId,
+    pub event: Event,
+    /// Milliseconds since the Unix epoch
+    pub at: i64,
+    pub actor: Option<String>,
+    pub notes: Option<String>,
+}
+
+pub type Guard = fn(&TransitionContext) -> bool;
+pub type Action = Arc<dyn Fn(&TransitionContext, StateId, StateId) + Send + Sync>;
+
+pub struct Rule {
+    pub from: StateId,
+    pub event: Event,
+    pub to: StateId,
+    /// Name (for diagrams) and predicate
+    pub guard: Option<(&'static str, Guard)>,
+    pub action: Option<Action>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransitionError {
+    /// No rule for this (state, event) pair
+    Illegal { from: StateId, event: Event },
+    /// Rules exist but none of their guards passed
+    GuardRejected { from: StateId, event: Event },
+    /// Another writer moved the stored state of `tx_id` away from `from` first, nothing was written
+    Stale { tx_id: String, from: StateId },
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionError::Illegal { from, event } => write!(f, "illegal transition: {} on {from}", event.name()),
+            TransitionError::GuardRejected { from, event } => write!(f, "no guard passed for {} on {from}", event.name()),
+            TransitionError::Stale { tx_id, from } => write!(f, "stored state of {tx_id} is no longer {from}"),
+        }
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+/// Problem found by `TransitionTable::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableIssue {
+    Unreachable(StateId),
+    /// Not terminal but without any outgoing rule
+    DeadEnd(StateId),
+}
+
+impl fmt::Display for TableIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableIssue::Unreachable(state) => write!(f, "state {state} is unreachable"),
+            TableIssue::DeadEnd(state) => write!(f, "state {state} is a dead end"),
+        }
+    }
+}
+
+pub struct TransitionTable {
+    initial: StateId,
+    terminals: HashSet<StateId>,
+    rules: Vec<Rule>,
+    /// Registered with `on`, also attached to the matching rules declared afterwards
+    actions: Vec<(StateId, StateId, Action)>,
+    observers: Vec<Arc<dyn TransitionObserver>>,
+}
+
+impl TransitionTable {
+    pub fn new(initial: StateId) -> Self {
+        Self {
+            initial,
+            terminals: HashSet::new(),
+            rules: Vec::new(),
+            actions: Vec::new(),
+            observers: Vec::new(),
+        }
+    }
+
+    /// Registers an observer called on every transition applied by a `StateMachine` using this table
+    pub fn observe(mut self, observer: Arc<dyn TransitionObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    fn notify(&self, record: &TransitionRecord) {
+        for observer in &self.observers {
+            observer.on_exit(&record.tx_id, record.from);
+            observer.on_transition(record);
+            observer.on_enter(&record.tx_id, record.to);
+        }
+    }
+
+    pub fn initial(&self) -> StateId {
+        self.initial
+    }
+
+    pub fn terminal(mut self, state: StateId) -> Self {
+        self.terminals.insert(state);
+        self
+    }
+
+    pub fn rule(self, from: StateId, event: Event, to: StateId) -> Self {
+        self.push(from, event, to, None)
+    }
+
+    pub fn guarded(self, from: StateId, event: Event, to: StateId, guard_name: &'static str, guard: Guard) -> Self {
+        self.push(from, event, to, Some((guard_name, guard)))
+    }
+
+    fn push(mut self, from: StateId, event: Event, to: StateId, guard: Option<(&'static str, Guard)>) -> Self {
+        let action = self.actions.iter().rev().find(|(f, t, _)| *f == from && *t == to).map(|(_, _, a)| a.clone());
+        self.rules.push(Rule { from, event, to, guard, action });
+        self
+    }
+
+    /// Attaches `action` to every rule going from `from` to `to`, whether declared before or after this call.
+    /// A rule runs a single action: calling `on` again for the same pair replaces the previous one, the last registration wins.
+    pub fn on(mut self, from: StateId, to: StateId, action: impl Fn(&TransitionContext, StateId, StateId) + Send + Sync + 'static) -> Self {
+        let action: Action = Arc::new(action);
+        for rule in self.rules.iter_mut().filter(|r| r.from == from && r.to == to) {
+            rule.action = Some(action.clone());
+        }
+        self.actions.push((from, to, action));
+        self
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn is_terminal(&self, state: StateId) -> bool {
+        self.terminals.contains(&state)
+    }
+
+    /// Finds the rule to apply, without running its action
+    pub fn resolve(&self, from: StateId, event: Event, ctx: &TransitionContext) -> Result<&Rule, TransitionError> {
+        let mut candidates = self.rules.iter().filter(|r| r.from == from && r.event == event).peekable();
+        if candidates.peek().is_none() {
+            return Err(TransitionError::Illegal { from, event });
+        }
+        candidates
+            .find(|r| r.guard.is_none_or(|(_, guard)| guard(ctx)))
+            .ok_or(TransitionError::GuardRejected { from, event })
+    }
+
+    /// Resolves the transition and runs its action
+    pub fn fire(&self, from: StateId, event: Event, ctx: &TransitionContext) -> Result<StateId, TransitionError> {
+        let rule = self.resolve(from, event, ctx)?;
+        if let Some(action) = &rule.action {
+            action(ctx, rule.from, rule.to);
+        }
+        Ok(rule.to)
+    }
+
+    /// Checks that every state is reachable from the initial state and that only terminal states have no way out
+    pub fn validate(&self) -> Result<(), Vec<TableIssue>> {
+        let mut reachable = HashSet::from([self.initial]);
+        let mut queue = VecDeque::from([self.initial]);
+        while let Some(state) = queue.pop_front() {
+            for rule in self.rules.iter().filter(|r| r.from == state) {
+                if reachable.insert(rule.to) {
+                    queue.push_back(rule.to);
+                }
+            }
+        }
+
+        let mut issues = Vec::new();
+        for state in StateId::ALL {
+            if !reachable.contains(&state) {
+                issues.push(TableIssue::Unreachable(state));
+            }
+            if !self.is_terminal(state) && !self.rules.iter().any(|r| r.from == state) {
+                issues.push(TableIssue::DeadEnd(state));
+            }
+        }
+
+        if issues.is_empty() { Ok(()) } else { Err(issues) }
+    }
+
+    fn label(rule: &Rule) -> String {
+        match rule.guard {
+            Some((name, _)) => format!("{} [{}]", rule.event.name(), name),
+            None => rule.event.name().to_string(),
+        }
+    }
+
+    /// Mermaid `stateDiagram-v2` source
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("stateDiagram-v2\n");
+        out.push_str(&format!("    [*] --> {}\n", self.initial));
+        for rule in &self.rules {
+            out.push_str(&format!("    {} --> {}: {}\n", rule.from, rule.to, Self::label(rule)));
+        }
+        for state in StateId::ALL.iter().filter(|s| self.is_terminal(**s)) {
+            out.push_str(&format!("    {state} --> [*]\n"));
+        }
+        out
+    }
+
+    /// Graphviz `dot` source
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph transactions {\n    rankdir=LR;\n    start [shape=point];\n");
+        for state in StateId::ALL {
+            let shape = if self.is_terminal(state) { "doublecircle" } else { "circle" };
+            out.push_str(&format!("    {state} [shape={shape}];\n"));
+        }
+        out.push_str(&format!("    start -> {};\n", self.initial));
+        for rule in &self.rules {
+            out.push_str(&format!("    {} -> {} [label=\"{}\"];\n", rule.from, rule.to, Self::label(rule)));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn is_fraud(ctx: &TransitionContext) -> bool {
+    ctx.score.as_ref().is_some_and(|s| s.is_fraud)
+}
+
+fn is_clean(ctx: &TransitionContext) -> bool {
+    ctx.score.as_ref().is_some_and(|s| !s.is_fraud)
+}
+
+fn has_analyst(ctx: &TransitionContext) -> bool {
+    ctx.actor.as_deref().is_some_and(|a| !a.is_empty())
+}
+
+/// The transaction lifecycle used by the library
+pub fn standard_table() -> TransitionTable {
+    TransitionTable::new(StateId::Validated)
+        .rule(StateId::Validated, Event::Process, StateId::Enriched)
+        .rule(StateId::Enriched, Event::Scored, StateId::Scored)
+        .guarded(StateId::Scored, Event::Persist, StateId::FlaggedAsFraud, "is_fraud", is_fraud)
+        .guarded(StateId::Scored, Event::Persist, StateId::Persisted, "is_clean", is_clean)
+        // Manual review: a flag opens a case instead of ending the lifecycle
+        .guarded(StateId::FlaggedAsFraud, Event::AssignToAnalyst, StateId::UnderReview, "has_analyst", has_analyst)
+        .rule(StateId::FlaggedAsFraud, Event::Escalate, StateId::UnderReview)
+        .guarded(StateId::UnderReview, Event::AssignToAnalyst, StateId::UnderReview, "has_analyst", has_analyst)
+        .rule(StateId::UnderReview, Event::Escalate, StateId::UnderReview)
+        .guarded(StateId::UnderReview, Event::ConfirmFraud, StateId::ConfirmedFraud, "has_analyst", has_analyst)
+        .guarded(StateId::UnderReview, Event::Clear, StateId::Cleared, "has_analyst", has_analyst)
+        .rule(StateId::UnderReview, Event::ReviewTimeout, StateId::Cleared)
+        // Post-settlement feedback reopens settled transactions
+        .rule(StateId::Persisted, Event::CustomerDisputed, StateId::Disputed)
+        .rule(StateId::Cleared, Event::CustomerDisputed, StateId::Disputed)
+        .rule(StateId::Persisted, Event::Chargeback, StateId::ChargedBack)
+        .rule(StateId::Cleared, Event::Chargeback, StateId::ChargedBack)
+        .rule(StateId::ConfirmedFraud, Event::Chargeback, StateId::ChargedBack)
+        .rule(StateId::Disputed, Event::Chargeback, StateId::ChargedBack)
+        .rule(StateId::Persisted, Event::Refund, StateId::Refunded)
+        .rule(StateId::Cleared, Event::Refund, StateId::Refunded)
+        .rule(StateId::Disputed, Event::Refund, StateId::Refunded)
+        // Terminal as far as the decision goes, settled ones can still be reopened by feedback
+        .terminal(StateId::Persisted)
+        .terminal(StateId::ConfirmedFraud)
+        .terminal(StateId::Cleared)
+        .terminal(StateId::ChargedBack)
+        .terminal(StateId::Refunded)
+}
+
+/// One transaction's position in a `TransitionTable`
+pub struct StateMachine {
+    table: Arc<TransitionTable>,
+    current: StateId,
+    history: Vec<TransitionRecord>,
+    /// State the repository is expected to hold, checked by `fire_and_record`: `None` for a new machine
+    stored: Option<StateId>,
+}
+
+impl StateMachine {
+    pub fn new(table: Arc<TransitionTable>) -> Self {
+        let current = table.initial();
+        Self {
+            table,
+            current,
+            history: Vec::new(),
+            stored: None,
+        }
+    }
+
+    /// Resumes a machine from a known state, the one stored for its transaction
+    pub fn at(table: Arc<TransitionTable>, current: StateId) -> Self {
+        Self {
+            table,
+            current,
+            history: Vec::new(),
+            stored: Some(current),
+        }
+    }
+
+    pub fn current(&self) -> StateId {
+        self.current
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.table.is_terminal(self.current)
+    }
+
+    /// Transitions applied by this machine, oldest first
+    pub fn history(&self) -> &[TransitionRecord] {
+        &self.history
+    }
+
+    /// Resolves `event` from `from` and builds its record, without running the action
+    fn prepare(&self, from: StateId, event: Event, ctx: &TransitionContext) -> Result<(TransitionRecord, Option<Action>), TransitionError> {
+        let rule = self.table.resolve(from, event, ctx).inspect_err(|e| {
+            debug!(tx_id = %ctx.tx_id, %from, event = event.name(), reason = %e, "Transition rejected");
+        })?;
+        let record = TransitionRecord {
+            tx_id: ctx.tx_id.clone(),
+            from,
+            to: rule.to,
+            event,
+            at: now_millis(),
+            actor: ctx.actor.clone(),
+            notes: ctx.notes.clone(),
+        };
+        Ok((record, rule.action.clone()))
+    }
+
+    /// Moves the machine along `record`: runs the rule's action and notifies the observers
+    fn apply(&mut self, record: TransitionRecord, action: Option<Action>, ctx: &TransitionContext) {
+        if let Some(action) = action {
+            action(ctx, record.from, record.to);
+        }
+        info!(
+            tx_id = %record.tx_id,
+            from = %record.from,
+            to = %record.to,
+            reason = record.event.name(),
+            actor = record.actor.as_deref(),
+            notes = record.notes.as_deref(),
+            "Transition"
+        );
+        self.current = record.to;
+        self.table.notify(&record);
+        self.history.push(record);
+    }
+
+    /// Applies `event`, the state is left unchanged on error
+    pub fn fire(&mut self, event: Event, ctx: &TransitionContext) -> Result<StateId, TransitionError> {
+        let (record, action) = self.prepare(self.current, event, ctx)?;
+        self.apply(record, action, ctx);
+        Ok(self.current)
+    }
+
+    /// Same as `fire`, but the transition is applied only once `write` stored its record. When `write`
+    /// returns false (the stored state moved meanwhile) no action runs, the machine is left unchanged
+    /// and the result is `TransitionError::Stale`.
+    pub fn fire_with(&mut self, event: Event, ctx: &TransitionContext, write: impl FnOnce(&TransitionRecord) -> bool) -> Result<StateId, TransitionError> {
+        let (record, action) = self.prepare(self.current, event, ctx)?;
+        if !write(&record) {
+            debug!(tx_id = %record.tx_id, from = %record.from, event = record.event.name(), "Stale transition dropped");
+            return Err(TransitionError::Stale { tx_id: record.tx_id, from: record.from });
+        }
+        self.stored = Some(record.to);
+        self.apply(record, action, ctx);
+        Ok(self.current)
+    }
+
+    /// Resumes the machine of `tx_id` from its stored state, if any
+    pub fn load(table: Arc<TransitionTable>, repo: &dyn StateRepository, tx_id: &str) -> Option<Self> {
+        repo.get_state(tx_id).map(|current| Self::at(table, current))
+    }
+
+    /// Same as `fire`, writing the new state and the transition to `repo` only if the stored state is still
+    /// the one this machine was loaded with (none for `new`): two writers racing on a transaction can't both win.
+    pub fn fire_and_record(&mut self, event: Event, ctx: &TransitionContext, repo: &dyn StateRepository) -> Result<StateId, TransitionError> {
+        let expected = self.stored;
+        self.fire_with(event, ctx, |record| repo.record_transition_if(expected, record.clone()))
+    }
+
+    /// Records the pipeline's path for a scored transaction: `Process`, `Scored`, then `Persist` to
+    /// `Persisted` or `FlaggedAsFraud`, all three in one write. Returns the transitions applied, none for
+    /// a transaction that already has a stored state (a retry, or a concurrent run that got there first).
+    pub fn record_decision(table: Arc<TransitionTable>, repo: &dyn StateRepository, score: &Score) -> Result<Vec<TransitionRecord>, TransitionError> {
+        let mut fsm = Self::new(table);
+        let ctx = TransitionContext::new(score.id.clone()).with_score(score.clone());
+        let mut steps = Vec::new();
+        let mut at = fsm.current;
+        for event in [Event::Process, Event::Scored, Event::Persist] {
+            let step = fsm.prepare(at, event, &ctx)?;
+            at = step.0.to;
+            steps.push(step);
+        }
+        let records: Vec<TransitionRecord> = steps.iter().map(|(record, _)| record.clone()).collect();
+        if !repo.record_transitions_if(None, &records) {
+            return Ok(Vec::new());
+        }
+        for (record, action) in steps {
+            fsm.apply(record, action, &ctx);
+        }
+        Ok(fsm.history)
+    }
+}
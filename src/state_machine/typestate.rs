@@ -0,0 +1,568 @@
+// src/state_machine/typestate.rs
+
+//! Compile-time version of the transaction lifecycle.
+//!
+//! `TxFlow<S>` carries the `Transaction` and the data accumulated so far; each transition consumes
+//! the flow and returns it in its next state, so only the legal path exists:
+//!
+//! `TxFlow<Validated>` -> `TxFlow<Enriched>` -> `TxFlow<Scored>` -> `TxFlow<Persisted>` | `TxFlow<FlaggedAsFraud>`
+//!
+//! and for flagged transactions, the manual review:
+//!
+//! `TxFlow<FlaggedAsFraud>` -> `TxFlow<UnderReview>` -> `TxFlow<ConfirmedFraud>` | `TxFlow<Cleared>`
+//!
+//! Settled flows can be reopened by post-settlement feedback:
+//!
+//! `TxFlow<Persisted>` | `TxFlow<Cleared>` -> `TxFlow<Disputed>` -> `TxFlow<ChargedBack>` | `TxFlow<Refunded>`
+//!
+//! A flow never goes back into the decision: a persisted flow can only be reopened by feedback, and
+//! `ChargedBack` and `Refunded` have no transition at all:
+//!
+//! ```compile_fail
+//! # use fraud_detection_3::state_machine::typestate::{TxFlow, Persisted};
+//! fn rescore(flow: TxFlow<Persisted>) {
+//!     flow.enrich(); // no such method on a persisted flow
+//! }
+//! ```
+//!
+//! Steps can't be skipped either:
+//!
+//! ```compile_fail
+//! # use fraud_detection_3::domain::fraud_scorer::RuleBasedScorer;
+//! # use fraud_detection_3::state_machine::typestate::{TxFlow, Validated};
+//! fn skip(flow: TxFlow<Validated>) {
+//!     flow.score(&RuleBasedScorer); // must be enriched first
+//! }
+//! ```
+//!
+//! `AnyTxFlow` is the dynamic form, used to store a flow and load it back whatever its state.
+
+use super::transitions::StateId;
+use crate::domain::clock::now_millis;
+use crate::domain::fraud_scorer::FraudScorer;
+use crate::domain::repository::{RepoError, SaveOutcome, ScoreRepository, TransRepository};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::{InvalidTransaction, Transaction};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validated;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enriched {
+    pub enriched_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scored {
+    pub enriched_at: i64,
+    pub score: Score,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Persisted {
+    pub score: Score,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlaggedAsFraud {
+    pub score: Score,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnderReview {
+    pub score: Score,
+    /// `None` while the case waits in the queue
+    pub analyst_id: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfirmedFraud {
+    pub score: Score,
+    pub analyst_id: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cleared {
+    pub score: Score,
+    /// `None` when cleared by the review timeout
+    pub analyst_id: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disputed {
+    pub score: Score,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChargedBack {
+    pub score: Score,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Refunded {
+    pub score: Score,
+    pub notes: Option<String>,
+}
+
+/// States a chargeback can reach: the original score is kept for the label
+pub trait Chargeable {
+    fn into_score(self) -> Score;
+}
+
+/// States a refund or a customer dispute can reach
+pub trait Refundable: Chargeable {}
+
+impl Chargeable for Persisted {
+    fn into_score(self) -> Score {
+        self.score
+    }
+}
+
+impl Chargeable for Cleared {
+    fn into_score(self) -> Score {
+        self.score
+    }
+}
+
+impl Chargeable for ConfirmedFraud {
+    fn into_score(self) -> Score {
+        self.score
+    }
+}
+
+impl Chargeable for Disputed {
+    fn into_score(self) -> Score {
+        self.score
+    }
+}
+
+impl Refundable for Persisted {}
+impl Refundable for Cleared {}
+impl Refundable for Disputed {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxFlow<S> {
+    tx: Transaction,
+    state: S,
+}
+
+impl<S> TxFlow<S> {
+    pub fn transaction(&self) -> &Transaction {
+        &self.tx
+    }
+
+    /// Data accumulated up to this state
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn into_parts(self) -> (Transaction, S) {
+        (self.tx, self.state)
+    }
+}
+
+impl TxFlow<Validated> {
+    /// The only way into the lifecycle
+    pub fn validate(tx: Transaction) -> Result<Self, InvalidTransaction> {
+        tx.validate()?;
+        Ok(Self { tx, state: Validated })
+    }
+
+    pub fn enrich(self) -> TxFlow<Enriched> {
+        TxFlow {
+            tx: self.tx,
+            state: Enriched { enriched_at: now_millis() },
+        }
+    }
+}
+
+impl TxFlow<Enriched> {
+    pub fn score(self, scorer: &dyn FraudScorer) -> TxFlow<Scored> {
+        let score = scorer.score_batch(std::slice::from_ref(&self.tx)).pop().expect("scorer returned no score");
+        TxFlow {
+            tx: self.tx,
+            state: Scored {
+                enriched_at: self.state.enriched_at,
+                score,
+            },
+        }
+    }
+}
+
+/// Where a scored flow ends up
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decided {
+    Persisted(TxFlow<Persisted>),
+    FlaggedAsFraud(TxFlow<FlaggedAsFraud>),
+}
+
+impl TxFlow<Scored> {
+    /// Saves the transaction and its score, then moves to the final state matching the score.
+    /// A retry keeps the score stored the first time and decides from it.
+    pub fn persist<TR: TransRepository + ?Sized, SR: ScoreRepository + ?Sized>(mut self, tx_repo: &TR, score_repo: &SR) -> Result<Decided, RepoError> {
+        let stored = match tx_repo.save_idempotent(self.tx.clone())? {
+            SaveOutcome::Inserted => None,
+            SaveOutcome::Duplicate => score_repo.get(&self.tx.id),
+        };
+        match stored {
+            Some(score) => self.state.score = score,
+            // First attempt, or a retry of one that stopped before its score was written
+            None => score_repo.save(self.state.score.clone()),
+        }
+        Ok(self.decide())
+    }
+
+    /// Final state without touching any repository
+    pub fn decide(self) -> Decided {
+        let score = self.state.score;
+        if score.is_fraud {
+            Decided::FlaggedAsFraud(TxFlow {
+                tx: self.tx,
+                state: FlaggedAsFraud { score },
+            })
+        } else {
+            Decided::Persisted(TxFlow {
+                tx: self.tx,
+                state: Persisted { score },
+            })
+        }
+    }
+}
+
+impl TxFlow<FlaggedAsFraud> {
+    pub fn assign_to(self, analyst_id: impl Into<String>, notes: Option<String>) -> TxFlow<UnderReview> {
+        TxFlow {
+            tx: self.tx,
+            state: UnderReview {
+                score: self.state.score,
+                analyst_id: Some(analyst_id.into()),
+                notes,
+            },
+        }
+    }
+
+    /// Opens the case without an analyst
+    pub fn escalate(self, notes: Option<String>) -> TxFlow<UnderReview> {
+        TxFlow {
+            tx: self.tx,
+            state: UnderReview {
+                score: self.state.score,
+                analyst_id: None,
+                notes,
+            },
+        }
+    }
+}
+
+impl TxFlow<UnderReview> {
+    /// Assigns or reassigns the case
+    pub fn assign_to(self, analyst_id: impl Into<String>, notes: Option<String>) -> TxFlow<UnderReview> {
+        TxFlow {
+            tx: self.tx,
+            state: UnderReview {
+                score: self.state.score,
+                analyst_id: Some(analyst_id.into()),
+                notes,
+            },
+        }
+    }
+
+    /// Sends the case back to the queue, for a more senior analyst
+    pub fn escalate(self, notes: Option<String>) -> TxFlow<UnderReview> {
+        TxFlow {
+            tx: self.tx,
+            state: UnderReview {
+                score: self.state.score,
+                analyst_id: None,
+                notes,
+            },
+        }
+    }
+
+    pub fn confirm_fraud(self, analyst_id: impl Into<String>, notes: Option<String>) -> TxFlow<ConfirmedFraud> {
+        TxFlow {
+            tx: self.tx,
+            state: ConfirmedFraud {
+                score: self.state.score,
+                analyst_id: analyst_id.into(),
+                notes,
+            },
+        }
+    }
+
+    pub fn clear(self, analyst_id: impl Into<String>, notes: Option<String>) -> TxFlow<Cleared> {
+        TxFlow {
+            tx: self.tx,
+            state: Cleared {
+                score: self.state.score,
+                analyst_id: Some(analyst_id.into()),
+                notes,
+            },
+        }
+    }
+
+    /// Auto-approval when nobody reviewed the case in time
+    pub fn review_timeout(self) -> TxFlow<Cleared> {
+        TxFlow {
+            tx: self.tx,
+            state: Cleared {
+                score: self.state.score,
+                analyst_id: None,
+                notes: Some("review timeout".to_string()),
+            },
+        }
+    }
+}
+
+impl<S: Chargeable> TxFlow<S> {
+    pub fn chargeback(self, notes: Option<String>) -> TxFlow<ChargedBack> {
+        TxFlow {
+            tx: self.tx,
+            state: ChargedBack {
+                score: self.state.into_score(),
+                notes,
+            },
+        }
+    }
+}
+
+impl<S: Refundable> TxFlow<S> {
+    pub fn refund(self, notes: Option<String>) -> TxFlow<Refunded> {
+        TxFlow {
+            tx: self.tx,
+            state: Refunded {
+                score: self.state.into_score(),
+                notes,
+            },
+        }
+    }
+}
+
+impl TxFlow<Persisted> {
+    pub fn dispute(self, notes: Option<String>) -> TxFlow<Disputed> {
+        TxFlow {
+            tx: self.tx,
+            state: Disputed { score: self.state.score, notes },
+        }
+    }
+}
+
+impl TxFlow<Cleared> {
+    pub fn dispute(self, notes: Option<String>) -> TxFlow<Disputed> {
+        TxFlow {
+            tx: self.tx,
+            state: Disputed { score: self.state.score, notes },
+        }
+    }
+}
+
+/// A flow in any state, for storage and for code that only learns the state at runtime
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnyTxFlow {
+    Validated(TxFlow<Validated>),
+    Enriched(TxFlow<Enriched>),
+    Scored(TxFlow<Scored>),
+    Persisted(TxFlow<Persisted>),
+    FlaggedAsFraud(TxFlow<FlaggedAsFraud>),
+    UnderReview(TxFlow<UnderReview>),
+    ConfirmedFraud(TxFlow<ConfirmedFraud>),
+    Cleared(TxFlow<Cleared>),
+    Disputed(TxFlow<Disputed>),
+    ChargedBack(TxFlow<ChargedBack>),
+    Refunded(TxFlow<Refunded>),
+}
+
+/// Flat, storable form of `AnyTxFlow`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FlowRecord {
+    pub tx: Transaction,
+    pub state: StateId,
+    pub enriched_at: Option<i64>,
+    pub score: Option<Score>,
+    pub analyst_id: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InconsistentRecord {
+    pub state: StateId,
+    pub missing: &'static str,
+}
+
+impl fmt::Display for InconsistentRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "record in state {} has no {}", self.state, self.missing)
+    }
+}
+
+impl std::error::Error for InconsistentRecord {}
+
+impl AnyTxFlow {
+    pub fn state_id(&self) -> StateId {
+        match self {
+            AnyTxFlow::Validated(_) => StateId::Validated,
+            AnyTxFlow::Enriched(_) => StateId::Enriched,
+            AnyTxFlow::Scored(_) => StateId::Scored,
+            AnyTxFlow::Persisted(_) => StateId::Persisted,
+            AnyTxFlow::FlaggedAsFraud(_) => StateId::FlaggedAsFraud,
+            AnyTxFlow::UnderReview(_) => StateId::UnderReview,
+            AnyTxFlow::ConfirmedFraud(_) => StateId::ConfirmedFraud,
+            AnyTxFlow::Cleared(_) => StateId::Cleared,
+            AnyTxFlow::Disputed(_) => StateId::Disputed,
+            AnyTxFlow::ChargedBack(_) => StateId::ChargedBack,
+            AnyTxFlow::Refunded(_) => StateId::Refunded,
+        }
+    }
+
+    pub fn transaction(&self) -> &Transaction {
+        match self {
+            AnyTxFlow::Validated(f) => f.transaction(),
+            AnyTxFlow::Enriched(f) => f.transaction(),
+            AnyTxFlow::Scored(f) => f.transaction(),
+            AnyTxFlow::Persisted(f) => f.transaction(),
+            AnyTxFlow::FlaggedAsFraud(f) => f.transaction(),
+            AnyTxFlow::UnderReview(f) => f.transaction(),
+            AnyTxFlow::ConfirmedFraud(f) => f.transaction(),
+            AnyTxFlow::Cleared(f) => f.transaction(),
+            AnyTxFlow::Disputed(f) => f.transaction(),
+            AnyTxFlow::ChargedBack(f) => f.transaction(),
+            AnyTxFlow::Refunded(f) => f.transaction(),
+        }
+    }
+
+    pub fn into_record(self) -> FlowRecord {
+        let state = self.state_id();
+        let (tx, enriched_at, score, analyst_id, notes) = match self {
+            AnyTxFlow::Validated(f) => (f.tx, None, None, None, None),
+            AnyTxFlow::Enriched(f) => (f.tx, Some(f.state.enriched_at), None, None, None),
+            AnyTxFlow::Scored(f) => (f.tx, Some(f.state.enriched_at), Some(f.state.score), None, None),
+            AnyTxFlow::Persisted(f) => (f.tx, None, Some(f.state.score), None, None),
+            AnyTxFlow::FlaggedAsFraud(f) => (f.tx, None, Some(f.state.score), None, None),
+            AnyTxFlow::UnderReview(f) => (f.tx, None, Some(f.state.score), f.state.analyst_id, f.state.notes),
+            AnyTxFlow::ConfirmedFraud(f) => (f.tx, None, Some(f.state.score), Some(f.state.analyst_id), f.state.notes),
+            AnyTxFlow::Cleared(f) => (f.tx, None, Some(f.state.score), f.state.analyst_id, f.state.notes),
+            AnyTxFlow::Disputed(f) => (f.tx, None, Some(f.state.score), None, f.state.notes),
+            AnyTxFlow::ChargedBack(f) => (f.tx, None, Some(f.state.score), None, f.state.notes),
+            AnyTxFlow::Refunded(f) => (f.tx, None, Some(f.state.score), None, f.state.notes),
+        };
+        FlowRecord {
+            tx,
+            state,
+            enriched_at,
+            score,
+            analyst_id,
+            notes,
+        }
+    }
+}
+
+impl TryFrom<FlowRecord> for AnyTxFlow {
+    type Error = InconsistentRecord;
+
+    fn try_from(record: FlowRecord) -> Result<Self, Self::Error> {
+        let state = record.state;
+        let missing = |missing| InconsistentRecord { state, missing };
+        let tx = record.tx;
+
+        Ok(match state {
+            StateId::Validated => AnyTxFlow::Validated(TxFlow { tx, state: Validated }),
+            StateId::Enriched => AnyTxFlow::Enriched(TxFlow {
+                tx,
+                state: Enriched {
+                    enriched_at: record.enriched_at.ok_or(missing("enriched_at"))?,
+                },
+            }),
+            StateId::Scored => AnyTxFlow::Scored(TxFlow {
+                tx,
+                state: Scored {
+                    enriched_at: record.enriched_at.ok_or(missing("enriched_at"))?,
+                    score: record.score.ok_or(missing("score"))?,
+                },
+            }),
+            StateId::Persisted => AnyTxFlow::Persisted(TxFlow {
+                tx,
+                state: Persisted {
+                    score: record.score.ok_or(missing("score"))?,
+                },
+            }),
+            StateId::FlaggedAsFraud => AnyTxFlow::FlaggedAsFraud(TxFlow {
+                tx,
+                state: FlaggedAsFraud {
+                    score: record.score.ok_or(missing("score"))?,
+                },
+            }),
+            StateId::UnderReview => AnyTxFlow::UnderReview(TxFlow {
+                tx,
+                state: UnderReview {
+                    score: record.score.ok_or(missing("score"))?,
+                    analyst_id: record.analyst_id,
+                    notes: record.notes,
+                },
+            }),
+            StateId::ConfirmedFraud => AnyTxFlow::ConfirmedFraud(TxFlow {
+                tx,
+                state: ConfirmedFraud {
+                    score: record.score.ok_or(missing("score"))?,
+                    analyst_id: record.analyst_id.ok_or(missing("analyst_id"))?,
+                    notes: record.notes,
+                },
+            }),
+            StateId::Cleared => AnyTxFlow::Cleared(TxFlow {
+                tx,
+                state: Cleared {
+                    score: record.score.ok_or(missing("score"))?,
+                    analyst_id: record.analyst_id,
+                    notes: record.notes,
+                },
+            }),
+            StateId::Disputed => AnyTxFlow::Disputed(TxFlow {
+                tx,
+                state: Disputed {
+                    score: record.score.ok_or(missing("score"))?,
+                    notes: record.notes,
+                },
+            }),
+            StateId::ChargedBack => AnyTxFlow::ChargedBack(TxFlow {
+                tx,
+                state: ChargedBack {
+                    score: record.score.ok_or(missing("score"))?,
+                    notes: record.notes,
+                },
+            }),
+            StateId::Refunded => AnyTxFlow::Refunded(TxFlow {
+                tx,
+                state: Refunded {
+                    score: record.score.ok_or(missing("score"))?,
+                    notes: record.notes,
+                },
+            }),
+        })
+    }
+}
+
+macro_rules! impl_from_flow {
+    ($($state:ident),*) => {
+        $(impl From<TxFlow<$state>> for AnyTxFlow {
+            fn from(flow: TxFlow<$state>) -> Self {
+                AnyTxFlow::$state(flow)
+            }
+        })*
+    };
+}
+
+impl_from_flow!(Validated, Enriched, Scored, Persisted, FlaggedAsFraud, UnderReview, ConfirmedFraud, Cleared, Disputed, ChargedBack, Refunded);
+
+impl From<Decided> for AnyTxFlow {
+    fn from(decided: Decided) -> Self {
+        match decided {
+            Decided::Persisted(f) => f.into(),
+            Decided::FlaggedAsFraud(f) => f.into(),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+// src/telemetry/mod.rs
+
+// Spans following one transaction end to end:
+//
+//   transaction{tx_id}                          created at ingestion, carried with the item through every channel
+//   ├── stage{stage="validation"}               one per pipeline stage
+//   ├── stage{stage="persistence"}              or per step of a dispatcher worker
+//   ├── stage{stage="state_machine"}            async state machine, `run_transaction`
+//   │   └── stage{stage="scoring",worker_id}    in the `ScoringPool` worker
+//   └── ...
+//
+// A batch worker handles several transactions at once: its `batch` span follows from each of their spans.
+//
+// The spans go wherever the subscriber sends them: the log lines of a `fmt` layer carry their fields,
+// and with the `otlp` feature `otlp::layer` exports them to an OpenTelemetry collector.
+
+#[cfg(feature = "otlp")]
+pub mod otlp;
+
+use crate::domain::transaction::Transaction;
+use tracing::{Span, info_span};
+
+/// The root span of `tx`, to create where it enters the system
+pub fn transaction_span(tx: &Transaction) -> Span {
+    info_span!("transaction", tx_id = %tx.id)
+}
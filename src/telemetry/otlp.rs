@@ -0,0 +1,58 @@
+// src/telemetry/otlp.rs
+
+// Export of the tracing spans to an OpenTelemetry collector over OTLP/gRPC, e.g. a local one started with
+//     docker run -p 4317:4317 otel/opentelemetry-collector
+// Spans are batched in the background: keep the `OtlpGuard` alive until exit so the last ones get flushed.
+
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::fmt;
+use tracing::Subscriber;
+use tracing_subscriber::Layer;
+use tracing_subscriber::registry::LookupSpan;
+
+const SERVICE_NAME: &str = "fraud_detection_3";
+
+#[derive(Debug)]
+pub struct OtlpError(String);
+
+impl fmt::Display for OtlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to set up OTLP export: {}", self.0)
+    }
+}
+
+impl std::error::Error for OtlpError {}
+
+/// Flushes and stops the exporter when dropped
+pub struct OtlpGuard {
+    provider: SdkTracerProvider,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("OTLP exporter shutdown failed: {e}");
+        }
+    }
+}
+
+/// A layer sending every span to the collector at `endpoint`. Call it inside a Tokio runtime.
+pub fn layer<S>(endpoint: &str) -> Result<(impl Layer<S>, OtlpGuard), OtlpError>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| OtlpError(e.to_string()))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+        .build();
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME));
+    Ok((layer, OtlpGuard { provider }))
+}
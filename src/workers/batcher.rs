@@ -0,0 +1,183 @@
+// src/workers/batcher.rs
+
+// Micro-batching stage between the dispatcher channel and the scorers.
+// Transactions are buffered until `max_size` is reached or `max_wait` has elapsed since the first
+// one arrived, then the whole batch goes through one `save_idempotent_batch`, one `score_batch`
+// and one `save_batch` call. This amortizes the per-call cost of ML scoring and SQLite commits.
+
+use crate::domain::fraud_scorer::FraudScorer;
+use crate::domain::repository::{RepoError, SaveOutcome, ScoreRepository, TransRepository};
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use crate::workers::dispatcher::WorkerMessage;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::time::{Instant, timeout_at};
+use tracing::{info, info_span, warn};
+
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// Flush as soon as this many transactions are buffered
+    pub max_size: usize,
+    /// Flush at the latest this long after the first transaction of the batch arrived
+    pub max_wait: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 64,
+            max_wait: Duration::from_millis(10),
+        }
+    }
+}
+
+/// Saves, scores and persists one batch.
+/// Same idempotency rules as `dispatcher::process_transaction`: one result per transaction, in input order.
+/// Copies of one id within the batch are scored once and share the score.
+pub fn process_batch<TR, SR, S>(batch: Vec<Transaction>, tx_repo: &TR, score_repo: &SR, scorer: &S) -> Vec<Result<Score, RepoError>>
+where
+    TR: TransRepository + ?Sized,
+    SR: ScoreRepository + ?Sized,
+    S: FraudScorer + ?Sized,
+{
+    let outcomes = tx_repo.save_idempotent_batch(batch.clone());
+
+    let mut results: Vec<Option<Result<Score, RepoError>>> = Vec::with_capacity(batch.len());
+    let mut to_score = Vec::new();
+    let mut to_score_idx = Vec::new();
+    // Position in `to_score` of each id, and the later copies of these ids as (result index, position)
+    let mut pending: HashMap<String, usize> = HashMap::new();
+    let mut copies = Vec::new();
+
+    for (i, (tx, outcome)) in batch.into_iter().zip(outcomes).enumerate() {
+        match outcome {
+            Ok(_) if let Some(&pos) = pending.get(&tx.id) => {
+                results.push(None);
+                copies.push((i, pos));
+            }
+            Ok(SaveOutcome::Duplicate) if let Some(original) = score_repo.get(&tx.id) => results.push(Some(Ok(original))),
+            Ok(_) => {
+                results.push(None);
+                pending.insert(tx.id.clone(), to_score.len());
+                to_score_idx.push(i);
+                to_score.push(tx);
+            }
+            Err(e) => results.push(Some(Err(e))),
+        }
+    }
+
+    if !to_score.is_empty() {
+        let scores = scorer.score_batch(&to_score);
+        score_repo.save_batch(scores.clone());
+        for (i, pos) in copies {
+            results[i] = Some(Ok(scores[pos].clone()));
+        }
+        for (i, score) in to_score_idx.into_iter().zip(scores) {
+            results[i] = Some(Ok(score));
+        }
+    }
+
+    results.into_iter().map(|r| r.expect("every transaction has a result")).collect()
+}
+
+/// Batching counterpart of `dispatcher::start_worker`
+pub async fn start_batch_worker<TR, SR, S>(rx: Receiver<WorkerMessage>, tx_repo: Arc<TR>, score_repo: Arc<SR>, scorer: Arc<S>, config: BatchConfig)
+where
+    TR: TransRepository + ?Sized + 'static,
+    SR: ScoreRepository + ?Sized + 'static,
+    S: FraudScorer + Send + Sync + 'static,
+{
+    batch_loop(rx, tx_repo, score_repo, scorer, config, None).await
+}
+
+/// Same as `start_batch_worker`, every score is also sent to `scored`, original scores of retries included
+pub async fn start_batch_worker_with_output<TR, SR, S>(
+    rx: Receiver<WorkerMessage>,
+    tx_repo: Arc<TR>,
+    score_repo: Arc<SR>,
+    scorer: Arc<S>,
+    config: BatchConfig,
+    scored: Sender<Score>,
+) where
+    TR: TransRepository + ?Sized + 'static,
+    SR: ScoreRepository + ?Sized + 'static,
+    S: FraudScorer + Send + Sync + 'static,
+{
+    batch_loop(rx, tx_repo, score_repo, scorer, config, Some(scored)).await
+}
+
+async fn batch_loop<TR, SR, S>(
+    mut rx: Receiver<WorkerMessage>,
+    tx_repo: Arc<TR>,
+    score_repo: Arc<SR>,
+    scorer: Arc<S>,
+    config: BatchConfig,
+    mut scored: Option<Sender<Score>>,
+) where
+    TR: TransRepository + ?Sized + 'static,
+    SR: ScoreRepository + ?Sized + 'static,
+    S: FraudScorer + Send + Sync + 'static,
+{
+    let max_size = config.max_size.max(1);
+    let mut batch = Vec::with_capacity(max_size);
+    // Spans of the traced transactions in `batch`, linked to the batch span
+    let mut spans = Vec::new();
+    let mut shutting_down = false;
+
+    while !shutting_down {
+        // Block until the first transaction of the next batch
+        match rx.recv().await {
+            Some(WorkerMessage::Transaction(tx)) => batch.push(tx),
+            Some(WorkerMessage::Traced { tx, span }) => {
+                batch.push(tx);
+                spans.push(span);
+            }
+            Some(WorkerMessage::Shutdown) | None => break,
+        }
+
+        let deadline = Instant::now() + config.max_wait;
+        while batch.len() < max_size {
+            match timeout_at(deadline, rx.recv()).await {
+                Ok(Some(WorkerMessage::Transaction(tx))) => batch.push(tx),
+                Ok(Some(WorkerMessage::Traced { tx, span })) => {
+                    batch.push(tx);
+                    spans.push(span);
+                }
+                Ok(Some(WorkerMessage::Shutdown)) | Ok(None) => {
+                    shutting_down = true;
+                    break;
+                }
+                Err(_elapsed) => break,
+            }
+        }
+
+        let size = batch.len();
+        let batch_span = info_span!("batch", size);
+        for span in spans.drain(..) {
+            batch_span.follows_from(&span);
+        }
+        let results = batch_span.in_scope(|| {
+            let results = process_batch(std::mem::take(&mut batch), tx_repo.as_ref(), score_repo.as_ref(), scorer.as_ref());
+            info!(size, "Batch processed");
+            results
+        });
+        for result in results {
+            match result {
+                Ok(score) => {
+                    if let Some(out) = &scored
+                        && out.send(score).await.is_err()
+                    {
+                        warn!("Score receiver closed, discarding scores");
+                        scored = None;
+                    }
+                }
+                Err(e) => warn!(error = %e, "Transaction rejected"),
+            }
+        }
+    }
+
+    info!("Batch worker shutting down.");
+}
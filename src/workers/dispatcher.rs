@@ -9,18 +9,17 @@
 // Use `#[cfg(feature = "bench")]` for benchmark-specific code.
 
 // Used in both runtime and bench mode → no cfg required
-use crate::domain::repository::{ScoreRepository, TransRepository};
+use crate::domain::repository::{RepoError, SaveOutcome, ScoreRepository, TransRepository};
 use crate::domain::scoring::Score;
 use crate::domain::transaction::Transaction;
+use crate::telemetry::transaction_span;
 use rand::random;
 use std::sync::Arc;
+use tracing::{Span, info, info_span /* , debug*/};
 
 // Should I use a cfg_if::cfg_if! {...} block ?
 #[cfg(not(feature = "bench"))]
-use {
-    tokio::sync::mpsc::Receiver,
-    tracing::{info /* , debug*/},
-};
+use {crate::metrics::Metrics, std::time::Instant, tokio::sync::mpsc::Receiver, tracing::warn};
 
 #[cfg(feature = "bench")]
 use crate::persistence::sqlite::{SQLiteScoreRepo, SQLiteTransRepo};
@@ -28,42 +27,111 @@ use crate::persistence::sqlite::{SQLiteScoreRepo, SQLiteTransRepo};
 #[derive(Debug)]
 pub enum WorkerMessage {
     Transaction(Transaction),
+    /// A transaction with the span it entered the system in, the worker processes it inside that span
+    Traced { tx: Transaction, span: Span },
     Shutdown,
 }
 
-// Updated start_worker
-#[cfg(not(feature = "bench"))]
-pub async fn start_worker<TR: TransRepository + Send + Sync + 'static, SR: ScoreRepository + Send + Sync + 'static>(mut rx: Receiver<WorkerMessage>, tx_repo: Arc<TR>, score_repo: Arc<SR>) {
-    while let Some(msg) = rx.recv().await {
-        match msg {
-            WorkerMessage::Transaction(tx) => {
-                info!(tx_id = %tx.id, "Processing transaction");
+impl WorkerMessage {
+    /// `tx` under a new `telemetry::transaction_span`
+    pub fn traced(tx: Transaction) -> Self {
+        let span = transaction_span(&tx);
+        WorkerMessage::Traced { tx, span }
+    }
+}
 
-                // Save transaction to DB
-                tx_repo.save(tx.clone());
-                info!(tx_id = %tx.id, "Transaction saved");
+/// Saves and scores one transaction.
+/// Idempotent on `tx.id`: a retried transaction gets its original score back instead of being rescored,
+/// a different payload under a known id is rejected with `RepoError::Conflict`.
+pub fn process_transaction<TR: TransRepository + ?Sized, SR: ScoreRepository + ?Sized>(tx: Transaction, tx_repo: &TR, score_repo: &SR) -> Result<Score, RepoError> {
+    // Save transaction to DB
+    let persistence = info_span!("stage", stage = "persistence").entered();
+    if tx_repo.save_idempotent(tx.clone())? == SaveOutcome::Duplicate {
+        if let Some(original) = score_repo.get(&tx.id) {
+            info!(tx_id = %tx.id, "Duplicate transaction, returning original score");
+            return Ok(original);
+        }
+        // The first attempt stopped before its score was saved: finish the job
+        info!(tx_id = %tx.id, "Duplicate transaction without score, scoring it");
+    }
+    info!(tx_id = %tx.id, "Transaction saved");
+
+    // Retrieve it back
+    if let Some(saved_tx) = tx_repo.get(&tx.id) {
+        info!(?saved_tx, "Transaction persisted");
+    }
+    drop(persistence);
 
-                // Retrieve it back
-                if let Some(saved_tx) = tx_repo.get(&tx.id) {
-                    info!(?saved_tx, "Transaction persisted");
-                }
+    // Generate a dummy score
+    // let mut rng = rand::thread_rng();
+    let (score, is_fraud) = info_span!("stage", stage = "scoring").in_scope(|| {
+        let score: f64 = random(); // value in [0.0, 1.0)
+        (score, score > 0.8)
+    });
 
-                // Generate a dummy score
-                // let mut rng = rand::thread_rng();
-                let score: f64 = random(); // value in [0.0, 1.0)
-                let is_fraud = score > 0.8;
+    // Build and persist scoring result
+    let result = Score { id: tx.id.clone(), score, is_fraud };
 
-                // Build and persist scoring result
-                let result = Score { id: tx.id.clone(), score, is_fraud };
+    let _score_persistence = info_span!("stage", stage = "score_persistence").entered();
+    score_repo.save(result.clone());
+    info!(?result, "Scoring result saved");
+    Ok(result)
+}
 
-                score_repo.save(result.clone());
-                info!(?result, "Scoring result saved");
-            }
+// Updated start_worker
+#[cfg(not(feature = "bench"))]
+pub async fn start_worker<TR: TransRepository + Send + Sync + 'static, SR: ScoreRepository + Send + Sync + 'static>(rx: Receiver<WorkerMessage>, tx_repo: Arc<TR>, score_repo: Arc<SR>) {
+    worker_loop(rx, tx_repo, score_repo, None).await
+}
 
+/// `start_worker` reporting outcomes, latency and queue depth to `metrics`, under `stage="dispatcher"`
+#[cfg(not(feature = "bench"))]
+pub async fn start_metered_worker<TR: TransRepository + Send + Sync + 'static, SR: ScoreRepository + Send + Sync + 'static>(
+    rx: Receiver<WorkerMessage>,
+    tx_repo: Arc<TR>,
+    score_repo: Arc<SR>,
+    metrics: Arc<Metrics>,
+) {
+    worker_loop(rx, tx_repo, score_repo, Some(metrics)).await
+}
+
+#[cfg(not(feature = "bench"))]
+async fn worker_loop<TR: TransRepository + Send + Sync + 'static, SR: ScoreRepository + Send + Sync + 'static>(
+    mut rx: Receiver<WorkerMessage>,
+    tx_repo: Arc<TR>,
+    score_repo: Arc<SR>,
+    metrics: Option<Arc<Metrics>>,
+) {
+    while let Some(msg) = rx.recv().await {
+        if let Some(metrics) = &metrics {
+            metrics.queue_depth.with(&["dispatcher"]).set(rx.len() as i64);
+        }
+        let (tx, span) = match msg {
+            WorkerMessage::Transaction(tx) => {
+                let span = transaction_span(&tx);
+                (tx, span)
+            }
+            WorkerMessage::Traced { tx, span } => (tx, span),
             WorkerMessage::Shutdown => {
                 info!("Worker shutting down.");
                 break;
             }
+        };
+        let _entered = span.enter();
+        info!(tx_id = %tx.id, "Processing transaction");
+
+        let tx_id = tx.id.clone();
+        let start = Instant::now();
+        let outcome = match process_transaction(tx, tx_repo.as_ref(), score_repo.as_ref()) {
+            Ok(_) => "scored",
+            Err(e) => {
+                warn!(tx_id = %tx_id, error = %e, "Transaction rejected");
+                "rejected"
+            }
+        };
+        if let Some(metrics) = &metrics {
+            metrics.stage_latency.with(&["dispatcher"]).observe_duration(start.elapsed());
+            metrics.transactions.with(&[outcome]).inc();
         }
     }
 }
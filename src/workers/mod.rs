@@ -1 +1,4 @@
+pub mod batcher;
 pub mod dispatcher;
+pub mod purge;
+pub mod scoring_pool;
@@ -0,0 +1,146 @@
+// src/workers/purge.rs
+
+// Scheduled purge of expired rows. Each batch is read, optionally archived, then deleted in its own short
+// SQL transaction, so the workers' inserts never wait long for the lock. The archive of a batch is on disk
+// before the batch is deleted: a crash in between archives those rows twice, it never loses them.
+
+use crate::domain::clock::now_millis;
+use crate::domain::repository::RetentionStore;
+use crate::domain::retention::{ExpiredRow, RetentionPolicy, RetentionTable};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone)]
+pub struct PurgeConfig {
+    pub policy: RetentionPolicy,
+    /// Rows read and deleted per SQL transaction
+    pub batch_size: usize,
+    /// Pause between two batches, lets the writers in
+    pub pause: Duration,
+    /// Purged rows are written to `<dir>/<table>-<run ms>.jsonl.gz` before being deleted, as stored:
+    /// encrypted columns stay encrypted under the column keys, tokenized account ids stay tokens.
+    pub archive_dir: Option<PathBuf>,
+}
+
+impl Default for PurgeConfig {
+    fn default() -> Self {
+        Self {
+            policy: RetentionPolicy::default(),
+            batch_size: 500,
+            pause: Duration::from_millis(20),
+            archive_dir: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PurgeError {
+    /// Nothing of the failed batch was deleted
+    Archive(io::Error),
+}
+
+impl fmt::Display for PurgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PurgeError::Archive(e) => write!(f, "failed to archive purged rows: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PurgeError {}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PurgeReport {
+    pub transactions: usize,
+    pub scores: usize,
+    /// Archive files written to during the run
+    pub archives: Vec<PathBuf>,
+}
+
+pub struct PurgeJob {
+    store: Arc<dyn RetentionStore>,
+    config: PurgeConfig,
+}
+
+impl PurgeJob {
+    pub fn new(store: Arc<dyn RetentionStore>, config: PurgeConfig) -> Self {
+        Self { store, config }
+    }
+
+    /// Purges every row expired at `now` (ms since epoch), batch after batch
+    pub fn run_once(&self, now: i64) -> Result<PurgeReport, PurgeError> {
+        let mut report = PurgeReport::default();
+        let batch_size = self.config.batch_size.max(1);
+        for table in RetentionTable::ALL {
+            let Some(cutoff) = self.config.policy.cutoff(table, now) else { continue };
+            let archive = self.config.archive_dir.as_ref().map(|dir| dir.join(format!("{}-{now}.jsonl.gz", table.name())));
+            let mut purged = 0;
+            loop {
+                let rows = self.store.expired(table, cutoff, self.config.policy.keep_fraud, batch_size);
+                if rows.is_empty() {
+                    break;
+                }
+                if let Some(path) = &archive {
+                    append_archive(path, &rows).map_err(PurgeError::Archive)?;
+                    if !report.archives.contains(path) {
+                        report.archives.push(path.clone());
+                    }
+                }
+                let ids: Vec<String> = rows.iter().map(|row| row.id().to_string()).collect();
+                let deleted = self.store.delete(table, &ids);
+                purged += deleted;
+                // Nothing deleted means someone else purged them, don't spin on the same rows
+                if rows.len() < batch_size || deleted == 0 {
+                    break;
+                }
+                std::thread::sleep(self.config.pause);
+            }
+            match table {
+                RetentionTable::Transactions => report.transactions = purged,
+                RetentionTable::Scores => report.scores = purged,
+            }
+        }
+        Ok(report)
+    }
+
+    /// Purges every `every`, the first run right away
+    pub fn spawn(self: Arc<Self>, every: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                let job = self.clone();
+                // Store access and archiving are blocking
+                match tokio::task::spawn_blocking(move || job.run_once(now_millis())).await {
+                    Ok(Ok(report)) => info!(transactions = report.transactions, scores = report.scores, "Purge done"),
+                    Ok(Err(e)) => warn!(error = %e, "Purge stopped"),
+                    Err(e) => warn!(error = %e, "Purge panicked"),
+                }
+            }
+        })
+    }
+}
+
+/// Appends `rows` as one gzip member: concatenated members read back as a single stream (`zcat`, `MultiGzDecoder`)
+fn append_archive(path: &Path, rows: &[ExpiredRow]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut gz = GzEncoder::new(BufWriter::new(file), Compression::default());
+    for row in rows {
+        serde_json::to_writer(&mut gz, row)?;
+        gz.write_all(b"\n")?;
+    }
+    let file = gz.finish()?.into_inner().map_err(|e| e.into_error())?;
+    // On disk before the rows are deleted
+    file.sync_data()
+}
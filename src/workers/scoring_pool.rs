@@ -0,0 +1,75 @@
+// src/workers/scoring_pool.rs
+
+// Pool of scoring workers answering through oneshot channels (see examples/00_state_machine_worker.rs).
+// The requester owns the oneshot receiver: dropping it cancels the request, a worker that picks up
+// a task whose receiver is gone skips the (expensive) scoring call.
+
+use crate::domain::fraud_scorer::FraudScorer;
+use crate::domain::scoring::Score;
+use crate::domain::transaction::Transaction;
+use std::sync::Arc;
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{Span, debug, info_span, warn};
+
+pub struct ScoringTask {
+    pub tx: Transaction,
+    pub reply: oneshot::Sender<Score>,
+    /// Span of the requester, the scoring span is its child
+    pub span: Span,
+}
+
+/// Cloneable handle on the workers' queue
+#[derive(Clone)]
+pub struct ScoringPool {
+    sender: mpsc::Sender<ScoringTask>,
+}
+
+impl ScoringPool {
+    /// Spawns `workers` tasks sharing a queue of `capacity` pending requests.
+    /// The workers stop once every `ScoringPool` clone has been dropped.
+    pub fn spawn<S: FraudScorer + Send + Sync + 'static>(scorer: Arc<S>, workers: usize, capacity: usize) -> (Self, Vec<JoinHandle<()>>) {
+        let (sender, rx) = mpsc::channel(capacity.max(1));
+        let rx = Arc::new(Mutex::new(rx));
+        let handles = (0..workers.max(1)).map(|worker_id| tokio::spawn(worker(worker_id, rx.clone(), scorer.clone()))).collect();
+        (Self { sender }, handles)
+    }
+
+    /// Queues `tx` and returns the receiver of its score.
+    /// Fails with the transaction back when the workers are gone.
+    pub async fn request(&self, tx: Transaction) -> Result<oneshot::Receiver<Score>, Transaction> {
+        let (reply, score_rx) = oneshot::channel();
+        let span = Span::current();
+        self.sender.send(ScoringTask { tx, reply, span }).await.map_err(|e| e.0.tx)?;
+        Ok(score_rx)
+    }
+}
+
+async fn worker<S: FraudScorer + Send + Sync + 'static>(worker_id: usize, rx: Arc<Mutex<mpsc::Receiver<ScoringTask>>>, scorer: Arc<S>) {
+    loop {
+        let task = { rx.lock().await.recv().await };
+        let Some(ScoringTask { tx, reply, span }) = task else { break };
+
+        if reply.is_closed() {
+            debug!(tx_id = %tx.id, worker_id, "Scoring request cancelled, skipping");
+            continue;
+        }
+
+        // Model calls are blocking: keep them off the async executor
+        let scorer = scorer.clone();
+        let tx_id = tx.id.clone();
+        let span = info_span!(parent: &span, "stage", stage = "scoring", worker_id);
+        let scored = tokio::task::spawn_blocking(move || span.in_scope(|| scorer.score_batch(std::slice::from_ref(&tx)).pop())).await;
+
+        match scored {
+            Ok(Some(score)) => {
+                if reply.send(score).is_err() {
+                    debug!(tx_id = %tx_id, worker_id, "Requester gone before the score was ready");
+                }
+            }
+            Ok(None) => warn!(tx_id = %tx_id, worker_id, "Scorer returned no score"),
+            Err(e) => warn!(tx_id = %tx_id, worker_id, error = %e, "Scorer panicked"),
+        }
+    }
+    debug!(worker_id, "Scoring worker stopped");
+}
@@ -0,0 +1,116 @@
+// tests/async_fsm.rs
+
+use fraud_detection_3::domain::fraud_scorer::FraudScorer;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::state_machine::async_fsm::{Decision, FallbackPolicy, FallbackReason, FsmConfig, run_transaction};
+use fraud_detection_3::state_machine::transitions::StateId;
+use fraud_detection_3::workers::scoring_pool::ScoringPool;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+fn tx(id: &str, amount: f64) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+// Flags everything above 1000 after `delay`, counts its calls
+struct SlowScorer {
+    delay: Duration,
+    calls: AtomicUsize,
+}
+
+impl SlowScorer {
+    fn new(delay: Duration) -> Arc<Self> {
+        Arc::new(Self { delay, calls: AtomicUsize::new(0) })
+    }
+}
+
+impl FraudScorer for SlowScorer {
+    fn is_fraud(&self, tx: &Transaction) -> bool {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        std::thread::sleep(self.delay);
+        tx.amount > 1000.0
+    }
+}
+
+#[tokio::test]
+async fn test_decision_from_worker() {
+    let (pool, _workers) = ScoringPool::spawn(SlowScorer::new(Duration::ZERO), 2, 8);
+    let config = FsmConfig::default();
+
+    let clean = run_transaction(tx("tx-1", 10.0), &pool, &config).await;
+    let fraud = run_transaction(tx("tx-2", 5000.0), &pool, &config).await;
+
+    assert!(matches!(clean, Decision::Clean(_)));
+    assert!(matches!(fraud, Decision::Fraud(_)));
+    assert_eq!(fraud.state(), StateId::FlaggedAsFraud);
+    assert_eq!(clean.state_name(), StateId::Persisted.name());
+}
+
+#[tokio::test]
+async fn test_timeout_uses_fallback() {
+    let (pool, _workers) = ScoringPool::spawn(SlowScorer::new(Duration::from_millis(300)), 1, 8);
+    let config = FsmConfig {
+        score_timeout: Duration::from_millis(20),
+        fallback: FallbackPolicy::Flag,
+    };
+
+    let decision = run_transaction(tx("tx-1", 10.0), &pool, &config).await;
+    assert_eq!(
+        decision,
+        Decision::Fallback {
+            reason: FallbackReason::Timeout,
+            is_fraud: true
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_worker_unavailable_uses_rules() {
+    let (pool, workers) = ScoringPool::spawn(SlowScorer::new(Duration::ZERO), 1, 8);
+    for worker in workers {
+        worker.abort();
+        let _ = worker.await;
+    }
+
+    let decision = run_transaction(tx("tx-1", 5000.0), &pool, &FsmConfig::default()).await;
+    assert_eq!(
+        decision,
+        Decision::Fallback {
+            reason: FallbackReason::WorkerUnavailable,
+            is_fraud: true
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_dropped_request_is_not_scored() {
+    let scorer = SlowScorer::new(Duration::from_millis(100));
+    let (pool, _workers) = ScoringPool::spawn(scorer.clone(), 1, 8);
+    let config = FsmConfig {
+        score_timeout: Duration::from_secs(5),
+        fallback: FallbackPolicy::Approve,
+    };
+
+    // The single worker is busy with the first transaction while the second one waits in the queue
+    let first = tokio::spawn({
+        let (pool, config) = (pool.clone(), config.clone());
+        async move { run_transaction(tx("tx-1", 10.0), &pool, &config).await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let second = tokio::spawn({
+        let (pool, config) = (pool.clone(), config.clone());
+        async move { run_transaction(tx("tx-2", 10.0), &pool, &config).await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    second.abort();
+
+    assert!(matches!(first.await.unwrap(), Decision::Clean(_)));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(scorer.calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,122 @@
+// tests/batching.rs
+
+use fraud_detection_3::domain::fraud_scorer::{FraudScorer, RuleBasedScorer};
+use fraud_detection_3::domain::repository::{RepoError, ScoreRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::in_memory::InMemoryTransactionRepo;
+use fraud_detection_3::persistence::sqlite::{SQLiteScoreRepo, SQLiteTransRepo};
+use fraud_detection_3::workers::batcher::{BatchConfig, process_batch, start_batch_worker};
+use fraud_detection_3::workers::dispatcher::WorkerMessage;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+fn tx(id: &str, amount: f64) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+// Records the size of every batch it is asked to score
+#[derive(Default)]
+struct RecordingScorer {
+    batch_sizes: Mutex<Vec<usize>>,
+}
+
+impl FraudScorer for RecordingScorer {
+    fn is_fraud(&self, tx: &Transaction) -> bool {
+        RuleBasedScorer.is_fraud(tx)
+    }
+
+    fn score_batch(&self, txs: &[Transaction]) -> Vec<Score> {
+        self.batch_sizes.lock().unwrap().push(txs.len());
+        txs.iter()
+            .map(|tx| Score {
+                id: tx.id.clone(),
+                score: 0.5,
+                is_fraud: self.is_fraud(tx),
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_process_batch_keeps_order_and_dedups() {
+    let tx_repo = SQLiteTransRepo::new(":memory:");
+    let score_repo = SQLiteScoreRepo::new(":memory:");
+    let scorer = RecordingScorer::default();
+
+    let first = process_batch(vec![tx("tx-1", 10.0), tx("tx-2", 5000.0)], &tx_repo, &score_repo, &scorer);
+    assert!(!first[0].as_ref().unwrap().is_fraud);
+    assert!(first[1].as_ref().unwrap().is_fraud);
+
+    // tx-1 is a retry, tx-2 a conflicting payload, tx-3 is new: only tx-3 reaches the scorer
+    let second = process_batch(vec![tx("tx-1", 10.0), tx("tx-2", 1.0), tx("tx-3", 1.0)], &tx_repo, &score_repo, &scorer);
+    assert_eq!(second[0].as_ref().unwrap().id, "tx-1");
+    assert_eq!(second[1], Err(RepoError::Conflict { id: "tx-2".to_string() }));
+    assert_eq!(second[2].as_ref().unwrap().id, "tx-3");
+
+    assert_eq!(*scorer.batch_sizes.lock().unwrap(), vec![2, 1]);
+    assert!(score_repo.get("tx-3").is_some());
+    assert!(tx_repo.get("tx-3").is_some());
+}
+
+#[tokio::test]
+async fn test_batch_worker_flushes_on_size_and_shutdown() {
+    let tx_repo = Arc::new(InMemoryTransactionRepo::new());
+    let score_repo = Arc::new(SQLiteScoreRepo::new(":memory:"));
+    let scorer = Arc::new(RecordingScorer::default());
+
+    let (sender, rx) = mpsc::channel(16);
+    for i in 0..5 {
+        sender.send(WorkerMessage::Transaction(tx(&format!("tx-{i}"), 10.0))).await.unwrap();
+    }
+    sender.send(WorkerMessage::Shutdown).await.unwrap();
+
+    let config = BatchConfig {
+        max_size: 2,
+        max_wait: Duration::from_secs(60),
+    };
+    start_batch_worker(rx, tx_repo.clone(), score_repo.clone(), scorer.clone(), config).await;
+
+    assert_eq!(*scorer.batch_sizes.lock().unwrap(), vec![2, 2, 1]);
+    assert!(score_repo.get("tx-4").is_some());
+}
+
+#[tokio::test]
+async fn test_batch_worker_flushes_on_timeout() {
+    let tx_repo = Arc::new(InMemoryTransactionRepo::new());
+    let score_repo = Arc::new(SQLiteScoreRepo::new(":memory:"));
+    let scorer = Arc::new(RecordingScorer::default());
+
+    let (sender, rx) = mpsc::channel(16);
+    let config = BatchConfig {
+        max_size: 100,
+        max_wait: Duration::from_millis(20),
+    };
+    let worker = tokio::spawn(start_batch_worker(rx, tx_repo, score_repo.clone(), scorer.clone(), config));
+
+    sender.send(WorkerMessage::Transaction(tx("tx-a", 10.0))).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(score_repo.get("tx-a").is_some());
+
+    drop(sender);
+    worker.await.unwrap();
+    assert_eq!(*scorer.batch_sizes.lock().unwrap(), vec![1]);
+}
+
+#[test]
+fn test_copies_within_a_batch_scored_once() {
+    let tx_repo = InMemoryTransactionRepo::new();
+    let score_repo = SQLiteScoreRepo::new(":memory:");
+    let scorer = RecordingScorer::default();
+
+    let results = process_batch(vec![tx("tx-1", 10.0), tx("tx-2", 10.0), tx("tx-1", 10.0)], &tx_repo, &score_repo, &scorer);
+    assert_eq!(*scorer.batch_sizes.lock().unwrap(), vec![2]);
+    assert_eq!(results[2], results[0]);
+    assert_eq!(results[2].as_ref().unwrap().id, "tx-1");
+}
@@ -10,6 +10,7 @@ fn test_process_transaction_command() {
         id: "tx-001".to_string(),
         amount: 123.45,
         currency: "USD".to_string(),
+        ..Default::default()
     };
 
     let cmd = ProcessTransaction { transaction: tx };
@@ -0,0 +1,35 @@
+// tests/conformance.rs
+
+use fraud_detection_3::persistence::conformance::{score_repository_suite, trans_repository_suite};
+use fraud_detection_3::persistence::in_memory::{InMemoryScoreRepo, InMemoryTransactionRepo};
+use fraud_detection_3::persistence::kv::RedbStore;
+use fraud_detection_3::persistence::sqlite::{SQLiteScoreRepo, SQLiteTransRepo, SqliteStore, SqliteViews};
+
+#[test]
+fn test_in_memory_trans_repo_conforms() {
+    trans_repository_suite(InMemoryTransactionRepo::new);
+}
+
+#[test]
+fn test_sqlite_trans_repo_conforms() {
+    trans_repository_suite(|| SQLiteTransRepo::new(":memory:"));
+    trans_repository_suite(|| SqliteStore::open(":memory:").transactions());
+}
+
+#[test]
+fn test_in_memory_score_repo_conforms() {
+    score_repository_suite(InMemoryScoreRepo::new);
+}
+
+#[test]
+fn test_sqlite_score_repo_conforms() {
+    score_repository_suite(|| SQLiteScoreRepo::new(":memory:"));
+    score_repository_suite(|| SQLiteScoreRepo::with_outbox(":memory:"));
+    score_repository_suite(|| SqliteStore::open(":memory:").scores());
+}
+
+#[test]
+fn test_redb_repos_conform() {
+    trans_repository_suite(|| RedbStore::in_memory().transactions());
+    score_repository_suite(|| RedbStore::in_memory().scores());
+}
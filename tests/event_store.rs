@@ -0,0 +1,140 @@
+// tests/event_store.rs
+
+use fraud_detection_3::domain::label::LabelOutcome;
+use fraud_detection_3::domain::repository::{AppendError, EventStore, ScoreRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::domain::tx_event::{TxEvent, TxView, load_view, rebuild_read_models};
+use fraud_detection_3::persistence::in_memory::InMemoryTransactionRepo;
+use fraud_detection_3::persistence::sqlite::{SQLiteEventStore, SQLiteScoreRepo};
+use fraud_detection_3::persistence::tokenizing::TokenizingEventStore;
+use fraud_detection_3::security::{ColumnCipher, ColumnEncryption, EncryptedColumn, Key, Tokenizer};
+
+fn received(id: &str, amount: f64) -> TxEvent {
+    TxEvent::TransactionReceived {
+        transaction: Transaction {
+            id: id.to_string(),
+            amount,
+            currency: "USD".to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+fn scored(id: &str, is_fraud: bool) -> TxEvent {
+    TxEvent::Scored {
+        score: Score {
+            id: id.to_string(),
+            score: if is_fraud { 0.9 } else { 0.1 },
+            is_fraud,
+        },
+        model: "rules-v1".to_string(),
+    }
+}
+
+#[test]
+fn test_streams_keep_every_event() {
+    let store = SQLiteEventStore::new(":memory:");
+    assert_eq!(store.append("tx-1", 0, vec![received("tx-1", 10.0), TxEvent::Validated]), Ok(2));
+    assert_eq!(store.append("tx-1", 2, vec![scored("tx-1", false)]), Ok(3));
+    assert_eq!(store.append("tx-1", 3, vec![TxEvent::ChargebackReceived { notes: None }]), Ok(4));
+    store.append("tx-2", 0, vec![received("tx-2", 20.0)]).unwrap();
+
+    let events = store.read_stream("tx-1", 0);
+    assert_eq!(events.iter().map(|e| e.version).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    assert_eq!(events[2].event, scored("tx-1", false));
+    assert_eq!(store.read_stream("tx-1", 3).len(), 1);
+
+    let view = load_view(&store, "tx-1", 100).unwrap();
+    assert_eq!(view.version, 4);
+    assert!(view.validated);
+    assert_eq!(view.outcome, Some(LabelOutcome::Fraud));
+    assert!(load_view(&store, "tx-3", 100).is_none());
+}
+
+#[test]
+fn test_stale_writer_is_rejected() {
+    let store = SQLiteEventStore::new(":memory:");
+    store.append("tx-1", 0, vec![received("tx-1", 10.0)]).unwrap();
+
+    // Two writers read version 1, the second one loses
+    store.append("tx-1", 1, vec![TxEvent::Validated]).unwrap();
+    let conflict = store.append("tx-1", 1, vec![TxEvent::Rejected { reason: "late".to_string() }]);
+    assert_eq!(
+        conflict,
+        Err(AppendError::VersionConflict {
+            stream_id: "tx-1".to_string(),
+            expected: 1,
+            actual: 2,
+        })
+    );
+    assert_eq!(store.read_stream("tx-1", 0).len(), 2);
+    assert!(store.append("tx-2", 1, vec![TxEvent::Validated]).is_err());
+}
+
+#[test]
+fn test_snapshot_is_taken_and_used() {
+    let store = SQLiteEventStore::new(":memory:");
+    store.append("tx-1", 0, vec![received("tx-1", 10.0), TxEvent::Validated, scored("tx-1", true)]).unwrap();
+
+    let view = load_view(&store, "tx-1", 2).unwrap();
+    let snapshot = store.load_snapshot("tx-1").unwrap();
+    assert_eq!(snapshot.view, view);
+
+    store.append("tx-1", 3, vec![TxEvent::Flagged { reason: "amount".to_string() }]).unwrap();
+    let view = load_view(&store, "tx-1", 2).unwrap();
+    assert_eq!(view, TxView::replay(store.read_stream("tx-1", 0).iter().map(|e| &e.event)));
+    assert_eq!(store.load_snapshot("tx-1").unwrap().view.version, 3);
+}
+
+#[test]
+fn test_read_models_are_rebuilt_from_events() {
+    let store = SQLiteEventStore::new(":memory:");
+    for (id, is_fraud) in [("tx-1", false), ("tx-2", true)] {
+        store.append(id, 0, vec![received(id, 10.0), TxEvent::Validated, scored(id, is_fraud)]).unwrap();
+    }
+
+    let tx_repo = InMemoryTransactionRepo::new();
+    let score_repo = SQLiteScoreRepo::new(":memory:");
+    assert_eq!(rebuild_read_models(&store, &tx_repo, &score_repo), 6);
+    assert_eq!(tx_repo.get("tx-2").unwrap().amount, 10.0);
+    assert!(score_repo.get("tx-2").unwrap().is_fraud);
+    assert!(!score_repo.get("tx-1").unwrap().is_fraud);
+}
+
+#[test]
+fn test_embedded_identifiers_are_protected_at_rest() {
+    let path = std::env::temp_dir().join(format!("fd3_events_sealed_{}.db", std::process::id()));
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+
+    // As `Backend` opens it with a token key: account tokenized, then both columns encrypted
+    let encryption = ColumnEncryption::new(ColumnCipher::new(vec![Key::generate("k1")]), vec![EncryptedColumn::MerchantId, EncryptedColumn::AccountId]);
+    let token_key = Key::generate("t1");
+    let store = TokenizingEventStore::new(SQLiteEventStore::with_encryption(path, encryption), Tokenizer::new(&token_key));
+    let transaction = Transaction {
+        id: "tx-1".to_string(),
+        amount: 10.0,
+        currency: "USD".to_string(),
+        merchant_id: "merchant-42".to_string(),
+        account_id: "acc-secret".to_string(),
+    };
+    store.append("tx-1", 0, vec![TxEvent::TransactionReceived { transaction: transaction.clone() }]).unwrap();
+    load_view(&store, "tx-1", 0).unwrap();
+
+    let conn = rusqlite::Connection::open(path).unwrap();
+    let payload: String = conn.query_row("SELECT payload FROM events", [], |row| row.get(0)).unwrap();
+    let snapshot: String = conn.query_row("SELECT view FROM snapshots", [], |row| row.get(0)).unwrap();
+    for stored in [payload, snapshot] {
+        assert!(stored.contains("enc:k1:"));
+        assert!(!stored.contains("merchant-42") && !stored.contains("acc-secret"));
+    }
+
+    let read = store.read_stream("tx-1", 0).remove(0).event;
+    let TxEvent::TransactionReceived { transaction: read } = read else { panic!("unexpected event") };
+    assert_eq!(read.merchant_id, "merchant-42");
+    assert_eq!(read.account_id, Tokenizer::new(&token_key).tokenize("acc-secret"));
+    assert_eq!(store.load_snapshot("tx-1").unwrap().view.transaction, Some(read));
+
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,89 @@
+// tests/export.rs
+
+use fraud_detection_3::domain::query::TransactionQuery;
+use fraud_detection_3::domain::repository::{ScoreRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::export::{self, COLUMNS, ExportFormat, ExportRow};
+use fraud_detection_3::persistence::sqlite::{SqliteStore, SqliteViews};
+
+fn seeded_store() -> SqliteStore {
+    let store = SqliteStore::open(":memory:");
+    for (id, merchant) in [("tx-1", "m-1"), ("tx-2", "Shop, \"Inc\""), ("tx-3", "m-3")] {
+        store.transactions().save(Transaction {
+            id: id.to_string(),
+            amount: 12.5,
+            currency: "USD".to_string(),
+            merchant_id: merchant.to_string(),
+            account_id: "acc-1".to_string(),
+        });
+        // Make received_at distinct
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+    store.scores().save(Score {
+        id: "tx-1".to_string(),
+        score: 0.9,
+        is_fraud: true,
+    });
+    store
+}
+
+#[test]
+fn test_csv_header_quoting_and_unscored_rows() {
+    let store = seeded_store();
+    let mut out = Vec::new();
+    let rows = export::export(&store.export(), &TransactionQuery::new(), ExportFormat::Csv, &mut out).unwrap();
+    assert_eq!(rows, 3);
+
+    let csv = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], COLUMNS.join(","));
+    assert!(lines[1].starts_with("tx-1,") && lines[1].contains(",0.9,true,"));
+    assert!(lines[2].contains(",\"Shop, \"\"Inc\"\"\",acc-1,,,"), "quoted field, empty score: {}", lines[2]);
+}
+
+#[test]
+fn test_jsonl_pages_through_the_range() {
+    let store = seeded_store();
+    let all: Vec<ExportRow> = {
+        let mut out = Vec::new();
+        // Pages of one row: the cursor must carry over between pages
+        export::export(&store.export(), &TransactionQuery::new().limit(1), ExportFormat::Jsonl, &mut out).unwrap();
+        String::from_utf8(out).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect()
+    };
+    assert_eq!(all.iter().map(|r| r.tx_id.as_str()).collect::<Vec<_>>(), ["tx-1", "tx-2", "tx-3"]);
+    assert_eq!(all[0].is_fraud, Some(true));
+    assert_eq!((all[1].score, all[1].is_fraud, all[1].scored_at), (None, None, None));
+
+    // [from, to) on reception time
+    let mut out = Vec::new();
+    let query = TransactionQuery::new().received_between(all[1].received_at, all[2].received_at);
+    assert_eq!(export::export(&store.export(), &query, ExportFormat::Jsonl, &mut out).unwrap(), 1);
+    assert!(String::from_utf8(out).unwrap().contains("\"tx_id\":\"tx-2\""));
+}
+
+#[cfg(not(feature = "parquet"))]
+#[test]
+fn test_parquet_needs_feature() {
+    let store = seeded_store();
+    let result = export::export(&store.export(), &TransactionQuery::new(), ExportFormat::Parquet, Vec::new());
+    assert!(matches!(result, Err(export::ExportError::Unsupported(ExportFormat::Parquet))));
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_parquet_round_trip() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let path = std::env::temp_dir().join(format!("fd3_export_{}.parquet", std::process::id()));
+    let store = seeded_store();
+    let file = std::fs::File::create(&path).unwrap();
+    export::export(&store.export(), &TransactionQuery::new(), ExportFormat::Parquet, file).unwrap();
+
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    let metadata = reader.metadata();
+    assert_eq!(metadata.file_metadata().num_rows(), 3);
+    let columns: Vec<&str> = metadata.file_metadata().schema_descr().columns().iter().map(|c| c.name()).collect();
+    assert_eq!(columns, COLUMNS);
+    let _ = std::fs::remove_file(&path);
+}
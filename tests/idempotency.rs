@@ -0,0 +1,81 @@
+// tests/idempotency.rs
+
+use fraud_detection_3::domain::repository::{RepoError, SaveOutcome, ScoreRepository, TransRepository};
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::in_memory::InMemoryTransactionRepo;
+use fraud_detection_3::persistence::sqlite::{SQLiteScoreRepo, SQLiteTransRepo};
+use fraud_detection_3::workers::dispatcher::process_transaction;
+use std::time::Duration;
+
+fn tx(id: &str, amount: f64) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+fn check_dedup(repo: &dyn TransRepository) {
+    assert_eq!(repo.save_idempotent(tx("tx-001", 10.0)), Ok(SaveOutcome::Inserted));
+    assert_eq!(repo.save_idempotent(tx("tx-001", 10.0)), Ok(SaveOutcome::Duplicate));
+    assert_eq!(repo.save_idempotent(tx("tx-001", 99.0)), Err(RepoError::Conflict { id: "tx-001".to_string() }));
+    // The original is never overwritten
+    assert_eq!(repo.get("tx-001"), Some(tx("tx-001", 10.0)));
+}
+
+#[test]
+fn test_in_memory_dedup() {
+    check_dedup(&InMemoryTransactionRepo::new());
+}
+
+#[test]
+fn test_sqlite_dedup() {
+    check_dedup(&SQLiteTransRepo::new(":memory:"));
+}
+
+#[test]
+fn test_dedup_window_expired() {
+    let mem = InMemoryTransactionRepo::with_dedup_window(Duration::ZERO);
+    let sqlite = SQLiteTransRepo::with_dedup_window(":memory:", Duration::ZERO);
+    for repo in [&mem as &dyn TransRepository, &sqlite] {
+        assert_eq!(repo.save_idempotent(tx("tx-002", 10.0)), Ok(SaveOutcome::Inserted));
+        assert_eq!(repo.save_idempotent(tx("tx-002", 99.0)), Ok(SaveOutcome::Inserted));
+        assert_eq!(repo.get("tx-002"), Some(tx("tx-002", 99.0)));
+    }
+}
+
+#[test]
+fn test_retry_returns_original_score() {
+    let tx_repo = SQLiteTransRepo::new(":memory:");
+    let score_repo = SQLiteScoreRepo::new(":memory:");
+
+    let first = process_transaction(tx("tx-003", 10.0), &tx_repo, &score_repo).unwrap();
+    let retry = process_transaction(tx("tx-003", 10.0), &tx_repo, &score_repo).unwrap();
+    assert_eq!(retry.score, first.score);
+    assert_eq!(score_repo.get("tx-003").unwrap().score, first.score);
+
+    let conflict = process_transaction(tx("tx-003", 20.0), &tx_repo, &score_repo);
+    assert!(matches!(conflict, Err(RepoError::Conflict { .. })));
+}
+
+#[test]
+fn test_received_at_added_to_old_databases() {
+    let path = std::env::temp_dir().join(format!("fd3_no_received_at_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE transactions (id TEXT PRIMARY KEY, amount REAL NOT NULL, currency TEXT NOT NULL,
+                merchant_id TEXT NOT NULL DEFAULT '', account_id TEXT NOT NULL DEFAULT '');
+            INSERT INTO transactions (id, amount, currency) VALUES ('old-1', 10.0, 'USD');",
+        )
+        .unwrap();
+    }
+
+    let repo = SQLiteTransRepo::new(path.to_str().unwrap());
+    // Migrated rows are inside the dedup window
+    assert_eq!(repo.save_idempotent(tx("old-1", 10.0)), Ok(SaveOutcome::Duplicate));
+    assert_eq!(repo.save_idempotent(tx("new-1", 20.0)), Ok(SaveOutcome::Inserted));
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,61 @@
+// tests/kv_backend.rs
+
+use fraud_detection_3::domain::query::{ScoreQuery, TransactionQuery};
+use fraud_detection_3::domain::repository::{SaveOutcome, ScoreRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::backend::Backend;
+use fraud_detection_3::persistence::kv::RedbStore;
+
+fn tx(id: &str) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount: 10.0,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_redb_survives_restart() {
+    let path = std::env::temp_dir().join(format!("fd3_kv_{}.redb", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let store = RedbStore::open(path.to_str().unwrap());
+        assert_eq!(store.transactions().save_idempotent(tx("tx-1")), Ok(SaveOutcome::Inserted));
+        store.scores().save_batch(vec![
+            Score {
+                id: "tx-1".to_string(),
+                score: 0.9,
+                is_fraud: true,
+            },
+            Score {
+                id: "tx-2".to_string(),
+                score: -0.5,
+                is_fraud: false,
+            },
+        ]);
+    }
+
+    let store = RedbStore::open(path.to_str().unwrap());
+    assert_eq!(store.transactions().save_idempotent(tx("tx-1")), Ok(SaveOutcome::Duplicate), "dedup window survives too");
+    assert_eq!(store.transactions().count(&TransactionQuery::new()), 1);
+    let ids: Vec<String> = store.scores().query(&ScoreQuery::new()).items.into_iter().map(|s| s.id).collect();
+    assert_eq!(ids, ["tx-1", "tx-2"], "negative scores sort last");
+    drop(store);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_backend_from_configuration() {
+    assert_eq!(Backend::parse("memory"), Backend::InMemory);
+    assert_eq!(Backend::parse("data.db"), Backend::Sqlite("data.db".to_string()));
+    assert_eq!(Backend::parse("sqlite::memory:"), Backend::Sqlite(":memory:".to_string()));
+    assert_eq!(Backend::parse("redb:data.redb"), Backend::Redb("data.redb".to_string()));
+
+    for backend in [Backend::InMemory, Backend::parse("sqlite::memory:")] {
+        let repos = backend.open();
+        repos.transactions.save(tx("tx-1"));
+        assert_eq!(repos.transactions.get("tx-1"), Some(tx("tx-1")), "{} backend", backend.name());
+    }
+}
@@ -0,0 +1,155 @@
+// tests/metrics.rs
+
+use fraud_detection_3::domain::fraud_scorer::RuleBasedScorer;
+use fraud_detection_3::domain::query::{Page, TransactionQuery};
+use fraud_detection_3::domain::repository::{RepoError, SaveOutcome, TransRepository};
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::metrics::{self, Counter, Family, Histogram, MeteredScoreRepo, MeteredScorer, MeteredTransRepo, Metrics};
+use fraud_detection_3::persistence::in_memory::{InMemoryScoreRepo, InMemoryTransactionRepo};
+use fraud_detection_3::pipeline::Pipeline;
+use fraud_detection_3::pipeline::stages::{PersistenceStage, ScorePersistenceStage, ScoringStage, ValidationStage};
+#[cfg(not(feature = "bench"))]
+use fraud_detection_3::workers::dispatcher::{self, WorkerMessage};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn tx(id: &str, amount: f64) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Fails like a SQLite repository on a broken database
+struct BrokenRepo;
+
+impl TransRepository for BrokenRepo {
+    fn save(&self, _: Transaction) {
+        panic!("Failed to insert transaction: disk I/O error");
+    }
+    fn get(&self, _: &str) -> Option<Transaction> {
+        None
+    }
+    fn save_idempotent(&self, _: Transaction) -> Result<SaveOutcome, RepoError> {
+        panic!("Failed to read transaction: disk I/O error");
+    }
+    fn query(&self, _: &TransactionQuery) -> Page<Transaction> {
+        Page::default()
+    }
+    fn count(&self, _: &TransactionQuery) -> usize {
+        0
+    }
+}
+
+#[test]
+fn test_text_format() {
+    let counter: Family<Counter> = Family::new("requests_total", "Requests", &["path"]);
+    counter.with(&["/a\"b"]).inc_by(3);
+    let histogram: Family<Histogram> = Family::with_buckets("latency_seconds", "Latency", &[], &[0.1, 1.0]);
+    let h = histogram.with(&[]);
+    h.observe(0.05);
+    h.observe(0.5);
+    h.observe(5.0);
+
+    let mut out = String::new();
+    counter.encode(&mut out);
+    histogram.encode(&mut out);
+    assert_eq!(
+        out,
+        "# HELP requests_total Requests\n\
+         # TYPE requests_total counter\n\
+         requests_total{path=\"/a\\\"b\"} 3\n\
+         # HELP latency_seconds Latency\n\
+         # TYPE latency_seconds histogram\n\
+         latency_seconds_bucket{le=\"0.1\"} 1\n\
+         latency_seconds_bucket{le=\"1\"} 2\n\
+         latency_seconds_bucket{le=\"+Inf\"} 3\n\
+         latency_seconds_sum 5.55\n\
+         latency_seconds_count 3\n"
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_repos_and_scorer_report() {
+    let metrics = Arc::new(Metrics::new());
+    let tx_repo = Arc::new(MeteredTransRepo::new(Arc::new(InMemoryTransactionRepo::new()), metrics.clone()));
+    let score_repo = Arc::new(MeteredScoreRepo::new(Arc::new(InMemoryScoreRepo::new()), metrics.clone()));
+    let scorer = Arc::new(MeteredScorer::new("rules", RuleBasedScorer, metrics.clone()));
+
+    let (pipeline, output) = Pipeline::builder(8)
+        .metrics(metrics.clone())
+        .stage(ValidationStage, 1)
+        .stage(PersistenceStage::new(tx_repo, score_repo.clone()), 1)
+        .stage(ScoringStage::new(scorer), 2)
+        .stage(ScorePersistenceStage::new(score_repo), 1)
+        .build();
+    drop(output);
+    // tx-0 is invalid, tx-3 and tx-4 are over the rule's threshold
+    for (i, amount) in [0.0, 10.0, 20.0, 5000.0, 2000.0].into_iter().enumerate() {
+        pipeline.submit(tx(&format!("tx-{i}"), amount)).await.unwrap();
+    }
+    pipeline.shutdown().await;
+
+    assert_eq!(metrics.stage_items.with(&["validation", "dropped"]).get(), 1);
+    assert_eq!(metrics.stage_items.with(&["validation", "forwarded"]).get(), 4);
+    assert_eq!(metrics.stage_latency.with(&["scoring"]).count(), 4);
+    assert_eq!(metrics.scores.with(&["rules", "true"]).get(), 2);
+    assert_eq!(metrics.scores.with(&["rules", "false"]).get(), 2);
+    assert_eq!(metrics.repo_operations.with(&["transactions", "save_idempotent"]).get(), 4);
+    assert_eq!(metrics.repo_operations.with(&["scores", "save"]).get(), 4);
+
+    let text = metrics.encode();
+    assert!(text.contains("fraud_detection_scores_total{scorer=\"rules\",is_fraud=\"true\"} 2"), "{text}");
+    assert!(text.contains("fraud_detection_queue_depth{stage=\"scoring\"}"), "{text}");
+}
+
+// The bench build swaps the dispatcher worker for an unmetered one
+#[cfg(not(feature = "bench"))]
+#[tokio::test]
+async fn test_dispatcher_outcomes() {
+    let metrics = Arc::new(Metrics::new());
+    let (sender, rx) = tokio::sync::mpsc::channel(8);
+    let tx_repo = Arc::new(MeteredTransRepo::new(Arc::new(InMemoryTransactionRepo::new()), metrics.clone()));
+    let worker = tokio::spawn(dispatcher::start_metered_worker(rx, tx_repo, Arc::new(InMemoryScoreRepo::new()), metrics.clone()));
+    sender.send(WorkerMessage::Transaction(tx("tx-1", 10.0))).await.unwrap();
+    sender.send(WorkerMessage::Transaction(tx("tx-1", 99.0))).await.unwrap(); // conflict
+    sender.send(WorkerMessage::Shutdown).await.unwrap();
+    worker.await.unwrap();
+
+    assert_eq!(metrics.transactions.with(&["scored"]).get(), 1);
+    assert_eq!(metrics.transactions.with(&["rejected"]).get(), 1);
+    assert_eq!(metrics.stage_latency.with(&["dispatcher"]).count(), 2);
+}
+
+#[test]
+fn test_db_errors() {
+    let metrics = Arc::new(Metrics::new());
+    let broken = MeteredTransRepo::new(Arc::new(BrokenRepo), metrics.clone());
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| broken.save_idempotent(tx("tx-2", 10.0))));
+    assert!(result.is_err(), "the failure still reaches the caller");
+    assert_eq!(metrics.db_errors.with(&["transactions", "save_idempotent"]).get(), 1);
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    let metrics = Arc::new(Metrics::new());
+    metrics.transactions.with(&["scored"]).inc_by(7);
+    let (addr, server) = metrics::server::spawn("127.0.0.1:0", metrics).await.unwrap();
+
+    let get = |path: &'static str| async move {
+        let mut conn = tokio::net::TcpStream::connect(addr).await.unwrap();
+        conn.write_all(format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes()).await.unwrap();
+        let mut response = String::new();
+        conn.read_to_string(&mut response).await.unwrap();
+        response
+    };
+
+    let response = get("/metrics").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+    assert!(response.contains("fraud_detection_transactions_total{outcome=\"scored\"} 7"));
+    assert!(get("/").await.starts_with("HTTP/1.1 404"));
+    server.abort();
+}
@@ -0,0 +1,57 @@
+// tests/observers.rs
+
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::observer::{RecordingObserver, TransitionObserver};
+use fraud_detection_3::state_machine::transitions::{StateId, StateMachine, TransitionContext, standard_table};
+use std::sync::{Arc, Mutex};
+
+/// Logs hook calls in order
+#[derive(Default)]
+struct HookLog(Mutex<Vec<String>>);
+
+impl TransitionObserver for HookLog {
+    fn on_exit(&self, tx_id: &str, state: StateId) {
+        self.0.lock().unwrap().push(format!("{tx_id} exit {state}"));
+    }
+
+    fn on_enter(&self, tx_id: &str, state: StateId) {
+        self.0.lock().unwrap().push(format!("{tx_id} enter {state}"));
+    }
+}
+
+#[test]
+fn test_hooks_run_in_order() {
+    let log = Arc::new(HookLog::default());
+    let mut fsm = StateMachine::new(Arc::new(standard_table().observe(log.clone())));
+
+    fsm.fire(Event::Process, &TransitionContext::new("tx-1")).unwrap();
+    fsm.fire(Event::Scored, &TransitionContext::new("tx-1")).unwrap();
+
+    assert_eq!(
+        *log.0.lock().unwrap(),
+        vec!["tx-1 exit Validated", "tx-1 enter Enriched", "tx-1 exit Enriched", "tx-1 enter Scored"]
+    );
+}
+
+#[test]
+fn test_observers_see_applied_transitions_only() {
+    let audit = Arc::new(RecordingObserver::new());
+    let metrics = Arc::new(RecordingObserver::new());
+    let table = Arc::new(standard_table().observe(audit.clone()).observe(metrics.clone()));
+    let ctx = TransitionContext::new("tx-1").with_score(Score {
+        id: "tx-1".to_string(),
+        score: 0.1,
+        is_fraud: false,
+    });
+
+    let mut fsm = StateMachine::new(table);
+    assert!(fsm.fire(Event::Persist, &ctx).is_err());
+    for event in [Event::Process, Event::Scored, Event::Persist] {
+        fsm.fire(event, &ctx).unwrap();
+    }
+
+    assert_eq!(audit.records(), fsm.history());
+    assert_eq!(metrics.records().len(), 3);
+    assert_eq!(audit.records().last().unwrap().to, StateId::Persisted);
+}
@@ -0,0 +1,162 @@
+// tests/outbox.rs
+
+use fraud_detection_3::domain::repository::{Outbox, ScoreRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::outbox::sinks::{AlertSink, ChannelSink, FileSink, SinkError, WebhookSink};
+use fraud_detection_3::outbox::{FRAUD_ALERT_TOPIC, FraudAlert, OutboxMessage, OutboxRelay, RelayConfig};
+use fraud_detection_3::persistence::sqlite::SQLiteScoreRepo;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+fn score(id: &str, is_fraud: bool) -> Score {
+    Score {
+        id: id.to_string(),
+        score: if is_fraud { 0.95 } else { 0.05 },
+        is_fraud,
+    }
+}
+
+/// Fails the first `failures` publishes
+struct FlakySink {
+    failures: AtomicUsize,
+    published: mpsc::UnboundedSender<i64>,
+}
+
+impl AlertSink for FlakySink {
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+            return Err(SinkError("broker down".to_string()));
+        }
+        self.published.send(message.id).unwrap();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_only_fraud_scores_enqueue_alerts() {
+    let repo = SQLiteScoreRepo::with_outbox(":memory:");
+    repo.save(score("tx-1", true));
+    repo.save(score("tx-2", false));
+    repo.save_batch(vec![score("tx-3", false), score("tx-4", true)]);
+
+    let pending = repo.outbox().pending(10);
+    assert_eq!(pending.iter().map(|m| m.key.as_str()).collect::<Vec<_>>(), vec!["tx-1", "tx-4"]);
+    assert_eq!(pending[0].topic, FRAUD_ALERT_TOPIC);
+    let alert: FraudAlert = serde_json::from_str(&pending[0].payload).unwrap();
+    assert_eq!(alert.score, 0.95);
+
+    // Plain repositories have no outbox
+    let plain = SQLiteScoreRepo::new(":memory:");
+    plain.save(score("tx-1", true));
+    assert!(plain.get("tx-1").is_some());
+}
+
+#[test]
+fn test_rescore_alerts_only_when_it_turns_to_fraud() {
+    let path = std::env::temp_dir().join(format!("fd3_outbox_rescore_{}.db", std::process::id()));
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+    let repo = SQLiteScoreRepo::with_outbox(path);
+    let conn = rusqlite::Connection::open(path).unwrap();
+    let scored_at = || -> i64 { conn.query_row("SELECT scored_at FROM scoring_results WHERE tx_id = 'tx-1'", [], |row| row.get(0)).unwrap() };
+
+    repo.save(score("tx-1", true));
+    let first = scored_at();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    // Retry, then a new fraud score: still the one alert, still the first scored_at
+    repo.save(score("tx-1", true));
+    repo.save(Score { score: 0.99, ..score("tx-1", true) });
+    assert_eq!(repo.get("tx-1").unwrap().score, 0.99);
+    assert_eq!(scored_at(), first);
+    assert_eq!(repo.outbox().pending(10).len(), 1);
+
+    // Cleared then flagged again alerts again
+    repo.save(score("tx-1", false));
+    assert_eq!(repo.outbox().pending(10).len(), 1);
+    repo.save(score("tx-1", true));
+    assert_eq!(repo.outbox().pending(10).len(), 2);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_relay_retries_in_order_until_delivered() {
+    let repo = SQLiteScoreRepo::with_outbox(":memory:");
+    for id in ["tx-1", "tx-2", "tx-3"] {
+        repo.save(score(id, true));
+    }
+    let outbox = Arc::new(repo.outbox());
+    let (published, mut rx) = mpsc::unbounded_channel();
+    let sink = FlakySink {
+        failures: AtomicUsize::new(1),
+        published,
+    };
+    let relay = OutboxRelay::new(outbox.clone(), sink, RelayConfig::default());
+
+    assert_eq!(relay.relay_once().await, 0);
+    let first = outbox.pending(10);
+    assert_eq!(first.len(), 3);
+    assert_eq!(first[0].attempts, 1);
+
+    assert_eq!(relay.relay_once().await, 3);
+    assert!(outbox.pending(10).is_empty());
+    assert_eq!(relay.relay_once().await, 0);
+
+    let mut ids = Vec::new();
+    while let Ok(id) = rx.try_recv() {
+        ids.push(id);
+    }
+    assert_eq!(ids, first.iter().map(|m| m.id).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_file_and_channel_sinks() {
+    let message = FraudAlert::from_score(&score("tx-1", true)).into_message();
+
+    let path = std::env::temp_dir().join(format!("fd3_alerts_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let file = FileSink::new(&path);
+    file.publish(&message).await.unwrap();
+    file.publish(&message).await.unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written.lines().count(), 2);
+    assert_eq!(serde_json::from_str::<OutboxMessage>(written.lines().next().unwrap()).unwrap(), message);
+
+    let (tx, mut rx) = mpsc::channel(1);
+    let channel = ChannelSink::new(tx);
+    channel.publish(&message).await.unwrap();
+    assert_eq!(rx.recv().await, Some(message.clone()));
+    drop(rx);
+    assert!(channel.publish(&message).await.is_err());
+}
+
+#[tokio::test]
+async fn test_webhook_sink_posts_json() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        for status in ["500 Internal Server Error", "204 No Content"] {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            requests.push(String::from_utf8_lossy(&buf[..n]).to_string());
+            socket.write_all(format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\n\r\n").as_bytes()).await.unwrap();
+        }
+        requests
+    });
+
+    let sink = WebhookSink::new(&format!("http://127.0.0.1:{port}/alerts")).unwrap();
+    let message = FraudAlert::from_score(&score("tx-1", true)).into_message();
+    assert!(sink.publish(&message).await.is_err());
+    sink.publish(&message).await.unwrap();
+
+    let requests = server.await.unwrap();
+    assert!(requests[1].starts_with("POST /alerts HTTP/1.1"));
+    assert!(requests[1].contains("\"key\":\"tx-1\""));
+    assert!(WebhookSink::new("https://example.com").is_err());
+}
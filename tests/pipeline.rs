@@ -0,0 +1,229 @@
+// tests/pipeline.rs
+
+use fraud_detection_3::domain::fraud_scorer::{FraudScorer, RuleBasedScorer};
+use fraud_detection_3::domain::query::{Page, ScoreQuery};
+use fraud_detection_3::domain::repository::{ScoreRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::in_memory::{InMemoryScoreRepo, InMemoryTransactionRepo};
+use fraud_detection_3::domain::repository::EventStore;
+use fraud_detection_3::persistence::sqlite::{SQLiteScoreRepo, SqliteStore, SqliteViews};
+use fraud_detection_3::pipeline::stages::{LifecycleStage, PersistenceStage, ScorePersistenceStage, ScoringStage, ValidationStage};
+use fraud_detection_3::pipeline::{Pipeline, Stage};
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::feedback::SettlementFeedback;
+use fraud_detection_3::state_machine::observer::LifecycleEvents;
+use fraud_detection_3::state_machine::timers::{TimerWheel, default_timer_rules};
+use fraud_detection_3::state_machine::transitions::{TransitionContext, standard_table};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::Duration;
+
+fn tx(id: &str, amount: f64) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_fraud_pipeline_end_to_end() {
+    let tx_repo = Arc::new(InMemoryTransactionRepo::new());
+    let score_repo = Arc::new(SQLiteScoreRepo::new(":memory:"));
+
+    let (pipeline, mut output) = Pipeline::builder(4)
+        .stage(ValidationStage, 1)
+        .stage(PersistenceStage::new(tx_repo.clone(), score_repo.clone()), 2)
+        .stage(ScoringStage::new(Arc::new(RuleBasedScorer)), 3)
+        .stage(ScorePersistenceStage::new(score_repo.clone()), 1)
+        .build();
+
+    let collector = tokio::spawn(async move {
+        let mut scores = Vec::new();
+        while let Some(score) = output.recv().await {
+            scores.push(score);
+        }
+        scores
+    });
+
+    for i in 0..20 {
+        pipeline.submit(tx(&format!("tx-{i}"), 100.0 * i as f64)).await.unwrap();
+    }
+    pipeline.submit(tx("tx-5", 500.0)).await.unwrap(); // duplicate
+
+    let stats = pipeline.shutdown().await;
+    let scores = collector.await.unwrap();
+
+    // tx-0 fails validation (amount 0), the duplicate gets a score too
+    assert_eq!(scores.len(), 20);
+    assert_eq!(scores.iter().filter(|s| s.id == "tx-5").count(), 2);
+    assert_eq!(stats.iter().map(|s| s.name).collect::<Vec<_>>(), ["validation", "persistence", "scoring", "score_persistence"]);
+    assert_eq!((stats[0].processed, stats[0].dropped), (20, 1));
+    assert_eq!((stats[1].processed, stats[1].dropped), (20, 0));
+    assert_eq!(stats[3].processed, 20);
+    assert!(tx_repo.get("tx-0").is_none());
+    assert!(score_repo.get("tx-19").unwrap().is_fraud);
+}
+
+/// Scores the n-th transaction it sees n
+#[derive(Default)]
+struct CountingScorer(AtomicUsize);
+
+impl FraudScorer for CountingScorer {
+    fn is_fraud(&self, _: &Transaction) -> bool {
+        false
+    }
+
+    fn score_batch(&self, txs: &[Transaction]) -> Vec<Score> {
+        txs.iter()
+            .map(|tx| Score {
+                id: tx.id.clone(),
+                score: (self.0.fetch_add(1, Ordering::SeqCst) + 1) as f64,
+                is_fraud: false,
+            })
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn test_retry_gets_original_score() {
+    let score_repo = Arc::new(InMemoryScoreRepo::new());
+    let scorer = Arc::new(CountingScorer::default());
+    let (pipeline, mut output) = Pipeline::builder(4)
+        .stage(PersistenceStage::new(Arc::new(InMemoryTransactionRepo::new()), score_repo.clone()), 1)
+        .stage(ScoringStage::new(scorer.clone()), 1)
+        .stage(ScorePersistenceStage::new(score_repo.clone()), 1)
+        .build();
+
+    pipeline.submit(tx("tx-1", 10.0)).await.unwrap();
+    let first = output.recv().await.unwrap();
+    pipeline.submit(tx("tx-1", 10.0)).await.unwrap();
+    let retry = output.recv().await.unwrap();
+    pipeline.shutdown().await;
+
+    assert_eq!(retry, first);
+    assert_eq!(scorer.0.load(Ordering::SeqCst), 1);
+    assert_eq!(score_repo.get("tx-1"), Some(first));
+}
+
+struct Double;
+
+impl Stage for Double {
+    type In = u32;
+    type Out = u32;
+
+    fn name(&self) -> &'static str {
+        "double"
+    }
+
+    async fn process(&self, n: u32) -> Option<u32> {
+        Some(n * 2)
+    }
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_without_output_receiver() {
+    let (pipeline, output) = Pipeline::builder(2).stage(Double, 2).stage(Double, 1).build();
+    drop(output);
+
+    let sender = pipeline.sender();
+    for n in 0..10 {
+        sender.send(n).await.unwrap();
+    }
+    drop(sender);
+
+    let stats = pipeline.shutdown().await;
+    assert_eq!(stats[0].processed, 10);
+    assert_eq!(stats[1].processed, 10);
+}
+
+/// Saves block until the test opens the gate, like a writer waiting on the SQLite lock
+struct GatedScoreRepo {
+    gate: Mutex<mpsc::Receiver<()>>,
+    opened: AtomicBool,
+    inner: InMemoryScoreRepo,
+}
+
+impl ScoreRepository for GatedScoreRepo {
+    fn save(&self, result: Score) {
+        let opened = self.gate.lock().unwrap().recv_timeout(Duration::from_secs(5)).is_ok();
+        self.opened.store(opened, Ordering::SeqCst);
+        self.inner.save(result);
+    }
+
+    fn get(&self, tx_id: &str) -> Option<Score> {
+        self.inner.get(tx_id)
+    }
+
+    fn query(&self, query: &ScoreQuery) -> Page<Score> {
+        self.inner.query(query)
+    }
+
+    fn count(&self, query: &ScoreQuery) -> usize {
+        self.inner.count(query)
+    }
+}
+
+#[tokio::test]
+async fn test_blocking_repository_leaves_the_runtime_free() {
+    let (open, gate) = mpsc::channel();
+    let score_repo = Arc::new(GatedScoreRepo {
+        gate: Mutex::new(gate),
+        opened: AtomicBool::new(false),
+        inner: InMemoryScoreRepo::new(),
+    });
+    let (pipeline, mut output) = Pipeline::builder(4)
+        .stage(PersistenceStage::new(Arc::new(InMemoryTransactionRepo::new()), score_repo.clone()), 1)
+        .stage(ScoringStage::new(Arc::new(RuleBasedScorer)), 1)
+        .stage(ScorePersistenceStage::new(score_repo.clone()), 1)
+        .build();
+    pipeline.submit(tx("tx-1", 10.0)).await.unwrap();
+
+    // Only runs if the save is not blocking the single runtime thread
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    open.send(()).unwrap();
+
+    assert_eq!(output.recv().await.map(|s| s.id), Some("tx-1".to_string()));
+    assert!(score_repo.opened.load(Ordering::SeqCst));
+    pipeline.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_stages_and_lifecycle_append_events() {
+    let store = SqliteStore::open(":memory:");
+    let events: Arc<dyn EventStore> = Arc::new(store.events());
+    let (states, scores) = (Arc::new(store.states()), Arc::new(store.scores()));
+    let table = Arc::new(standard_table().observe(Arc::new(LifecycleEvents::new(events.clone()))));
+    let wheel = Arc::new(TimerWheel::new(table.clone(), states.clone(), Arc::new(store.timers()), default_timer_rules()));
+
+    let (pipeline, mut output) = Pipeline::builder(4)
+        .stage(ValidationStage, 1)
+        .stage(PersistenceStage::new(Arc::new(store.transactions()), scores.clone()).with_events(events.clone()), 1)
+        .stage(ScoringStage::new(Arc::new(RuleBasedScorer)), 1)
+        .stage(ScorePersistenceStage::new(scores.clone()).with_events(events.clone(), "rules-v1"), 1)
+        .stage(LifecycleStage::new(wheel.clone()), 1)
+        .build();
+    let drain = tokio::spawn(async move { while output.recv().await.is_some() {} });
+    pipeline.submit(tx("tx-1", 5000.0)).await.unwrap();
+    pipeline.submit(tx("tx-2", 10.0)).await.unwrap();
+    pipeline.shutdown().await;
+    drain.await.unwrap();
+
+    // An analyst confirms the flag, the issuer charges the clean one back
+    for event in [Event::AssignToAnalyst, Event::ConfirmFraud] {
+        wheel.fire(event, &TransitionContext::new("tx-1").by("analyst-7")).unwrap();
+    }
+    let feedback = SettlementFeedback::new(table, states, scores, Arc::new(store.labels()));
+    feedback.apply(Event::Chargeback, &TransitionContext::new("tx-2").by("issuer")).unwrap();
+
+    let kinds = |id| events.read_stream(id, 0).iter().map(|e| e.event.kind()).collect::<Vec<_>>();
+    assert_eq!(kinds("tx-1"), ["TransactionReceived", "Validated", "Scored", "Flagged", "Reviewed"]);
+    assert_eq!(kinds("tx-2"), ["TransactionReceived", "Validated", "Scored", "ChargebackReceived"]);
+    let view = fraud_detection_3::domain::tx_event::load_view(events.as_ref(), "tx-1", 100).unwrap();
+    assert_eq!(view.transaction, Some(tx("tx-1", 5000.0)));
+    assert!(view.score.unwrap().is_fraud);
+    let review = view.review.unwrap();
+    assert_eq!((review.analyst_id.as_str(), review.confirmed_fraud), ("analyst-7", true));
+}
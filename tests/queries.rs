@@ -0,0 +1,109 @@
+// tests/queries.rs
+
+use fraud_detection_3::domain::clock::now_millis;
+use fraud_detection_3::domain::query::{Cursor, ScoreQuery, TransactionQuery};
+use fraud_detection_3::domain::repository::{ScoreRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::in_memory::InMemoryTransactionRepo;
+use fraud_detection_3::persistence::kv::RedbStore;
+use fraud_detection_3::persistence::sqlite::{SQLiteScoreRepo, SQLiteTransRepo};
+
+fn seed(repo: &dyn TransRepository) {
+    for i in 0..10 {
+        repo.save(Transaction {
+            id: format!("tx-{i:02}"),
+            amount: 10.0 * i as f64,
+            currency: if i % 2 == 0 { "USD" } else { "EUR" }.to_string(),
+            merchant_id: format!("m-{}", i % 3),
+            account_id: "acc-1".to_string(),
+        });
+    }
+}
+
+/// Follows the cursors until the last page
+fn all_ids(repo: &dyn TransRepository, query: TransactionQuery) -> (Vec<String>, usize) {
+    let mut ids = Vec::new();
+    let mut pages = 0;
+    let mut query = query;
+    loop {
+        let page = repo.query(&query);
+        pages += 1;
+        ids.extend(page.items.into_iter().map(|tx| tx.id));
+        match page.next {
+            Some(cursor) => query = query.after(cursor),
+            None => return (ids, pages),
+        }
+    }
+}
+
+fn check_transaction_queries(repo: &dyn TransRepository) {
+    let start = now_millis();
+    seed(repo);
+
+    let (ids, pages) = all_ids(repo, TransactionQuery::new().limit(3));
+    assert_eq!(ids.len(), 10);
+    assert_eq!(pages, 4);
+    let mut sorted = ids.clone();
+    sorted.dedup();
+    assert_eq!(sorted.len(), 10, "no item is returned twice");
+
+    let usd = TransactionQuery::new().currency("USD");
+    assert_eq!(repo.count(&usd), 5);
+    assert!(repo.query(&usd).items.iter().all(|tx| tx.currency == "USD"));
+
+    let query = TransactionQuery::new().currency("EUR").merchant("m-1");
+    assert_eq!(all_ids(repo, query.clone().limit(1)).0, vec!["tx-01", "tx-07"]);
+    assert_eq!(repo.count(&query), 2);
+
+    assert_eq!(repo.count(&TransactionQuery::new().received_between(start, now_millis() + 1)), 10);
+    assert_eq!(repo.count(&TransactionQuery::new().received_between(0, start)), 0);
+    assert_eq!(repo.count(&TransactionQuery::new().account("acc-2")), 0);
+}
+
+#[test]
+fn test_in_memory_transaction_queries() {
+    check_transaction_queries(&InMemoryTransactionRepo::new());
+}
+
+#[test]
+fn test_sqlite_transaction_queries() {
+    check_transaction_queries(&SQLiteTransRepo::new(":memory:"));
+}
+
+#[test]
+fn test_redb_transaction_queries() {
+    check_transaction_queries(&RedbStore::in_memory().transactions());
+}
+
+fn check_score_review_queue(repo: &dyn ScoreRepository) {
+    for (id, score) in [("tx-1", 0.95), ("tx-2", 0.2), ("tx-3", 0.95), ("tx-4", 0.85), ("tx-5", 0.5)] {
+        repo.save(Score {
+            id: id.to_string(),
+            score,
+            is_fraud: score > 0.8,
+        });
+    }
+
+    let queue = ScoreQuery::new().fraud(true).limit(2);
+    assert_eq!(repo.count(&queue), 3);
+    let first = repo.query(&queue);
+    assert_eq!(first.items.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["tx-1", "tx-3"]);
+
+    // The cursor survives a round trip through a URL
+    let token = first.next.unwrap().as_str().to_string();
+    let second = repo.query(&queue.clone().after(Cursor::from_token(&token).unwrap()));
+    assert_eq!(second.items.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["tx-4"]);
+    assert!(second.next.is_none());
+
+    let middle = ScoreQuery::new().score_between(0.2, 0.85);
+    assert_eq!(repo.count(&middle), 3);
+    assert_eq!(repo.query(&middle).items.first().unwrap().id, "tx-4");
+    assert!(Cursor::from_token("no-separator").is_none());
+}
+
+#[test]
+fn test_score_review_queue() {
+    check_score_review_queue(&SQLiteScoreRepo::new(":memory:"));
+    check_score_review_queue(&RedbStore::in_memory().scores());
+}
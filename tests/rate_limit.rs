@@ -0,0 +1,132 @@
+// tests/rate_limit.rs
+
+use fraud_detection_3::domain::fraud_scorer::{FraudScorer, RuleBasedScorer};
+use fraud_detection_3::domain::repository::TransRepository;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::ingestion::rate_limit::{Admission, LimitScope, RateLimit, RateLimitConfig, RateLimiter, RiskSignals};
+use fraud_detection_3::ingestion::{Ingress, RateLimitAwareScorer};
+use fraud_detection_3::persistence::sqlite::SQLiteTransRepo;
+use fraud_detection_3::workers::dispatcher::WorkerMessage;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+fn tx(id: &str, merchant_id: &str, account_id: &str) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount: 10.0,
+        currency: "USD".to_string(),
+        merchant_id: merchant_id.to_string(),
+        account_id: account_id.to_string(),
+    }
+}
+
+// No refill: the outcome only depends on the number of calls
+fn config(merchant: f64, account: f64) -> RateLimitConfig {
+    RateLimitConfig {
+        per_merchant: RateLimit {
+            capacity: merchant,
+            refill_per_sec: 0.0,
+        },
+        per_account: RateLimit {
+            capacity: account,
+            refill_per_sec: 0.0,
+        },
+        idle_ttl: Duration::from_secs(60),
+    }
+}
+
+#[test]
+fn test_limits_per_merchant_and_account() {
+    let limiter = RateLimiter::new(config(3.0, 2.0));
+
+    assert_eq!(limiter.check(&tx("1", "m1", "a1")), Admission::Admitted);
+    assert_eq!(limiter.check(&tx("2", "m1", "a1")), Admission::Admitted);
+    assert_eq!(
+        limiter.check(&tx("3", "m1", "a1")),
+        Admission::Throttled {
+            scope: LimitScope::Account,
+            key: "a1".to_string()
+        }
+    );
+    // The account rejection above did not consume a merchant token
+    assert_eq!(limiter.check(&tx("4", "m1", "a2")), Admission::Admitted);
+    assert_eq!(
+        limiter.check(&tx("5", "m1", "a3")),
+        Admission::Throttled {
+            scope: LimitScope::Merchant,
+            key: "m1".to_string()
+        }
+    );
+    assert_eq!(limiter.check(&tx("6", "m2", "a3")), Admission::Admitted);
+}
+
+#[test]
+fn test_eviction_of_idle_buckets() {
+    let mut cfg = config(1.0, 1.0);
+    cfg.idle_ttl = Duration::ZERO;
+    let limiter = RateLimiter::new(cfg);
+
+    limiter.check(&tx("1", "m1", "a1"));
+    assert_eq!(limiter.tracked_keys(), 2);
+    assert_eq!(limiter.evict_idle(), 2);
+    assert_eq!(limiter.check(&tx("2", "m1", "a1")), Admission::Admitted);
+}
+
+#[tokio::test]
+async fn test_throttled_transaction_becomes_risk_signal() {
+    let (sender, mut rx) = mpsc::channel(8);
+    let limiter = Arc::new(RateLimiter::new(config(100.0, 1.0)));
+    let signals = Arc::new(RiskSignals::new(Duration::from_secs(60)));
+    let ingress = Ingress::new(sender, limiter, signals.clone());
+
+    assert_eq!(ingress.submit(tx("1", "m1", "a1")).await, Ok(Admission::Admitted));
+    assert!(matches!(ingress.submit(tx("2", "m1", "a1")).await, Ok(Admission::Throttled { .. })));
+
+    // Only the admitted transaction reached the workers
+    assert!(matches!(rx.recv().await, Some(WorkerMessage::Traced { tx: t, .. }) if t.id == "1"));
+    assert!(rx.try_recv().is_err());
+
+    let scorer = RateLimitAwareScorer {
+        inner: RuleBasedScorer,
+        signals: signals.clone(),
+        threshold: 1,
+    };
+    assert!(scorer.is_fraud(&tx("3", "m9", "a1")));
+    assert!(!scorer.is_fraud(&tx("4", "m9", "a9")));
+}
+
+#[test]
+fn test_risk_signals_stay_bounded_and_expire() {
+    let signals = RiskSignals::new(Duration::from_millis(100));
+    for _ in 0..10_000 {
+        signals.record_throttled(LimitScope::Merchant, "m1");
+    }
+    assert_eq!(signals.tracked_keys(), 1);
+    assert_eq!(signals.recent_throttles(&tx("1", "m1", "a1")), 10_000);
+
+    std::thread::sleep(Duration::from_millis(250));
+    assert_eq!(signals.recent_throttles(&tx("2", "m1", "a1")), 0);
+    assert_eq!(signals.evict_expired(), 1);
+    assert_eq!(signals.tracked_keys(), 0);
+}
+
+#[test]
+fn test_identifier_columns_added_to_old_databases() {
+    let path = std::env::temp_dir().join(format!("fd3_no_identifiers_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE transactions (id TEXT PRIMARY KEY, amount REAL NOT NULL, currency TEXT NOT NULL);
+            INSERT INTO transactions VALUES ('old-1', 10.0, 'USD');",
+        )
+        .unwrap();
+    }
+
+    let repo = SQLiteTransRepo::new(path.to_str().unwrap());
+    assert_eq!(repo.get("old-1"), Some(tx("old-1", "", "")));
+    repo.save(tx("new-1", "merchant-1", "account-1"));
+    assert_eq!(repo.get("new-1"), Some(tx("new-1", "merchant-1", "account-1")));
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,88 @@
+// tests/redaction.rs
+
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::security::RedactingFields;
+use fraud_detection_3::security::redact::{mask_tail, redact_text};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Captured;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+fn card_tx() -> Transaction {
+    Transaction {
+        id: "tx-1".to_string(),
+        amount: 12.5,
+        currency: "EUR".to_string(),
+        merchant_id: "m-1".to_string(),
+        account_id: "4111 1111 1111 1111".to_string(),
+    }
+}
+
+#[test]
+fn test_redact_text_masks_pans_emails_and_ips() {
+    let text = "card 4111-1111-1111-1111 from jane.doe@example.com at 192.168.1.20:443 and 2001:db8::1.";
+    let redacted = redact_text(text);
+    assert_eq!(redacted, "card ****1111 from ***@example.com at 192.168.*.* and 2001:db8:*.");
+
+    // Ids, amounts and numbers failing Luhn stay readable
+    let plain = "tx-42 amount=1250.00 order 4111111111111112";
+    assert_eq!(redact_text(plain), plain);
+    assert_eq!(mask_tail("acc-1"), "****");
+    assert_eq!(mask_tail("tok_0123456789abcdef"), "****cdef");
+}
+
+#[test]
+fn test_transaction_debug_and_display_hide_account() {
+    let tx = card_tx();
+    let debug = format!("{tx:?}");
+    assert!(!debug.contains("4111 1111"), "{debug}");
+    assert!(debug.contains("****1111") && debug.contains("m-1"));
+    assert_eq!(tx.to_string(), "tx-1 (12.50 EUR)");
+}
+
+#[test]
+fn test_layer_masks_configured_fields_and_scanned_values() {
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::registry().with(
+        tracing_subscriber::fmt::layer()
+            .fmt_fields(RedactingFields::new(["account_id", "email"]))
+            .with_writer(captured.clone())
+            .with_ansi(false),
+    );
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("ingest", client_ip = "10.1.2.3");
+        let _entered = span.enter();
+        info!(account_id = "acc-12345678", email = "someone", tx = ?card_tx(), "Seen card 5500005555555559 twice");
+    });
+
+    let out = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    for secret in ["acc-12345678", "someone", "4111 1111", "5500005555555559", "10.1.2.3"] {
+        assert!(!out.contains(secret), "{secret} leaked in {out}");
+    }
+    assert!(out.contains("account_id=\"****5678\""), "{out}");
+    assert!(out.contains("Seen card ****5559 twice"), "{out}");
+    assert!(out.contains("client_ip=\"10.1.*.*\""), "{out}");
+}
@@ -0,0 +1,257 @@
+// tests/retention.rs
+
+use flate2::read::MultiGzDecoder;
+use fraud_detection_3::domain::label::{Label, LabelOutcome};
+use fraud_detection_3::domain::repository::{LabelRepository, Outbox, ScoreRepository, StateRepository, TimerStore, TransRepository};
+use fraud_detection_3::domain::retention::{ExpiredRow, RetentionPolicy};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::backend::Backend;
+use fraud_detection_3::persistence::sqlite::{SQLiteScoreRepo, SqliteStore, SqliteViews, StoreConfig};
+use fraud_detection_3::security::{ColumnCipher, ColumnEncryption, EncryptedColumn, Key};
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::feedback::SettlementFeedback;
+use fraud_detection_3::state_machine::transitions::{StateId, StateMachine, TransitionContext, standard_table};
+use fraud_detection_3::workers::purge::{PurgeConfig, PurgeJob};
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn now() -> i64 {
+    fraud_detection_3::domain::clock::now_millis()
+}
+
+fn tx(id: &str) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount: 10.0,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+fn score(id: &str, is_fraud: bool) -> Score {
+    Score {
+        id: id.to_string(),
+        score: if is_fraud { 0.95 } else { 0.1 },
+        is_fraud,
+    }
+}
+
+fn seeded_store() -> SqliteStore {
+    let store = SqliteStore::open(":memory:");
+    for (id, is_fraud) in [("legit-1", false), ("legit-2", false), ("fraud-1", true)] {
+        store.transactions().save(tx(id));
+        store.scores().save(score(id, is_fraud));
+    }
+    store
+}
+
+#[test]
+fn test_policy_per_table() {
+    let store = seeded_store();
+    let job = PurgeJob::new(Arc::new(store.retention()), PurgeConfig::default());
+
+    let report = job.run_once(now() + 30 * DAY_MS).unwrap();
+    assert_eq!((report.transactions, report.scores), (0, 0), "nothing expired yet");
+
+    // Raw transactions go after 90 days, scores stay
+    let report = job.run_once(now() + 91 * DAY_MS).unwrap();
+    assert_eq!((report.transactions, report.scores), (2, 0));
+    assert_eq!(store.transactions().get("legit-1"), None);
+    assert!(store.scores().get("legit-1").is_some());
+
+    // Scores go after 2 years, fraud stays forever
+    let report = job.run_once(now() + 731 * DAY_MS).unwrap();
+    assert_eq!((report.transactions, report.scores), (0, 2));
+    assert_eq!(store.transactions().get("fraud-1"), Some(tx("fraud-1")));
+    assert_eq!(store.scores().get("fraud-1"), Some(score("fraud-1", true)));
+}
+
+#[test]
+fn test_backend_purges_through_its_own_connection() {
+    // `:memory:` is private to a connection: the purge only sees these rows on the repositories' one
+    let repos = Backend::parse("sqlite::memory:").open();
+    repos.transactions.save(tx("legit-1"));
+    repos.scores.save(score("legit-1", false));
+    let job = PurgeJob::new(repos.retention.expect("SQLite has retention"), PurgeConfig::default());
+
+    let report = job.run_once(now() + 731 * DAY_MS).unwrap();
+    assert_eq!((report.transactions, report.scores), (1, 1));
+    assert_eq!(repos.transactions.get("legit-1"), None);
+    assert!(Backend::InMemory.open().retention.is_none());
+}
+
+#[test]
+fn test_fraud_purged_when_not_kept() {
+    let store = seeded_store();
+    let config = PurgeConfig {
+        policy: RetentionPolicy {
+            scores: None,
+            keep_fraud: false,
+            ..RetentionPolicy::default()
+        },
+        ..PurgeConfig::default()
+    };
+    let report = PurgeJob::new(Arc::new(store.retention()), config).run_once(now() + 3650 * DAY_MS).unwrap();
+    assert_eq!((report.transactions, report.scores), (3, 0), "scores without a max age are kept");
+    assert_eq!(store.transactions().get("fraud-1"), None);
+}
+
+#[test]
+fn test_archive_before_delete() {
+    let dir = std::env::temp_dir().join(format!("fd3_archive_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = seeded_store();
+    for i in 0..5 {
+        store.transactions().save(tx(&format!("extra-{i}")));
+    }
+    let config = PurgeConfig {
+        batch_size: 2,
+        pause: Duration::ZERO,
+        archive_dir: Some(dir.clone()),
+        ..PurgeConfig::default()
+    };
+
+    let report = PurgeJob::new(Arc::new(store.retention()), config).run_once(now() + 91 * DAY_MS).unwrap();
+    assert_eq!(report.transactions, 7);
+    assert_eq!(report.archives.len(), 1, "one file per table and run");
+
+    // One gzip member per batch, read back as one stream
+    let reader = BufReader::new(MultiGzDecoder::new(std::fs::File::open(&report.archives[0]).unwrap()));
+    let rows: Vec<ExpiredRow> = reader.lines().map(|line| serde_json::from_str(&line.unwrap()).unwrap()).collect();
+    assert_eq!(rows.len(), 7);
+    assert!(rows.iter().all(|row| matches!(row, ExpiredRow::Transaction { .. })));
+    assert!(rows.iter().any(|row| row.id() == "legit-2"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// `id` scored and moved through the pipeline's lifecycle, with a stray timer
+fn decided(store: &SqliteStore, id: &str, is_fraud: bool) {
+    store.transactions().save(tx(id));
+    store.scores().save(score(id, is_fraud));
+    StateMachine::record_decision(Arc::new(standard_table()), &store.states(), &score(id, is_fraud)).unwrap();
+    store.timers().schedule(id, Event::Escalate, now());
+}
+
+#[test]
+fn test_purge_keeps_open_cases_lifecycle_labels_and_pending_alerts() {
+    let store = SqliteStore::open_with(
+        ":memory:",
+        StoreConfig {
+            outbox: true,
+            ..StoreConfig::default()
+        },
+    );
+    decided(&store, "legit-1", false);
+    decided(&store, "fraud-1", true);
+    store.labels().save(Label {
+        tx_id: "legit-1".to_string(),
+        outcome: LabelOutcome::Legit,
+        source: Event::Refund,
+        score: Some(score("legit-1", false)),
+        labeled_at: now(),
+        notes: None,
+    });
+    let purge_fraud = PurgeConfig {
+        policy: RetentionPolicy {
+            keep_fraud: false,
+            ..RetentionPolicy::default()
+        },
+        ..PurgeConfig::default()
+    };
+    let job = PurgeJob::new(Arc::new(store.retention()), purge_fraud);
+
+    // The flag is still open: only legit-1 goes, its lifecycle and label stay
+    assert_eq!(job.run_once(now() + 91 * DAY_MS).unwrap().transactions, 1);
+    assert_eq!(store.transactions().get("legit-1"), None);
+    assert_eq!(store.states().get_state("legit-1"), Some(StateId::Persisted));
+    assert_eq!(store.states().history("legit-1").len(), 3);
+    assert_eq!(store.labels().for_tx("legit-1").len(), 1);
+    assert!(store.timers().pending("legit-1").is_empty());
+    assert!(store.transactions().get("fraud-1").is_some());
+    assert_eq!(job.run_once(now() + 731 * DAY_MS).unwrap().scores, 1, "the open case keeps its score too");
+
+    // Once confirmed it goes, its alert stays until delivered
+    for event in [Event::AssignToAnalyst, Event::ConfirmFraud] {
+        StateMachine::load(Arc::new(standard_table()), &store.states(), "fraud-1")
+            .unwrap()
+            .fire_and_record(event, &TransitionContext::new("fraud-1").by("analyst-7"), &store.states())
+            .unwrap();
+    }
+    assert_eq!(job.run_once(now() + 91 * DAY_MS).unwrap().transactions, 1);
+    assert_eq!(store.outbox().pending(10).len(), 1);
+}
+
+#[test]
+fn test_late_chargeback_after_purge() {
+    let store = Arc::new(SqliteStore::open(":memory:"));
+    decided(&store, "legit-1", false);
+    PurgeJob::new(Arc::new(store.retention()), PurgeConfig::default()).run_once(now() + 91 * DAY_MS).unwrap();
+    assert_eq!(store.transactions().get("legit-1"), None);
+
+    let states = Arc::new(store.states());
+    let feedback = SettlementFeedback::with_store(Arc::new(standard_table()), states.clone(), Arc::new(store.scores()), store.clone());
+    let label = feedback.apply(Event::Chargeback, &TransitionContext::new("legit-1").by("issuer")).unwrap();
+    assert_eq!(label.outcome, LabelOutcome::Fraud);
+    assert_eq!(label.score, Some(score("legit-1", false)));
+    assert_eq!(states.get_state("legit-1"), Some(StateId::ChargedBack));
+}
+
+#[test]
+fn test_archive_keeps_columns_encrypted() {
+    let dir = std::env::temp_dir().join(format!("fd3_archive_sealed_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let encryption = ColumnEncryption::new(ColumnCipher::new(vec![Key::generate("c1")]), vec![EncryptedColumn::MerchantId, EncryptedColumn::AccountId]);
+    let store = SqliteStore::open_with(
+        ":memory:",
+        StoreConfig {
+            encryption: Some(encryption.clone()),
+            ..StoreConfig::default()
+        },
+    );
+    let stored = Transaction {
+        merchant_id: "m-1".to_string(),
+        account_id: "acc-1".to_string(),
+        ..tx("legit-1")
+    };
+    store.transactions().save(stored.clone());
+    let config = PurgeConfig {
+        archive_dir: Some(dir.clone()),
+        ..PurgeConfig::default()
+    };
+
+    let report = PurgeJob::new(Arc::new(store.retention()), config).run_once(now() + 91 * DAY_MS).unwrap();
+    let reader = BufReader::new(MultiGzDecoder::new(std::fs::File::open(&report.archives[0]).unwrap()));
+    let rows: Vec<ExpiredRow> = reader.lines().map(|line| serde_json::from_str(&line.unwrap()).unwrap()).collect();
+    let [ExpiredRow::Transaction { transaction, .. }] = &rows[..] else { panic!("expected one transaction, got {rows:?}") };
+    assert!(transaction.merchant_id.starts_with("enc:c1:") && transaction.account_id.starts_with("enc:c1:"));
+    // Readable with the column keys only
+    assert_eq!(encryption.decrypt_tx(transaction.clone()).unwrap(), stored);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_scored_at_added_to_old_databases() {
+    let path = std::env::temp_dir().join(format!("fd3_scored_at_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE scoring_results (tx_id TEXT PRIMARY KEY, score REAL NOT NULL, is_fraud INTEGER NOT NULL);
+            INSERT INTO scoring_results VALUES ('old-1', 0.2, 0);",
+        )
+        .unwrap();
+    }
+
+    let repo = SQLiteScoreRepo::new(path.to_str().unwrap());
+    assert_eq!(repo.get("old-1").map(|s| s.score), Some(0.2));
+    let store = SqliteStore::open(path.to_str().unwrap());
+    let job = PurgeJob::new(Arc::new(store.retention()), PurgeConfig::default());
+    // Existing scores start their retention period at the migration
+    assert_eq!(job.run_once(now() + 700 * DAY_MS).unwrap().scores, 0);
+    assert_eq!(job.run_once(now() + 731 * DAY_MS).unwrap().scores, 1);
+    let _ = std::fs::remove_file(&path);
+}
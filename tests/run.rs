@@ -0,0 +1,86 @@
+// tests/run.rs
+
+use fraud_detection_3::domain::label::LabelOutcome;
+use fraud_detection_3::domain::repository::{EventStore, LabelRepository, StateRepository, TimerStore};
+use fraud_detection_3::persistence::sqlite::{SqliteStore, SqliteViews};
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::feedback::SettlementFeedback;
+use fraud_detection_3::state_machine::transitions::{StateId, TransitionContext, standard_table};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+const INPUT: &str = "tx-1,42.0,EUR,m-1,a-1\ntx-2,5000.0,EUR,m-1,a-2\ntx-1,42.0,EUR,m-1,a-1\n";
+
+/// Feeds `INPUT` to `fraud_detection_3 run` on a fresh database and returns its path
+fn run(name: &str, extra: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("fd3_run_{name}_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let path = path.to_str().unwrap().to_string();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_fraud_detection_3"))
+        .args(["run", "--db", &path])
+        .args(extra)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(INPUT.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 3);
+    path
+}
+
+/// The relay published the one fraud alert to the default target before exiting
+fn assert_alerts(path: &str) {
+    let alerts = format!("{path}.alerts.jsonl");
+    let published = std::fs::read_to_string(&alerts).unwrap();
+    let keys: Vec<String> = published.lines().map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["key"].as_str().unwrap().to_string()).collect();
+    assert_eq!(keys, ["tx-2"]);
+    let _ = std::fs::remove_file(&alerts);
+}
+
+fn assert_lifecycle(path: &str) {
+    let store = SqliteStore::open(path);
+    let states = Arc::new(store.states());
+    assert_eq!(states.get_state("tx-1"), Some(StateId::Persisted));
+    assert_eq!(states.get_state("tx-2"), Some(StateId::FlaggedAsFraud));
+    // The retry of tx-1 kept its first path
+    assert_eq!(states.history("tx-1").len(), 3);
+
+    // Only the flag arms a timer, the wheel escalates it if nobody picks it up
+    let timers = store.timers();
+    assert!(timers.pending("tx-1").is_empty());
+    let pending = timers.pending("tx-2");
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].event, Event::Escalate);
+
+    let labels = store.labels();
+    let store = Arc::new(store);
+    let feedback = SettlementFeedback::with_store(Arc::new(standard_table()), states.clone(), Arc::new(store.scores()), store.clone());
+    let label = feedback.apply(Event::Chargeback, &TransitionContext::new("tx-1").by("issuer")).unwrap();
+    assert_eq!(label.outcome, LabelOutcome::Fraud);
+    assert_eq!(states.get_state("tx-1"), Some(StateId::ChargedBack));
+    assert_eq!(labels.for_tx("tx-1"), vec![label]);
+}
+
+#[test]
+fn test_pipeline_run_records_the_lifecycle() {
+    let path = run("pipeline", &[]);
+    assert_lifecycle(&path);
+    assert_alerts(&path);
+    let events = SqliteStore::open(&path).events();
+    let kinds: Vec<_> = events.read_stream("tx-2", 0).iter().map(|e| e.event.kind()).collect();
+    assert_eq!(kinds, ["TransactionReceived", "Validated", "Scored", "Flagged"]);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_batched_run_records_the_lifecycle() {
+    let path = run("batched", &["--batch", "2"]);
+    assert_lifecycle(&path);
+    assert_alerts(&path);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,188 @@
+// tests/security.rs
+
+use fraud_detection_3::domain::query::TransactionQuery;
+use fraud_detection_3::domain::repository::TransRepository;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::in_memory::InMemoryTransactionRepo;
+use fraud_detection_3::persistence::backend::sqlite_encryption;
+use fraud_detection_3::persistence::sqlite::{SQLiteTransRepo, SqliteStore, SqliteViews, StoreConfig};
+use fraud_detection_3::persistence::tokenizing::TokenizingTransRepo;
+use fraud_detection_3::security::{CipherError, ColumnCipher, ColumnEncryption, EncryptedColumn, Key, KeyFile, Tokenizer, tokenize};
+use rusqlite::{Connection, params};
+
+fn tx(id: &str, merchant: &str, account: &str) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount: 10.0,
+        currency: "USD".to_string(),
+        merchant_id: merchant.to_string(),
+        account_id: account.to_string(),
+    }
+}
+
+fn temp_db(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("fd3_security_{name}_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path.to_str().unwrap().to_string()
+}
+
+fn encryption(keys: Vec<Key>) -> ColumnEncryption {
+    ColumnEncryption::new(ColumnCipher::new(keys), vec![EncryptedColumn::MerchantId, EncryptedColumn::AccountId])
+}
+
+fn raw_merchant(db_path: &str, id: &str) -> String {
+    let conn = Connection::open(db_path).unwrap();
+    conn.query_row("SELECT merchant_id FROM transactions WHERE id = ?1", params![id], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn test_pan_tokens_keep_last_digits_and_fail_luhn() {
+    let tokenizer = Tokenizer::new(&Key::generate("t1"));
+    let pan = "4111111111111111";
+    assert!(tokenize::looks_like_pan(pan));
+
+    let token = tokenizer.tokenize(pan);
+    assert_eq!(token.len(), pan.len());
+    assert!(token.ends_with("1111"));
+    assert_ne!(token, pan);
+    assert!(!tokenize::looks_like_pan(&token), "a token must never pass for a card number");
+    assert_eq!(tokenizer.tokenize(pan), token, "tokens are deterministic");
+
+    let account = tokenizer.tokenize("acc-42");
+    assert!(account.starts_with("tok_"));
+    assert_ne!(Tokenizer::new(&Key::generate("t2")).tokenize("acc-42"), account);
+}
+
+#[test]
+fn test_tokenizing_repo_stores_tokens_and_filters_on_raw_ids() {
+    let tokenizer = Tokenizer::new(&Key::generate("t1"));
+    let expected = tokenizer.tokenize("4111111111111111");
+    let repo = TokenizingTransRepo::new(InMemoryTransactionRepo::new(), tokenizer);
+    repo.save(tx("tx-1", "m-1", "4111111111111111"));
+    repo.save_idempotent(tx("tx-2", "m-1", "acc-2")).unwrap();
+
+    assert_eq!(repo.get("tx-1").unwrap().account_id, expected);
+    let query = TransactionQuery {
+        account_id: Some("4111111111111111".to_string()),
+        ..TransactionQuery::new()
+    };
+    let page = repo.query(&query);
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].id, "tx-1");
+    assert_eq!(repo.count(&query), 1);
+}
+
+#[test]
+fn test_encrypted_columns_round_trip_and_filter() {
+    let db_path = temp_db("round_trip");
+    let repo = SQLiteTransRepo::with_encryption(&db_path, encryption(vec![Key::generate("k1")]));
+    for i in 0..30 {
+        repo.save(tx(&format!("tx-{i:02}"), &format!("m-{}", i % 3), "acc-1"));
+    }
+
+    assert!(raw_merchant(&db_path, "tx-04").starts_with("enc:k1:"));
+    assert_eq!(repo.get("tx-04").unwrap(), tx("tx-04", "m-1", "acc-1"));
+
+    // Paged through, filtering on an encrypted column
+    let mut query = TransactionQuery {
+        merchant_id: Some("m-1".to_string()),
+        limit: 4,
+        ..TransactionQuery::new()
+    };
+    let mut ids = Vec::new();
+    loop {
+        let page = repo.query(&query);
+        assert!(page.items.iter().all(|t| t.merchant_id == "m-1"));
+        ids.extend(page.items.into_iter().map(|t| t.id));
+        match page.next {
+            Some(cursor) => query.after = Some(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(ids.len(), 10);
+    query.after = None;
+    assert_eq!(repo.count(&query), 10);
+}
+
+#[test]
+fn test_rotation_reencrypts_old_rows() {
+    let db_path = temp_db("rotation");
+    let keys_path = std::env::temp_dir().join(format!("fd3_security_keys_{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&keys_path);
+    let keys = KeyFile::generate(&keys_path).unwrap();
+    {
+        let repo = SQLiteTransRepo::with_encryption(&db_path, encryption(keys.column.clone()));
+        repo.save(tx("tx-1", "m-1", "acc-1"));
+        repo.save(tx("tx-2", "m-2", "acc-2"));
+    }
+    // Written before encryption was enabled
+    SQLiteTransRepo::new(&db_path).save(tx("tx-3", "m-3", "acc-3"));
+
+    assert_eq!(KeyFile::rotate(&keys_path).unwrap(), "k2");
+    let keys = KeyFile::load(&keys_path).unwrap();
+    let repo = SQLiteTransRepo::with_encryption(&db_path, encryption(keys.column.clone()));
+    assert_eq!(repo.get("tx-1").unwrap().merchant_id, "m-1", "old keys still decrypt");
+
+    assert_eq!(repo.reencrypt(2), 3);
+    assert_eq!(repo.reencrypt(2), 0);
+    for id in ["tx-1", "tx-2", "tx-3"] {
+        assert!(raw_merchant(&db_path, id).starts_with("enc:k2:"));
+    }
+    // Only the new key is needed from now on
+    let k2_only = SQLiteTransRepo::with_encryption(&db_path, encryption(vec![keys.column[1].clone()]));
+    assert_eq!(k2_only.get("tx-3").unwrap(), tx("tx-3", "m-3", "acc-3"));
+    let _ = std::fs::remove_file(&keys_path);
+}
+
+#[test]
+fn test_tampered_or_moved_ciphertext_does_not_decrypt() {
+    let cipher = ColumnCipher::new(vec![Key::generate("k1")]);
+    let sealed = cipher.encrypt("merchant_id", "m-1");
+    assert_eq!(cipher.decrypt("merchant_id", &sealed).unwrap(), "m-1");
+    assert_eq!(cipher.decrypt("account_id", &sealed), Err(CipherError::Decrypt));
+
+    let mut tampered = sealed.clone();
+    let last = tampered.pop().unwrap();
+    tampered.push(if last == '0' { '1' } else { '0' });
+    assert_eq!(cipher.decrypt("merchant_id", &tampered), Err(CipherError::Decrypt));
+
+    let other = ColumnCipher::new(vec![Key::generate("k2")]);
+    assert_eq!(other.decrypt("merchant_id", &sealed), Err(CipherError::UnknownKey("k1".to_string())));
+
+    let debug = format!("{:?}", KeyFile::parse(&format!("column k1 {}", "ab".repeat(32))).unwrap());
+    assert!(debug.contains("k1") && !debug.contains("abab"), "key material must not be printed");
+}
+
+#[test]
+fn test_generated_keys_are_private_and_encrypt_raw_account_ids() {
+    let db_path = temp_db("raw_account");
+    let keys_path = std::env::temp_dir().join(format!("fd3_security_private_keys_{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&keys_path);
+    let keys = KeyFile::generate(&keys_path).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&keys_path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    // A token key does not stop raw account ids written through the store's own views from being encrypted
+    assert!(keys.token.is_some());
+    let config = StoreConfig {
+        encryption: sqlite_encryption(&keys),
+        ..StoreConfig::default()
+    };
+    let store = SqliteStore::open_with(&db_path, config);
+    store.transactions().save(tx("tx-1", "m-1", "acc-1"));
+    store.unit_of_work(|uow| {
+        uow.save_transaction(&tx("tx-2", "m-2", "acc-2"));
+        Ok::<_, ()>(())
+    })
+    .unwrap();
+    let conn = Connection::open(&db_path).unwrap();
+    for id in ["tx-1", "tx-2"] {
+        let account: String = conn.query_row("SELECT account_id FROM transactions WHERE id = ?1", params![id], |row| row.get(0)).unwrap();
+        assert!(account.starts_with("enc:k1:"));
+    }
+    assert_eq!(store.transactions().get("tx-2").unwrap(), tx("tx-2", "m-2", "acc-2"));
+    let _ = std::fs::remove_file(&keys_path);
+}
@@ -0,0 +1,116 @@
+// tests/settlement_feedback.rs
+
+use fraud_detection_3::domain::label::{Label, LabelOutcome};
+use fraud_detection_3::domain::repository::{LabelRepository, ScoreRepository, StateRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::persistence::in_memory::{InMemoryLabelRepo, InMemoryStateRepo};
+use fraud_detection_3::persistence::sqlite::{SQLiteLabelRepo, SQLiteScoreRepo};
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::feedback::{FeedbackError, SettlementFeedback};
+use fraud_detection_3::state_machine::transitions::{StateId, StateMachine, TransitionContext, TransitionError, standard_table};
+use std::sync::Arc;
+
+/// Runs `tx_id` through the pipeline events with the given score and stores the score
+fn settle(states: &InMemoryStateRepo, scores: &SQLiteScoreRepo, tx_id: &str, is_fraud: bool) {
+    let score = Score {
+        id: tx_id.to_string(),
+        score: if is_fraud { 0.9 } else { 0.1 },
+        is_fraud,
+    };
+    scores.save(score.clone());
+    let ctx = TransitionContext::new(tx_id).with_score(score);
+    let mut fsm = StateMachine::new(Arc::new(standard_table()));
+    for event in [Event::Process, Event::Scored, Event::Persist] {
+        fsm.fire_and_record(event, &ctx, states).unwrap();
+    }
+}
+
+struct Fixture {
+    states: Arc<InMemoryStateRepo>,
+    scores: Arc<SQLiteScoreRepo>,
+    labels: Arc<InMemoryLabelRepo>,
+    feedback: SettlementFeedback,
+}
+
+fn fixture() -> Fixture {
+    let states = Arc::new(InMemoryStateRepo::new());
+    let scores = Arc::new(SQLiteScoreRepo::new(":memory:"));
+    let labels = Arc::new(InMemoryLabelRepo::new());
+    let feedback = SettlementFeedback::new(Arc::new(standard_table()), states.clone(), scores.clone(), labels.clone());
+    Fixture { states, scores, labels, feedback }
+}
+
+#[test]
+fn test_chargeback_reopens_persisted_transaction_and_labels_it() {
+    let f = fixture();
+    settle(&f.states, &f.scores, "tx-1", false);
+    assert_eq!(f.states.get_state("tx-1"), Some(StateId::Persisted));
+
+    let ctx = TransitionContext::new("tx-1").by("issuer").with_notes("reason code 10.4");
+    let label = f.feedback.apply(Event::Chargeback, &ctx).unwrap();
+
+    assert_eq!(label.outcome, LabelOutcome::Fraud);
+    assert_eq!(label.score.as_ref().map(|s| s.is_fraud), Some(false));
+    assert_eq!(label.score_was_right(), Some(false));
+    assert_eq!(f.states.get_state("tx-1"), Some(StateId::ChargedBack));
+    assert_eq!(f.states.history("tx-1").last().unwrap().actor.as_deref(), Some("issuer"));
+    assert_eq!(f.labels.for_tx("tx-1"), vec![label]);
+}
+
+#[test]
+fn test_dispute_then_refund() {
+    let f = fixture();
+    settle(&f.states, &f.scores, "tx-1", false);
+
+    let disputed = f.feedback.apply(Event::CustomerDisputed, &TransitionContext::new("tx-1")).unwrap();
+    assert_eq!(disputed.score_was_right(), None);
+    let refunded = f.feedback.apply(Event::Refund, &TransitionContext::new("tx-1")).unwrap();
+    assert_eq!(refunded.score_was_right(), Some(true));
+
+    assert_eq!(f.states.get_state("tx-1"), Some(StateId::Refunded));
+    let outcomes: Vec<_> = f.labels.for_tx("tx-1").into_iter().map(|l| l.outcome).collect();
+    assert_eq!(outcomes, vec![LabelOutcome::Disputed, LabelOutcome::Legit]);
+}
+
+#[test]
+fn test_feedback_errors_leave_state_untouched() {
+    let f = fixture();
+    let ctx = TransitionContext::new("tx-1");
+    assert_eq!(f.feedback.apply(Event::Chargeback, &ctx), Err(FeedbackError::UnknownTransaction("tx-1".to_string())));
+
+    settle(&f.states, &f.scores, "tx-1", true);
+    assert_eq!(f.feedback.apply(Event::Escalate, &ctx), Err(FeedbackError::NotFeedback(Event::Escalate)));
+    // Still waiting for a review, there is nothing to refund yet
+    assert_eq!(
+        f.feedback.apply(Event::Refund, &ctx),
+        Err(FeedbackError::Transition(TransitionError::Illegal {
+            from: StateId::FlaggedAsFraud,
+            event: Event::Refund,
+        }))
+    );
+    assert_eq!(f.states.get_state("tx-1"), Some(StateId::FlaggedAsFraud));
+    assert!(f.labels.for_tx("tx-1").is_empty());
+}
+
+#[test]
+fn test_sqlite_labels_round_trip() {
+    let states = Arc::new(InMemoryStateRepo::new());
+    let scores = Arc::new(SQLiteScoreRepo::new(":memory:"));
+    let labels = Arc::new(SQLiteLabelRepo::new(":memory:"));
+    let feedback = SettlementFeedback::new(Arc::new(standard_table()), states.clone(), scores.clone(), labels.clone());
+    settle(&states, &scores, "tx-1", false);
+
+    let label = feedback.apply(Event::Chargeback, &TransitionContext::new("tx-1").with_notes("reason code 10.4")).unwrap();
+    let unscored = Label {
+        tx_id: "tx-2".to_string(),
+        score: None,
+        labeled_at: label.labeled_at + 1,
+        ..label.clone()
+    };
+    labels.save(unscored.clone());
+
+    assert_eq!(labels.for_tx("tx-1"), vec![label.clone()]);
+    assert_eq!(labels.since(label.labeled_at, 10), vec![label.clone(), unscored.clone()]);
+    assert_eq!(labels.since(label.labeled_at + 1, 10), vec![unscored]);
+    assert_eq!(labels.since(0, 1), vec![label]);
+}
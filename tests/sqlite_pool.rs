@@ -0,0 +1,96 @@
+// tests/sqlite_pool.rs
+
+use fraud_detection_3::domain::repository::{ScoreRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::conformance;
+use fraud_detection_3::persistence::sqlite::{PoolConfig, SqlitePool, SqliteViews};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+static NEXT_DB: AtomicUsize = AtomicUsize::new(0);
+
+// A fresh database file per call, tests run in parallel
+fn db_path(name: &str) -> PathBuf {
+    let n = NEXT_DB.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("fd3_pool_{name}_{}_{n}.db", std::process::id()));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+    path
+}
+
+fn tx(id: &str) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount: 10.0,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_readers_see_committed_writes() {
+    let pool = SqlitePool::open(db_path("visible").to_str().unwrap());
+    let repo = pool.transactions();
+    repo.save(tx("tx-1"));
+    assert_eq!(repo.get("tx-1"), Some(tx("tx-1")));
+
+    pool.scores().save(Score {
+        id: "tx-1".to_string(),
+        score: 0.9,
+        is_fraud: true,
+    });
+    assert_eq!(pool.scores().get("tx-1").map(|s| s.is_fraud), Some(true));
+    assert_eq!(pool.idle_readers(), 4, "readers are returned after use");
+}
+
+#[test]
+fn test_reads_do_not_wait_for_writer() {
+    let path = db_path("concurrent");
+    let pool = SqlitePool::open(path.to_str().unwrap());
+    pool.transactions().save(tx("tx-1"));
+
+    let store = fraud_detection_3::persistence::sqlite::SqliteStore::open(path.to_str().unwrap());
+    std::thread::scope(|s| {
+        // Holds the write lock of another connection on the same file for a while
+        let writer = s.spawn(|| {
+            store
+                .unit_of_work(|uow| {
+                    uow.save_transaction(&tx("tx-2"));
+                    std::thread::sleep(Duration::from_millis(300));
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+        });
+        std::thread::sleep(Duration::from_millis(50));
+        let started = Instant::now();
+        let readers: Vec<_> = (0..4).map(|_| s.spawn(|| pool.transactions().get("tx-1"))).collect();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), Some(tx("tx-1")));
+        }
+        assert!(started.elapsed() < Duration::from_millis(200), "readers waited for the writer");
+        // Uncommitted rows aren't visible yet
+        assert_eq!(pool.transactions().get("tx-2"), None);
+        writer.join().unwrap();
+    });
+    assert_eq!(pool.transactions().get("tx-2"), Some(tx("tx-2")));
+}
+
+#[test]
+#[should_panic(expected = "SqlitePool needs a database file")]
+fn test_in_memory_rejected() {
+    SqlitePool::open(":memory:");
+}
+
+#[test]
+fn test_pool_views_conform() {
+    let config = PoolConfig {
+        readers: 2,
+        statement_cache: 16,
+        ..PoolConfig::default()
+    };
+    conformance::trans_repository_suite(|| SqlitePool::open_with(db_path("trans").to_str().unwrap(), config.clone()).transactions());
+    conformance::score_repository_suite(|| SqlitePool::open_with(db_path("scores").to_str().unwrap(), config.clone()).scores());
+}
@@ -0,0 +1,174 @@
+// tests/sqlite_store.rs
+
+use fraud_detection_3::domain::clock::now_millis;
+use fraud_detection_3::domain::repository::{LabelRepository, Outbox, RepoError, SaveOutcome, ScoreRepository, StateRepository, TransRepository};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::sqlite::{SqliteStore, SqliteViews, StoreConfig};
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::feedback::SettlementFeedback;
+use fraud_detection_3::state_machine::timers::{TimerWheel, default_timer_rules};
+use fraud_detection_3::state_machine::transitions::{StateId, StateMachine, TransitionContext, TransitionRecord, standard_table};
+use std::sync::Arc;
+
+fn tx(id: &str, amount: f64) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+fn score(id: &str) -> Score {
+    Score {
+        id: id.to_string(),
+        score: 0.9,
+        is_fraud: true,
+    }
+}
+
+fn record(id: &str) -> TransitionRecord {
+    TransitionRecord {
+        tx_id: id.to_string(),
+        from: StateId::Scored,
+        to: StateId::FlaggedAsFraud,
+        event: Event::Persist,
+        at: 1,
+        actor: None,
+        notes: None,
+    }
+}
+
+#[test]
+fn test_unit_of_work_commits_everything() {
+    let store = SqliteStore::open(":memory:");
+    let saved = store.unit_of_work(|uow| {
+        let outcome = uow.save_idempotent(tx("tx-1", 10.0))?;
+        uow.save_score(&score("tx-1"));
+        uow.record_transition(&record("tx-1"));
+        Ok::<_, RepoError>(outcome)
+    });
+    assert_eq!(saved, Ok(SaveOutcome::Inserted));
+
+    assert_eq!(store.transactions().get("tx-1"), Some(tx("tx-1", 10.0)));
+    assert_eq!(store.scores().get("tx-1"), Some(score("tx-1")));
+    assert_eq!(store.states().get_state("tx-1"), Some(StateId::FlaggedAsFraud));
+    assert_eq!(store.states().history("tx-1").len(), 1);
+}
+
+#[test]
+fn test_unit_of_work_rolls_back_on_error() {
+    let store = SqliteStore::open(":memory:");
+    store.transactions().save_idempotent(tx("tx-1", 10.0)).unwrap();
+
+    let conflict = store.unit_of_work(|uow| {
+        uow.save_score(&score("tx-1"));
+        uow.save_state("tx-1", StateId::Scored);
+        // A different payload under the same id: nothing of this unit must remain
+        uow.save_idempotent(tx("tx-1", 99.0))
+    });
+    assert_eq!(conflict, Err(RepoError::Conflict { id: "tx-1".to_string() }));
+    assert_eq!(store.scores().get("tx-1"), None);
+    assert_eq!(store.states().get_state("tx-1"), None);
+    assert_eq!(store.transactions().get("tx-1"), Some(tx("tx-1", 10.0)));
+}
+
+#[test]
+fn test_timers_and_labels_share_the_store_connection() {
+    // On `:memory:` every connection sees its own database: this only works on one shared connection
+    let store = SqliteStore::open(":memory:");
+    let table = Arc::new(standard_table());
+    let states = Arc::new(store.states());
+    let wheel = TimerWheel::new(table.clone(), states.clone(), Arc::new(store.timers()), default_timer_rules());
+
+    let ctx = TransitionContext::new("tx-1").with_score(score("tx-1"));
+    for event in [Event::Process, Event::Scored, Event::Persist] {
+        wheel.fire(event, &ctx).unwrap();
+    }
+    assert_eq!(wheel.fire_due(now_millis() + 2 * 60 * 60 * 1000), 1);
+    assert_eq!(store.states().get_state("tx-1"), Some(StateId::UnderReview));
+
+    store.scores().save(score("tx-1"));
+    wheel.fire(Event::AssignToAnalyst, &TransitionContext::new("tx-1").by("analyst-7")).unwrap();
+    wheel.fire(Event::ConfirmFraud, &TransitionContext::new("tx-1").by("analyst-7")).unwrap();
+    let scores = Arc::new(store.scores());
+    let labels = store.labels();
+    let store = Arc::new(store);
+    let feedback = SettlementFeedback::with_store(table, states, scores, store.clone());
+    feedback.apply(Event::Chargeback, &TransitionContext::new("tx-1")).unwrap();
+    assert_eq!(labels.for_tx("tx-1")[0].score, Some(score("tx-1")));
+    assert_eq!(store.states().get_state("tx-1"), Some(StateId::ChargedBack));
+}
+
+#[test]
+fn test_feedback_label_and_transition_roll_back_together() {
+    let path = std::env::temp_dir().join(format!("fd3_store_feedback_{}.db", std::process::id()));
+    let path = path.to_str().unwrap().to_string();
+    let _ = std::fs::remove_file(&path);
+    let table = Arc::new(standard_table());
+    {
+        let store = SqliteStore::open(&path);
+        let ctx = TransitionContext::new("tx-1").with_score(Score {
+            is_fraud: false,
+            ..score("tx-1")
+        });
+        let mut fsm = StateMachine::new(table.clone());
+        for event in [Event::Process, Event::Scored, Event::Persist] {
+            fsm.fire_and_record(event, &ctx, &store.states()).unwrap();
+        }
+    }
+    // The label insert fails after the transition was written
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch("CREATE TRIGGER no_labels BEFORE INSERT ON labels BEGIN SELECT RAISE(ABORT, 'no labels'); END;")
+        .unwrap();
+
+    let store = Arc::new(SqliteStore::open(&path));
+    let feedback = SettlementFeedback::with_store(table, Arc::new(store.states()), Arc::new(store.scores()), store.clone());
+    let failed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| feedback.apply(Event::Chargeback, &TransitionContext::new("tx-1"))));
+    assert!(failed.is_err());
+    drop(feedback);
+    drop(store);
+
+    let store = SqliteStore::open(&path);
+    assert_eq!(store.states().get_state("tx-1"), Some(StateId::Persisted));
+    assert_eq!(store.states().history("tx-1").len(), 3);
+    assert!(store.labels().for_tx("tx-1").is_empty());
+    drop(store);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{path}{suffix}"));
+    }
+}
+
+#[test]
+fn test_file_store_uses_wal_and_survives_reopen() {
+    let path = std::env::temp_dir().join(format!("fd3_store_{}.db", std::process::id()));
+    let path = path.to_str().unwrap().to_string();
+    let _ = std::fs::remove_file(&path);
+    {
+        let store = SqliteStore::open_with(
+            &path,
+            StoreConfig {
+                outbox: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(store.journal_mode(), "wal");
+        store.unit_of_work(|uow| {
+            uow.save_transaction(&tx("tx-1", 10.0));
+            uow.save_score(&score("tx-1"));
+            Ok::<_, RepoError>(())
+        })
+        .unwrap();
+        assert_eq!(store.outbox().pending(10).len(), 1);
+    }
+
+    let store = SqliteStore::open(&path);
+    assert_eq!(store.scores().get("tx-1"), Some(score("tx-1")));
+    assert_eq!(store.transactions().get("tx-1"), Some(tx("tx-1", 10.0)));
+    drop(store);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{path}{suffix}"));
+    }
+}
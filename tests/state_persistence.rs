@@ -0,0 +1,134 @@
+// tests/state_persistence.rs
+
+use fraud_detection_3::domain::repository::StateRepository;
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::persistence::in_memory::InMemoryStateRepo;
+use fraud_detection_3::persistence::sqlite::SQLiteStateRepo;
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::transitions::{StateId, StateMachine, TransitionContext, TransitionError, standard_table};
+use std::sync::Arc;
+
+fn flag_and_assign(repo: &dyn StateRepository) {
+    let table = Arc::new(standard_table());
+    let ctx = TransitionContext::new("tx-1").with_score(Score {
+        id: "tx-1".to_string(),
+        score: 0.95,
+        is_fraud: true,
+    });
+
+    repo.save_state("tx-1", table.initial());
+    let mut fsm = StateMachine::load(table, repo, "tx-1").unwrap();
+    for event in [Event::Process, Event::Scored, Event::Persist] {
+        fsm.fire_and_record(event, &ctx, repo).unwrap();
+    }
+    fsm.fire_and_record(Event::AssignToAnalyst, &ctx.clone().by("analyst-7").with_notes("check device"), repo).unwrap();
+}
+
+fn check_history(repo: &dyn StateRepository) {
+    assert_eq!(repo.get_state("tx-1"), Some(StateId::UnderReview));
+    assert_eq!(repo.ids_in_state(StateId::UnderReview), vec!["tx-1".to_string()]);
+
+    let history = repo.history("tx-1");
+    let path: Vec<_> = history.iter().map(|r| (r.from, r.event, r.to)).collect();
+    assert_eq!(
+        path,
+        [
+            (StateId::Validated, Event::Process, StateId::Enriched),
+            (StateId::Enriched, Event::Scored, StateId::Scored),
+            (StateId::Scored, Event::Persist, StateId::FlaggedAsFraud),
+            (StateId::FlaggedAsFraud, Event::AssignToAnalyst, StateId::UnderReview),
+        ]
+    );
+    assert_eq!(history[3].actor.as_deref(), Some("analyst-7"));
+    assert_eq!(history[3].notes.as_deref(), Some("check device"));
+    assert!(repo.history("tx-unknown").is_empty());
+}
+
+/// Two writers load tx-1 in the same state, only the first one to record wins
+fn race_on_stored_state(repo: &dyn StateRepository) {
+    let table = Arc::new(standard_table());
+    let mut analyst = StateMachine::load(table.clone(), repo, "tx-1").unwrap();
+    let mut late = StateMachine::load(table, repo, "tx-1").unwrap();
+
+    analyst.fire_and_record(Event::Clear, &TransitionContext::new("tx-1").by("analyst-7"), repo).unwrap();
+    let stale = late.fire_and_record(Event::ReviewTimeout, &TransitionContext::new("tx-1").by("timer"), repo);
+    assert_eq!(
+        stale,
+        Err(TransitionError::Stale {
+            tx_id: "tx-1".to_string(),
+            from: StateId::UnderReview
+        })
+    );
+    assert_eq!(late.current(), StateId::UnderReview);
+    assert!(late.history().is_empty());
+    assert_eq!(repo.get_state("tx-1"), Some(StateId::Cleared));
+    assert_eq!(repo.history("tx-1").last().unwrap().actor.as_deref(), Some("analyst-7"));
+}
+
+/// Concurrent runs of the pipeline on the same transaction record its decision once
+fn race_record_decision(repo: Arc<dyn StateRepository>) {
+    let score = Score {
+        id: "tx-2".to_string(),
+        score: 0.95,
+        is_fraud: true,
+    };
+    let applied: Vec<usize> = std::thread::scope(|s| {
+        let runs: Vec<_> = (0..8)
+            .map(|_| s.spawn(|| StateMachine::record_decision(Arc::new(standard_table()), repo.as_ref(), &score).unwrap().len()))
+            .collect();
+        runs.into_iter().map(|run| run.join().unwrap()).collect()
+    });
+    assert_eq!(applied.iter().filter(|n| **n == 3).count(), 1);
+    assert_eq!(applied.iter().sum::<usize>(), 3);
+    assert_eq!(repo.get_state("tx-2"), Some(StateId::FlaggedAsFraud));
+    assert_eq!(repo.history("tx-2").len(), 3);
+}
+
+#[test]
+fn test_in_memory_state_repo() {
+    let repo = InMemoryStateRepo::new();
+    flag_and_assign(&repo);
+    check_history(&repo);
+}
+
+#[test]
+fn test_in_memory_writers_race() {
+    let repo = Arc::new(InMemoryStateRepo::new());
+    flag_and_assign(repo.as_ref());
+    race_on_stored_state(repo.as_ref());
+    race_record_decision(repo);
+}
+
+#[test]
+fn test_sqlite_writers_race() {
+    let path = std::env::temp_dir().join(format!("fd3_state_race_{}.db", std::process::id()));
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+
+    let repo = Arc::new(SQLiteStateRepo::new(path));
+    flag_and_assign(repo.as_ref());
+    race_on_stored_state(repo.as_ref());
+    race_record_decision(repo);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_sqlite_state_repo_survives_restart() {
+    let path = std::env::temp_dir().join(format!("fd3_state_{}.db", std::process::id()));
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+
+    flag_and_assign(&SQLiteStateRepo::new(path));
+
+    // A new connection plays the restarted service
+    let repo = SQLiteStateRepo::new(path);
+    check_history(&repo);
+
+    let mut fsm = StateMachine::load(Arc::new(standard_table()), &repo, "tx-1").unwrap();
+    fsm.fire_and_record(Event::Clear, &TransitionContext::new("tx-1").by("analyst-7"), &repo).unwrap();
+    assert_eq!(repo.get_state("tx-1"), Some(StateId::Cleared));
+    assert_eq!(repo.history("tx-1").len(), 5);
+
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,137 @@
+// tests/telemetry.rs
+
+use fraud_detection_3::domain::fraud_scorer::RuleBasedScorer;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::in_memory::{InMemoryScoreRepo, InMemoryTransactionRepo};
+use fraud_detection_3::pipeline::Pipeline;
+use fraud_detection_3::pipeline::stages::{PersistenceStage, ScorePersistenceStage, ScoringStage, ValidationStage};
+use fraud_detection_3::state_machine::async_fsm::{FsmConfig, run_transaction};
+use fraud_detection_3::telemetry::transaction_span;
+#[cfg(not(feature = "bench"))]
+use fraud_detection_3::workers::dispatcher::{self, WorkerMessage};
+use fraud_detection_3::workers::scoring_pool::ScoringPool;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Instrument, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
+
+fn tx(id: &str) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount: 10.0,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+struct TxId(String);
+
+#[derive(Default)]
+struct SpanFields {
+    tx_id: Option<String>,
+    stage: Option<String>,
+}
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "stage" {
+            self.stage = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "tx_id" {
+            self.tx_id = Some(format!("{value:?}"));
+        }
+    }
+}
+
+/// A stage name and the id of the transaction span it is nested in
+type StageSpan = (String, Option<String>);
+
+/// Records every stage span
+#[derive(Clone, Default)]
+struct StageCapture(Arc<Mutex<Vec<StageSpan>>>);
+
+impl StageCapture {
+    fn stages_of(&self, tx_id: &str) -> Vec<String> {
+        let stages = self.0.lock().unwrap();
+        stages.iter().filter(|(_, id)| id.as_deref() == Some(tx_id)).map(|(stage, _)| stage.clone()).collect()
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for StageCapture {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+        let span = ctx.span(id).expect("span just created");
+        if let Some(tx_id) = fields.tx_id {
+            span.extensions_mut().insert(TxId(tx_id));
+        }
+        if let Some(stage) = fields.stage {
+            let tx_id = span.scope().find_map(|s| s.extensions().get::<TxId>().map(|t| t.0.clone()));
+            self.0.lock().unwrap().push((stage, tx_id));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_stages_nest_in_transaction_span() {
+    let capture = StageCapture::default();
+    let _guard = tracing_subscriber::registry().with(capture.clone()).set_default();
+
+    let (pipeline, output) = Pipeline::builder(8)
+        .stage(ValidationStage, 1)
+        .stage(PersistenceStage::new(Arc::new(InMemoryTransactionRepo::new()), Arc::new(InMemoryScoreRepo::new())), 1)
+        .stage(ScoringStage::new(Arc::new(RuleBasedScorer)), 2)
+        .stage(ScorePersistenceStage::new(Arc::new(InMemoryScoreRepo::new())), 1)
+        .build();
+    drop(output);
+    for id in ["tx-1", "tx-2"] {
+        let tx = tx(id);
+        let span = transaction_span(&tx);
+        pipeline.submit(tx).instrument(span).await.unwrap();
+    }
+    pipeline.shutdown().await;
+
+    for id in ["tx-1", "tx-2"] {
+        assert_eq!(capture.stages_of(id), ["validation", "persistence", "scoring", "score_persistence"], "{id}");
+    }
+}
+
+// The bench build has no dispatcher worker
+#[cfg(not(feature = "bench"))]
+#[tokio::test]
+async fn test_span_crosses_dispatcher_channel() {
+    let capture = StageCapture::default();
+    let _guard = tracing_subscriber::registry().with(capture.clone()).set_default();
+
+    let (sender, rx) = tokio::sync::mpsc::channel(8);
+    let worker = tokio::spawn(dispatcher::start_worker(rx, Arc::new(InMemoryTransactionRepo::new()), Arc::new(InMemoryScoreRepo::new())));
+    sender.send(WorkerMessage::traced(tx("tx-1"))).await.unwrap();
+    sender.send(WorkerMessage::Transaction(tx("tx-2"))).await.unwrap();
+    sender.send(WorkerMessage::Shutdown).await.unwrap();
+    worker.await.unwrap();
+
+    // An untraced transaction gets its span in the worker
+    for id in ["tx-1", "tx-2"] {
+        assert_eq!(capture.stages_of(id), ["persistence", "scoring", "score_persistence"], "{id}");
+    }
+}
+
+#[tokio::test]
+async fn test_state_machine_and_scoring_worker_spans() {
+    let capture = StageCapture::default();
+    let _guard = tracing_subscriber::registry().with(capture.clone()).set_default();
+
+    let (pool, _workers) = ScoringPool::spawn(Arc::new(RuleBasedScorer), 1, 4);
+    let tx = tx("tx-1");
+    let span = transaction_span(&tx);
+    run_transaction(tx, &pool, &FsmConfig::default()).instrument(span).await;
+
+    assert_eq!(capture.stages_of("tx-1"), ["state_machine", "scoring"]);
+}
@@ -0,0 +1,180 @@
+// tests/timers.rs
+
+use fraud_detection_3::domain::clock::now_millis;
+use fraud_detection_3::domain::repository::{StateRepository, TimerStore};
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::persistence::in_memory::{InMemoryStateRepo, InMemoryTimerStore};
+use fraud_detection_3::persistence::sqlite::{SQLiteStateRepo, SQLiteTimerStore};
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::timers::{TIMER_ACTOR, TimerWheel, default_timer_rules};
+use fraud_detection_3::state_machine::transitions::{StateId, TransitionContext, TransitionRecord, standard_table};
+use std::sync::{Arc, Mutex};
+
+const HOUR: i64 = 60 * 60 * 1000;
+
+fn wheel(states: Arc<dyn StateRepository>, timers: Arc<dyn TimerStore>) -> TimerWheel {
+    TimerWheel::new(Arc::new(standard_table()), states, timers, default_timer_rules())
+}
+
+fn flag(wheel: &TimerWheel, tx_id: &str) {
+    let ctx = TransitionContext::new(tx_id).with_score(Score {
+        id: tx_id.to_string(),
+        score: 0.95,
+        is_fraud: true,
+    });
+    for event in [Event::Process, Event::Scored, Event::Persist] {
+        wheel.fire(event, &ctx).unwrap();
+    }
+}
+
+#[test]
+fn test_flag_escalates_then_review_times_out() {
+    let states = Arc::new(InMemoryStateRepo::new());
+    let timers = Arc::new(InMemoryTimerStore::new());
+    let wheel = wheel(states.clone(), timers.clone());
+
+    flag(&wheel, "tx-1");
+    assert_eq!(timers.pending("tx-1").len(), 1);
+    assert_eq!(wheel.fire_due(now_millis()), 0);
+
+    // 1h later nobody picked the case up
+    assert_eq!(wheel.fire_due(now_millis() + HOUR + 1), 1);
+    assert_eq!(states.get_state("tx-1"), Some(StateId::UnderReview));
+    let pending = timers.pending("tx-1");
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].event, Event::ReviewTimeout);
+
+    // 24h more without analyst action
+    assert_eq!(wheel.fire_due(now_millis() + 25 * HOUR + 1), 1);
+    assert_eq!(states.get_state("tx-1"), Some(StateId::Cleared));
+    assert!(timers.pending("tx-1").is_empty());
+    assert_eq!(states.history("tx-1").last().unwrap().actor.as_deref(), Some(TIMER_ACTOR));
+}
+
+#[test]
+fn test_analyst_action_disarms_timer() {
+    let states = Arc::new(InMemoryStateRepo::new());
+    let timers = Arc::new(InMemoryTimerStore::new());
+    let wheel = wheel(states.clone(), timers.clone());
+
+    flag(&wheel, "tx-1");
+    wheel.fire(Event::AssignToAnalyst, &TransitionContext::new("tx-1").by("analyst-7")).unwrap();
+    wheel.fire(Event::ConfirmFraud, &TransitionContext::new("tx-1").by("analyst-7")).unwrap();
+
+    assert!(timers.pending("tx-1").is_empty());
+    assert_eq!(wheel.fire_due(now_millis() + 100 * HOUR), 0);
+    assert_eq!(states.get_state("tx-1"), Some(StateId::ConfirmedFraud));
+}
+
+#[test]
+fn test_stale_timer_is_discarded() {
+    let states = Arc::new(InMemoryStateRepo::new());
+    let timers = Arc::new(InMemoryTimerStore::new());
+    let wheel = wheel(states.clone(), timers.clone());
+
+    flag(&wheel, "tx-1");
+    // Crash between applying an event and removing its timer: the state moved on, the timer is still there
+    states.save_state("tx-1", StateId::Cleared);
+    assert_eq!(wheel.fire_due(now_millis() + 2 * HOUR), 0);
+    assert!(timers.pending("tx-1").is_empty());
+}
+
+#[test]
+fn test_sqlite_timers_survive_restart() {
+    let path = std::env::temp_dir().join(format!("fd3_timers_{}.db", std::process::id()));
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+
+    flag(&wheel(Arc::new(SQLiteStateRepo::new(path)), Arc::new(SQLiteTimerStore::new(path))), "tx-1");
+
+    // Restarted service, well past the escalation deadline
+    let states = Arc::new(SQLiteStateRepo::new(path));
+    let restarted = wheel(states.clone(), Arc::new(SQLiteTimerStore::new(path)));
+    assert_eq!(restarted.fire_due(now_millis() + 2 * HOUR), 1);
+    assert_eq!(states.get_state("tx-1"), Some(StateId::UnderReview));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_record_decision_arms_timers_once() {
+    let states = Arc::new(InMemoryStateRepo::new());
+    let timers = Arc::new(InMemoryTimerStore::new());
+    let wheel = wheel(states.clone(), timers.clone());
+    let score = Score {
+        id: "tx-1".to_string(),
+        score: 0.95,
+        is_fraud: true,
+    };
+
+    assert_eq!(wheel.record_decision(&score), Ok(StateId::FlaggedAsFraud));
+    let armed = timers.pending("tx-1");
+    assert_eq!(armed.len(), 1);
+
+    // A retry keeps the first decision and its timer
+    assert_eq!(wheel.record_decision(&score), Ok(StateId::FlaggedAsFraud));
+    assert_eq!(timers.pending("tx-1"), armed);
+    assert_eq!(states.history("tx-1").len(), 3);
+}
+
+/// Stores an analyst's review right before the next conditional write, as if the analyst clicked while the
+/// wheel was between loading the state and recording its timer event
+struct ReviewLandsFirst {
+    states: Arc<InMemoryStateRepo>,
+    timers: Arc<InMemoryTimerStore>,
+    review: Mutex<Option<Event>>,
+}
+
+impl StateRepository for ReviewLandsFirst {
+    fn save_state(&self, tx_id: &str, state: StateId) {
+        self.states.save_state(tx_id, state);
+    }
+
+    fn get_state(&self, tx_id: &str) -> Option<StateId> {
+        self.states.get_state(tx_id)
+    }
+
+    fn record_transition(&self, record: TransitionRecord) {
+        self.states.record_transition(record);
+    }
+
+    fn record_transitions_if(&self, expected: Option<StateId>, records: &[TransitionRecord]) -> bool {
+        if let Some(event) = self.review.lock().unwrap().take() {
+            let analyst = wheel(self.states.clone(), self.timers.clone());
+            analyst.fire(event, &TransitionContext::new(records[0].tx_id.clone()).by("analyst-7")).unwrap();
+        }
+        self.states.record_transitions_if(expected, records)
+    }
+
+    fn history(&self, tx_id: &str) -> Vec<TransitionRecord> {
+        self.states.history(tx_id)
+    }
+
+    fn ids_in_state(&self, state: StateId) -> Vec<String> {
+        self.states.ids_in_state(state)
+    }
+}
+
+#[test]
+fn test_review_beats_due_timer() {
+    let states = Arc::new(InMemoryStateRepo::new());
+    let timers = Arc::new(InMemoryTimerStore::new());
+    let racing = Arc::new(ReviewLandsFirst {
+        states: states.clone(),
+        timers: timers.clone(),
+        review: Mutex::new(None),
+    });
+    let wheel = wheel(racing.clone(), timers.clone());
+    flag(&wheel, "tx-1");
+    wheel.fire(Event::AssignToAnalyst, &TransitionContext::new("tx-1").by("analyst-7")).unwrap();
+
+    // The review timeout is due, the analyst confirms the fraud while the wheel fires it
+    *racing.review.lock().unwrap() = Some(Event::ConfirmFraud);
+    assert_eq!(wheel.fire_due(now_millis() + 25 * HOUR), 0);
+
+    assert_eq!(states.get_state("tx-1"), Some(StateId::ConfirmedFraud));
+    let history = states.history("tx-1");
+    assert_eq!(history.len(), 5);
+    assert_eq!(history[4].actor.as_deref(), Some("analyst-7"));
+    assert!(timers.pending("tx-1").is_empty());
+}
@@ -0,0 +1,175 @@
+// tests/transition_table.rs
+
+use fraud_detection_3::domain::fraud_scorer::RuleBasedScorer;
+use fraud_detection_3::domain::scoring::Score;
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::state_machine::event::Event;
+use fraud_detection_3::state_machine::state::{Enriched, State, Validated, boxed};
+use fraud_detection_3::state_machine::transitions::{StateId, StateMachine, TableIssue, TransitionContext, TransitionError, TransitionTable, standard_table};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn scored(is_fraud: bool) -> TransitionContext {
+    TransitionContext::new("tx-001").with_score(Score {
+        id: "tx-001".to_string(),
+        score: if is_fraud { 0.9 } else { 0.1 },
+        is_fraud,
+    })
+}
+
+#[test]
+fn test_standard_lifecycle() {
+    let table = Arc::new(standard_table());
+    assert_eq!(table.validate(), Ok(()));
+
+    let mut fsm = StateMachine::new(table.clone());
+    assert_eq!(fsm.fire(Event::Process, &TransitionContext::default()), Ok(StateId::Enriched));
+    assert_eq!(fsm.fire(Event::Scored, &scored(true)), Ok(StateId::Scored));
+    assert_eq!(fsm.fire(Event::Persist, &scored(true)), Ok(StateId::FlaggedAsFraud));
+    assert!(!fsm.is_finished()); // a flag opens a review case
+
+    let mut fsm = StateMachine::at(table, StateId::Scored);
+    assert_eq!(fsm.fire(Event::Persist, &scored(false)), Ok(StateId::Persisted));
+}
+
+#[test]
+fn test_review_workflow_records_analyst_and_notes() {
+    let mut fsm = StateMachine::at(Arc::new(standard_table()), StateId::FlaggedAsFraud);
+    let ctx = TransitionContext::new("tx-001");
+
+    // Assigning needs an analyst
+    assert!(matches!(fsm.fire(Event::AssignToAnalyst, &ctx), Err(TransitionError::GuardRejected { .. })));
+
+    fsm.fire(Event::AssignToAnalyst, &ctx.clone().by("analyst-7").with_notes("card testing pattern")).unwrap();
+    fsm.fire(Event::Escalate, &ctx.clone().by("analyst-7").with_notes("needs a senior")).unwrap();
+    assert_eq!(fsm.current(), StateId::UnderReview);
+    assert_eq!(fsm.fire(Event::ConfirmFraud, &ctx.clone().by("senior-1")), Ok(StateId::ConfirmedFraud));
+    assert!(fsm.is_finished());
+
+    let history = fsm.history();
+    assert_eq!(history.len(), 3);
+    assert_eq!((history[0].from, history[0].to), (StateId::FlaggedAsFraud, StateId::UnderReview));
+    assert_eq!(history[0].actor.as_deref(), Some("analyst-7"));
+    assert_eq!(history[1].notes.as_deref(), Some("needs a senior"));
+    assert_eq!(history[2].event, Event::ConfirmFraud);
+
+    let mut timed_out = StateMachine::at(Arc::new(standard_table()), StateId::UnderReview);
+    assert_eq!(timed_out.fire(Event::ReviewTimeout, &ctx), Ok(StateId::Cleared));
+}
+
+#[test]
+fn test_illegal_transitions_are_rejected() {
+    let mut fsm = StateMachine::new(Arc::new(standard_table()));
+
+    let err = fsm.fire(Event::Scored, &scored(true)).unwrap_err();
+    assert_eq!(
+        err,
+        TransitionError::Illegal {
+            from: StateId::Validated,
+            event: Event::Scored
+        }
+    );
+    assert_eq!(fsm.current(), StateId::Validated);
+
+    // Persist without a score: both guards fail
+    fsm.fire(Event::Process, &TransitionContext::default()).unwrap();
+    fsm.fire(Event::Scored, &TransitionContext::default()).unwrap();
+    assert!(matches!(fsm.fire(Event::Persist, &TransitionContext::default()), Err(TransitionError::GuardRejected { .. })));
+    assert_eq!(fsm.current(), StateId::Scored);
+}
+
+#[test]
+fn test_actions_run_on_transition() {
+    let flagged = Arc::new(AtomicUsize::new(0));
+    let counter = flagged.clone();
+    let table = standard_table().on(StateId::Scored, StateId::FlaggedAsFraud, move |_, _, _| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    table.fire(StateId::Scored, Event::Persist, &scored(false)).unwrap();
+    table.fire(StateId::Scored, Event::Persist, &scored(true)).unwrap();
+    assert_eq!(flagged.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_action_declared_before_its_rule() {
+    let flagged = Arc::new(AtomicUsize::new(0));
+    let counter = flagged.clone();
+    let table = TransitionTable::new(StateId::Validated)
+        .on(StateId::Validated, StateId::Enriched, move |_, _, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .rule(StateId::Validated, Event::Process, StateId::Enriched);
+
+    table.fire(StateId::Validated, Event::Process, &TransitionContext::default()).unwrap();
+    assert_eq!(flagged.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_last_action_registered_wins() {
+    let first = Arc::new(AtomicUsize::new(0));
+    let second = Arc::new(AtomicUsize::new(0));
+    let (a, b) = (first.clone(), second.clone());
+    let table = TransitionTable::new(StateId::Validated)
+        .rule(StateId::Validated, Event::Process, StateId::Enriched)
+        .on(StateId::Validated, StateId::Enriched, move |_, _, _| {
+            a.fetch_add(1, Ordering::SeqCst);
+        })
+        .on(StateId::Validated, StateId::Enriched, move |_, _, _| {
+            b.fetch_add(1, Ordering::SeqCst);
+        });
+
+    table.fire(StateId::Validated, Event::Process, &TransitionContext::default()).unwrap();
+    assert_eq!((first.load(Ordering::SeqCst), second.load(Ordering::SeqCst)), (0, 1));
+}
+
+#[test]
+fn test_validation_finds_unreachable_and_dead_end_states() {
+    let table = TransitionTable::new(StateId::Validated)
+        .rule(StateId::Validated, Event::Process, StateId::Enriched)
+        .terminal(StateId::Persisted);
+
+    let issues = table.validate().unwrap_err();
+    assert!(issues.contains(&TableIssue::DeadEnd(StateId::Enriched)));
+    assert!(issues.contains(&TableIssue::Unreachable(StateId::Persisted)));
+    assert!(issues.contains(&TableIssue::Unreachable(StateId::FlaggedAsFraud)));
+    assert!(issues.contains(&TableIssue::DeadEnd(StateId::Scored)));
+}
+
+#[test]
+fn test_diagram_export() {
+    let table = standard_table();
+
+    let mermaid = table.to_mermaid();
+    assert!(mermaid.starts_with("stateDiagram-v2"));
+    assert!(mermaid.contains("Scored --> FlaggedAsFraud: Persist [is_fraud]"));
+
+    let dot = table.to_dot();
+    assert!(dot.contains("Validated -> Enriched [label=\"Process\"];"));
+    assert!(dot.contains("Persisted [shape=doublecircle];"));
+}
+
+#[test]
+fn test_legacy_state_objects_follow_the_table() {
+    let state: Box<dyn State> = Box::new(Validated);
+    let state = state.handle(Event::Process);
+    assert_eq!(state.id(), StateId::Enriched);
+
+    let enriched = state.as_any().downcast::<Enriched>().unwrap();
+    let big = Transaction {
+        id: "tx-1".to_string(),
+        amount: 5000.0,
+        currency: "USD".to_string(),
+        ..Default::default()
+    };
+    let state = enriched.handle_with_scorer(&big, &RuleBasedScorer);
+    assert_eq!(state.name(), "FlaggedAsFraud");
+
+    // Events the old impls swallowed now follow the table, illegal ones still leave the state as is
+    let state = state.handle(Event::Escalate);
+    assert_eq!(state.id(), StateId::UnderReview);
+    let state = state.handle(Event::Process);
+    assert_eq!(state.id(), StateId::UnderReview);
+    // Without a score the guards of `Persist` reject it
+    assert_eq!(boxed(StateId::Scored).handle(Event::Persist).id(), StateId::Scored);
+}
@@ -0,0 +1,123 @@
+// tests/typestate.rs
+
+use fraud_detection_3::domain::fraud_scorer::{FraudScorer, RuleBasedScorer};
+use fraud_detection_3::domain::repository::{ScoreRepository, TransRepository};
+use fraud_detection_3::domain::transaction::Transaction;
+use fraud_detection_3::persistence::in_memory::InMemoryTransactionRepo;
+use fraud_detection_3::persistence::sqlite::SQLiteScoreRepo;
+use fraud_detection_3::state_machine::transitions::StateId;
+use fraud_detection_3::state_machine::typestate::{AnyTxFlow, Decided, FlowRecord, TxFlow};
+
+fn tx(id: &str, amount: f64) -> Transaction {
+    Transaction {
+        id: id.to_string(),
+        amount,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+struct NeverFraud;
+
+impl FraudScorer for NeverFraud {
+    fn is_fraud(&self, _tx: &Transaction) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_full_lifecycle() {
+    let tx_repo = InMemoryTransactionRepo::new();
+    let score_repo = SQLiteScoreRepo::new(":memory:");
+
+    let scored = TxFlow::validate(tx("tx-1", 5000.0)).unwrap().enrich().score(&RuleBasedScorer);
+    assert!(scored.state().score.is_fraud);
+
+    match scored.persist(&tx_repo, &score_repo).unwrap() {
+        Decided::FlaggedAsFraud(flow) => assert_eq!(flow.transaction().id, "tx-1"),
+        Decided::Persisted(_) => panic!("expected a fraud flag"),
+    }
+    assert!(tx_repo.get("tx-1").is_some());
+    assert!(score_repo.get("tx-1").unwrap().is_fraud);
+}
+
+#[test]
+fn test_retry_keeps_the_first_score() {
+    let tx_repo = InMemoryTransactionRepo::new();
+    let score_repo = SQLiteScoreRepo::new(":memory:");
+
+    let first = TxFlow::validate(tx("tx-1", 5000.0)).unwrap().enrich().score(&RuleBasedScorer);
+    let original = first.state().score.clone();
+    assert!(matches!(first.persist(&tx_repo, &score_repo).unwrap(), Decided::FlaggedAsFraud(_)));
+
+    // The retry is scored clean this time
+    let retry = TxFlow::validate(tx("tx-1", 5000.0)).unwrap().enrich().score(&NeverFraud);
+    match retry.persist(&tx_repo, &score_repo).unwrap() {
+        Decided::FlaggedAsFraud(flow) => assert_eq!(flow.state().score, original),
+        Decided::Persisted(_) => panic!("the retry must keep the first decision"),
+    }
+    assert_eq!(score_repo.get("tx-1"), Some(original));
+}
+
+#[test]
+fn test_invalid_transaction_never_enters_the_flow() {
+    assert!(TxFlow::validate(tx("tx-1", -1.0)).is_err());
+}
+
+#[test]
+fn test_dynamic_form_round_trip() {
+    let enriched = TxFlow::validate(tx("tx-1", 10.0)).unwrap().enrich();
+    let scored = enriched.clone().score(&RuleBasedScorer);
+
+    for flow in [AnyTxFlow::from(enriched), AnyTxFlow::from(scored.clone()), AnyTxFlow::from(scored.decide())] {
+        let record = flow.clone().into_record();
+        assert_eq!(record.state, flow.state_id());
+        assert_eq!(AnyTxFlow::try_from(record), Ok(flow));
+    }
+}
+
+#[test]
+fn test_review_workflow() {
+    let flagged = match TxFlow::validate(tx("tx-1", 5000.0)).unwrap().enrich().score(&RuleBasedScorer).decide() {
+        Decided::FlaggedAsFraud(flow) => flow,
+        Decided::Persisted(_) => panic!("expected a fraud flag"),
+    };
+
+    let review = flagged.assign_to("analyst-7", Some("looks like card testing".to_string()));
+    assert_eq!(review.state().analyst_id.as_deref(), Some("analyst-7"));
+
+    let escalated = review.escalate(Some("needs a senior".to_string()));
+    assert_eq!(escalated.state().analyst_id, None);
+
+    let confirmed = escalated.assign_to("senior-1", None).confirm_fraud("senior-1", Some("customer confirmed".to_string()));
+    let record = AnyTxFlow::from(confirmed.clone()).into_record();
+    assert_eq!(record.state, StateId::ConfirmedFraud);
+    assert_eq!(record.analyst_id.as_deref(), Some("senior-1"));
+    assert_eq!(AnyTxFlow::try_from(record), Ok(AnyTxFlow::ConfirmedFraud(confirmed)));
+}
+
+#[test]
+fn test_settled_flow_reopens_on_feedback() {
+    let persisted = match TxFlow::validate(tx("tx-1", 10.0)).unwrap().enrich().score(&RuleBasedScorer).decide() {
+        Decided::Persisted(flow) => flow,
+        Decided::FlaggedAsFraud(_) => panic!("expected a clean decision"),
+    };
+    let original = persisted.state().score.clone();
+
+    let charged_back = persisted.dispute(Some("customer does not recognize it".to_string())).chargeback(None);
+    assert_eq!(charged_back.state().score, original);
+
+    let record = AnyTxFlow::from(charged_back.clone()).into_record();
+    assert_eq!(record.state, StateId::ChargedBack);
+    assert_eq!(AnyTxFlow::try_from(record), Ok(AnyTxFlow::ChargedBack(charged_back)));
+}
+
+#[test]
+fn test_inconsistent_record_is_rejected() {
+    let record = FlowRecord {
+        tx: tx("tx-1", 10.0),
+        state: StateId::Persisted,
+        ..Default::default()
+    };
+    assert_eq!(AnyTxFlow::try_from(record).unwrap_err().missing, "score");
+}